@@ -0,0 +1,223 @@
+use app_lib::ClipboardFormats;
+use clipboard_rs::common::ClipboardContent as RsContent;
+use clipboard_rs::{Clipboard, ClipboardContext};
+use regex::Regex;
+use std::sync::OnceLock;
+use url::Url;
+
+// Word and browsers can put megabytes of markup on the clipboard; history stays lean
+const MAX_FORMAT_BYTES: usize = 1024 * 1024;
+
+// Name of the SVG clipboard format on each platform
+#[cfg(target_os = "macos")]
+const SVG_FORMAT: &str = "public.svg-image";
+#[cfg(not(target_os = "macos"))]
+const SVG_FORMAT: &str = "image/svg+xml";
+
+fn fragment_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"(?is)<!--\s*StartFragment\s*-->(.*?)<!--\s*EndFragment\s*-->").unwrap())
+}
+
+// ammonia's default allowlist of formatting tags, link schemes and attributes;
+// inline styles are kept because that is where Word and browsers put the formatting
+fn html_sanitizer() -> &'static ammonia::Builder<'static> {
+    static BUILDER: OnceLock<ammonia::Builder<'static>> = OnceLock::new();
+    BUILDER.get_or_init(|| {
+        let mut builder = ammonia::Builder::default();
+        builder
+            .add_generic_attributes(["style"])
+            // Dropped along with their content rather than unwrapped into text
+            .clean_content_tags(["script", "style", "iframe", "object", "noscript", "template"].into());
+        builder
+    })
+}
+
+// Shapes, text and paint servers; no scripts, foreign objects, animation or <use>
+fn svg_sanitizer() -> &'static ammonia::Builder<'static> {
+    static BUILDER: OnceLock<ammonia::Builder<'static>> = OnceLock::new();
+    BUILDER.get_or_init(|| {
+        let mut builder = ammonia::Builder::empty();
+        builder
+            .add_tags([
+                "svg", "g", "defs", "title", "desc", "path", "rect", "circle", "ellipse", "line",
+                "polyline", "polygon", "text", "tspan", "linearGradient", "radialGradient", "stop",
+                "clipPath", "mask", "pattern", "symbol",
+            ])
+            .add_generic_attributes([
+                "xmlns", "id", "viewBox", "preserveAspectRatio", "width", "height", "x", "y", "x1", "y1",
+                "x2", "y2", "cx", "cy", "r", "rx", "ry", "d", "points", "transform", "fill",
+                "fill-opacity", "fill-rule", "stroke", "stroke-width", "stroke-opacity", "stroke-linecap",
+                "stroke-linejoin", "stroke-dasharray", "opacity", "clip-path", "mask", "offset",
+                "stop-color", "stop-opacity", "gradientUnits", "gradientTransform", "patternUnits",
+                "font-family", "font-size", "font-weight", "text-anchor", "dx", "dy", "style",
+            ]);
+        builder
+    })
+}
+
+/// Reduces a captured fragment to allowlisted tags, attributes and URL schemes so
+/// it is safe to render and to paste elsewhere.
+pub fn sanitize_html(html: &str) -> String {
+    // Windows wraps the copied part in fragment markers inside a full document
+    let html = fragment_regex().captures(html)
+        .and_then(|c| c.get(1))
+        .map(|m| m.as_str())
+        .unwrap_or(html);
+    html_sanitizer().clean(html).to_string().trim().to_string()
+}
+
+/// Like `sanitize_html`, with an allowlist of SVG drawing elements instead.
+pub fn sanitize_svg(svg: &str) -> String {
+    svg_sanitizer().clean(svg).to_string().trim().to_string()
+}
+
+fn keep(value: String) -> Option<String> {
+    (!value.trim().is_empty() && value.len() <= MAX_FORMAT_BYTES).then_some(value)
+}
+
+fn file_uri(entry: &str) -> Option<String> {
+    let entry = entry.trim();
+    if entry.is_empty() || entry.starts_with('#') {
+        return None;
+    }
+    if entry.starts_with("file://") {
+        return Some(entry.to_string());
+    }
+    Url::from_file_path(entry).ok().map(|u| u.to_string())
+}
+
+fn file_path(uri: &str) -> String {
+    Url::parse(uri).ok()
+        .and_then(|u| u.to_file_path().ok())
+        .map(|p| p.to_string_lossy().to_string())
+        .unwrap_or_else(|| uri.to_string())
+}
+
+/// Paths of a file list, one per line; the plain text of a file capture.
+pub fn files_as_text(files: &[String]) -> String {
+    files.iter().map(|f| file_path(f)).collect::<Vec<_>>().join("\n")
+}
+
+fn files(ctx: &ClipboardContext) -> Vec<String> {
+    ctx.get_files().unwrap_or_default().iter().filter_map(|f| file_uri(f)).collect()
+}
+
+/// Just the copied files, as `file://` URIs; much cheaper than `read`.
+pub fn read_files() -> Vec<String> {
+    ClipboardContext::new().map(|ctx| files(&ctx)).unwrap_or_default()
+}
+
+/// Reads whatever rich formats the clipboard currently holds. Missing formats are
+/// normal, so failures just leave the field empty.
+pub fn read() -> ClipboardFormats {
+    let ctx = match ClipboardContext::new() {
+        Ok(ctx) => ctx,
+        Err(_) => return ClipboardFormats::default(),
+    };
+    let svg = ctx.get_buffer(SVG_FORMAT).ok()
+        .and_then(|bytes| String::from_utf8(bytes).ok())
+        .filter(|svg| svg.contains("<svg"))
+        .map(|svg| sanitize_svg(&svg))
+        .and_then(keep);
+    ClipboardFormats {
+        html: ctx.get_html().ok().map(|h| sanitize_html(&h)).and_then(keep),
+        rtf: ctx.get_rich_text().ok().and_then(keep),
+        files: files(&ctx),
+        svg,
+    }
+}
+
+/// Whether the clipboard owner asked for its content to stay out of clipboard
+/// history. Password managers do this with marker formats: the KDE hint on Linux,
+/// the monitor-exclusion formats on Windows, the nspasteboard.org types on macOS.
+pub fn marked_secret() -> bool {
+    let ctx = match ClipboardContext::new() {
+        Ok(ctx) => ctx,
+        Err(_) => return false,
+    };
+    let offered = match ctx.available_formats() {
+        Ok(formats) => formats,
+        Err(_) => return false,
+    };
+    offered.iter().any(|format| match format.as_str() {
+        // Defined value is "secret"; assume it when it cannot be read
+        "x-kde-passwordManagerHint" => ctx.get_buffer(format)
+            .map_or(true, |v| String::from_utf8_lossy(&v).trim() == "secret"),
+        "ExcludeClipboardContentFromMonitorProcessing" | "Clipboard Viewer Ignore" => true,
+        // A DWORD; zero keeps the content out of history
+        "CanIncludeInClipboardHistory" => ctx.get_buffer(format)
+            .is_ok_and(|v| v.len() >= 4 && v[..4] == [0, 0, 0, 0]),
+        "org.nspasteboard.ConcealedType" | "org.nspasteboard.TransientType" => true,
+        _ => false,
+    })
+}
+
+/// Puts `text` and every stored representation on the clipboard in one go.
+pub fn write(text: &str, formats: &ClipboardFormats) -> Result<(), String> {
+    let ctx = ClipboardContext::new()
+        .map_err(|e| format!("Failed to open clipboard: {}", e))?;
+    let mut contents = vec![RsContent::Text(text.to_string())];
+    if let Some(html) = &formats.html {
+        contents.push(RsContent::Html(html.clone()));
+    }
+    if let Some(rtf) = &formats.rtf {
+        contents.push(RsContent::Rtf(rtf.clone()));
+    }
+    if !formats.files.is_empty() {
+        // X11 takes the URIs as they are; the other platforms want plain paths
+        let files = if cfg!(target_os = "linux") {
+            formats.files.clone()
+        } else {
+            formats.files.iter().map(|f| file_path(f)).collect()
+        };
+        contents.push(RsContent::Files(files));
+    }
+    if let Some(svg) = &formats.svg {
+        contents.push(RsContent::Other(SVG_FORMAT.to_string(), svg.clone().into_bytes()));
+    }
+    ctx.set(contents)
+        .map_err(|e| format!("Failed to write clipboard: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formatting_and_safe_links_survive() {
+        let html = "<html><body><!--StartFragment--><p style=\"color: red\"><b>Bold</b> <a href=\"https://example.com/\">link</a></p><!--EndFragment--></body></html>";
+        assert_eq!(
+            sanitize_html(html),
+            "<p style=\"color: red\"><b>Bold</b> <a href=\"https://example.com/\" rel=\"noopener noreferrer\">link</a></p>"
+        );
+    }
+
+    #[test]
+    fn entity_encoded_script_urls_are_dropped() {
+        let clean = sanitize_html("<a href=\"jav&#x61;script:alert(1)\">one</a><a href=\"&#106;avascript&colon;alert(2)\">two</a>");
+        assert_eq!(clean, "<a rel=\"noopener noreferrer\">one</a><a rel=\"noopener noreferrer\">two</a>");
+    }
+
+    #[test]
+    fn quoted_angle_brackets_do_not_end_the_tag() {
+        let clean = sanitize_html("<img title=\"a>b\" src=\"https://example.com/x.png\" onerror=\"alert(1)\"><p title='x>' onclick=alert(2)>text</p>");
+        assert_eq!(clean, "<img title=\"a>b\" src=\"https://example.com/x.png\"><p title=\"x>\">text</p>");
+    }
+
+    #[test]
+    fn unquoted_srcdoc_and_formaction_are_dropped() {
+        let clean = sanitize_html("<iframe srcdoc=<script>alert(1)</script>></iframe><form><button formaction=javascript:alert(2)>Go</button></form><div srcdoc=x formaction=y>ok</div>");
+        assert_eq!(clean, "Go<div>ok</div>");
+    }
+
+    #[test]
+    fn svg_keeps_drawing_and_loses_scripts() {
+        let svg = "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"0 0 10 10\" onload=\"alert(1)\"><script>alert(2)</script><path d=\"M0 0L10 10\" stroke=\"red\"/><foreignObject><iframe src=\"https://example.com\"></iframe></foreignObject><a href=\"javascript:alert(3)\"><text x=\"1\" y=\"9\">Hi</text></a></svg>";
+        let clean = sanitize_svg(svg);
+        assert!(!clean.contains("alert") && !clean.contains("iframe") && !clean.contains("javascript"), "{}", clean);
+        assert!(clean.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"0 0 10 10\">"), "{}", clean);
+        assert!(clean.contains("<path d=\"M0 0L10 10\" stroke=\"red\">"), "{}", clean);
+        assert!(clean.contains("<text x=\"1\" y=\"9\">Hi</text>"), "{}", clean);
+    }
+}
@@ -0,0 +1,161 @@
+use app_lib::{ClipboardHistoryEntry, ClipboardImageRef};
+use png::{BitDepth, ColorType, Decoder, Encoder, Transformations};
+use std::collections::HashSet;
+use std::ffi::OsString;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use tauri_plugin_clipboard_manager::ClipboardExt;
+
+use super::source::ClipboardImage;
+use crate::get_app_data_dir;
+
+// Longest side of the thumbnails shown in the history list
+const THUMBNAIL_SIZE: u32 = 256;
+
+fn images_dir() -> Result<PathBuf, String> {
+    let dir = get_app_data_dir()?.join("clipboard_images");
+    fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create clipboard images directory: {}", e))?;
+    Ok(dir)
+}
+
+pub fn encode_png(image: &ClipboardImage) -> Result<Vec<u8>, String> {
+    let mut png_data: Vec<u8> = Vec::new();
+    {
+        let mut encoder = Encoder::new(&mut png_data, image.width, image.height);
+        encoder.set_color(ColorType::Rgba);
+        encoder.set_depth(BitDepth::Eight);
+        let mut writer = encoder.write_header()
+            .map_err(|e| format!("Failed to write PNG header: {}", e))?;
+        writer.write_image_data(&image.rgba)
+            .map_err(|e| format!("Failed to write PNG data: {}", e))?;
+    }
+    Ok(png_data)
+}
+
+/// Reads a stored PNG back into RGBA pixels.
+pub fn decode_png(path: &Path) -> Result<ClipboardImage, String> {
+    let file = File::open(path)
+        .map_err(|e| format!("Failed to open image: {}", e))?;
+    let mut decoder = Decoder::new(file);
+    decoder.set_transformations(Transformations::EXPAND | Transformations::STRIP_16);
+    let mut reader = decoder.read_info()
+        .map_err(|e| format!("Failed to read image: {}", e))?;
+    let mut buf = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buf)
+        .map_err(|e| format!("Failed to decode image: {}", e))?;
+    buf.truncate(info.buffer_size());
+    let rgba = match info.color_type {
+        ColorType::Rgba => buf,
+        ColorType::Rgb => buf.chunks(3).flat_map(|p| [p[0], p[1], p[2], 255]).collect(),
+        ColorType::GrayscaleAlpha => buf.chunks(2).flat_map(|p| [p[0], p[0], p[0], p[1]]).collect(),
+        ColorType::Grayscale => buf.iter().flat_map(|&g| [g, g, g, 255]).collect(),
+        ColorType::Indexed => return Err("Unsupported PNG color type".to_string()),
+    };
+    Ok(ClipboardImage { width: info.width, height: info.height, rgba })
+}
+
+/// Box-filtered copy that fits in `THUMBNAIL_SIZE`; small images are kept as they are.
+fn thumbnail(image: &ClipboardImage) -> ClipboardImage {
+    let longest = image.width.max(image.height);
+    if longest <= THUMBNAIL_SIZE {
+        return image.clone();
+    }
+    let scale = |v: u32| ((v as u64 * THUMBNAIL_SIZE as u64 / longest as u64) as u32).max(1);
+    let (width, height) = (scale(image.width), scale(image.height));
+    let mut rgba = Vec::with_capacity((width * height * 4) as usize);
+    for y in 0..height {
+        let y0 = y * image.height / height;
+        let y1 = ((y + 1) * image.height / height).max(y0 + 1);
+        for x in 0..width {
+            let x0 = x * image.width / width;
+            let x1 = ((x + 1) * image.width / width).max(x0 + 1);
+            let mut sum = [0u64; 4];
+            for sy in y0..y1 {
+                let row = (sy * image.width) as usize * 4;
+                for sx in x0..x1 {
+                    let i = row + sx as usize * 4;
+                    for (c, s) in sum.iter_mut().enumerate() {
+                        *s += image.rgba[i + c] as u64;
+                    }
+                }
+            }
+            let count = ((y1 - y0) * (x1 - x0)) as u64;
+            rgba.extend(sum.iter().map(|s| (s / count) as u8));
+        }
+    }
+    ClipboardImage { width, height, rgba }
+}
+
+/// Writes the PNG and its thumbnail under the image hash. The same image copied
+/// again reuses the files already there.
+pub fn store(image: &ClipboardImage, png: &[u8], hash: &str) -> Result<ClipboardImageRef, String> {
+    let dir = images_dir()?;
+    let path = dir.join(format!("{}.png", hash));
+    let thumb_path = dir.join(format!("{}_thumb.png", hash));
+    if !path.exists() {
+        fs::write(&path, png)
+            .map_err(|e| format!("Failed to save clipboard image: {}", e))?;
+    }
+    if !thumb_path.exists() {
+        fs::write(&thumb_path, encode_png(&thumbnail(image))?)
+            .map_err(|e| format!("Failed to save clipboard thumbnail: {}", e))?;
+    }
+    Ok(ClipboardImageRef {
+        path: path.to_string_lossy().to_string(),
+        thumbnail: thumb_path.to_string_lossy().to_string(),
+        width: image.width,
+        height: image.height,
+    })
+}
+
+/// Stored paths are absolute; if the app data directory moved, look for the file
+/// under the current one.
+pub fn resolve(path: &str) -> PathBuf {
+    let stored = PathBuf::from(path);
+    if stored.exists() {
+        return stored;
+    }
+    match (images_dir(), stored.file_name()) {
+        (Ok(dir), Some(name)) => dir.join(name),
+        _ => stored,
+    }
+}
+
+/// Puts a stored image back on the clipboard. Goes through the plugin, whose
+/// clipboard stays alive to serve the image after this returns.
+pub fn write(app_handle: &tauri::AppHandle, image: &ClipboardImageRef) -> Result<(), String> {
+    let decoded = decode_png(&resolve(&image.path))?;
+    let image = tauri::image::Image::new_owned(decoded.rgba, decoded.width, decoded.height);
+    app_handle.clipboard().write_image(&image)
+        .map_err(|e| format!("Failed to write clipboard image: {}", e))
+}
+
+/// Removes the entries `keep` rejects. Returns the image files only those entries
+/// used; delete them with `remove_files` once the history lock is released.
+pub fn retain_entries(history: &mut Vec<ClipboardHistoryEntry>, keep: impl FnMut(&ClipboardHistoryEntry) -> bool) -> Vec<String> {
+    let (kept, removed): (Vec<_>, Vec<_>) = std::mem::take(history).into_iter().partition(keep);
+    *history = kept;
+    if removed.iter().all(|e| e.image.is_none()) {
+        return Vec::new();
+    }
+    // The same image copied twice shares its files; match by name as stored paths may be stale
+    let referenced: HashSet<OsString> = history.iter()
+        .filter_map(|e| e.image.as_ref())
+        .flat_map(|i| [Path::new(&i.path).file_name(), Path::new(&i.thumbnail).file_name()])
+        .flatten()
+        .map(|n| n.to_os_string())
+        .collect();
+    removed.iter()
+        .filter_map(|e| e.image.as_ref())
+        .flat_map(|i| [i.path.clone(), i.thumbnail.clone()])
+        .filter(|p| Path::new(p).file_name().is_some_and(|n| !referenced.contains(n)))
+        .collect()
+}
+
+/// Deletes image files returned by `retain_entries`.
+pub fn remove_files(paths: Vec<String>) {
+    for path in paths {
+        let _ = fs::remove_file(resolve(&path));
+    }
+}
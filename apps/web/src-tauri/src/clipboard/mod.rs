@@ -0,0 +1,57 @@
+mod formats;
+mod images;
+mod monitor;
+mod retention;
+mod secrets;
+mod source;
+#[cfg(target_os = "linux")]
+mod wayland;
+#[cfg(target_os = "linux")]
+mod x11;
+
+pub use formats::write as write_formats;
+pub use images::{remove_files as remove_images, resolve as resolve_image, retain_entries as retain_history, write as write_image};
+pub use monitor::{new_clip_id, ClipboardMonitor, MonitorStatus};
+pub use retention::{measure as measure_entry, spawn_sweeper, sweep as sweep_history};
+
+use std::sync::mpsc::Receiver;
+#[cfg(target_os = "linux")]
+use std::sync::mpsc::{self, Sender};
+#[cfg(target_os = "linux")]
+use std::thread;
+
+pub const DEFAULT_POLL_INTERVAL_MS: u64 = 500;
+pub const MIN_POLL_INTERVAL_MS: u64 = 100;
+pub const MAX_POLL_INTERVAL_MS: u64 = 10_000;
+
+/// Starts a native clipboard change listener where the platform has one: the
+/// data-control protocol on Wayland, XFixes selection events on X11. Every change
+/// sends `()` on the returned channel, which disconnects if the listener dies.
+/// Returns `None` when the caller has to fall back to polling.
+#[cfg(target_os = "linux")]
+pub fn watch_changes() -> Option<(&'static str, Receiver<()>)> {
+    fn spawn(run: impl FnOnce(Sender<()>) + Send + 'static) -> Receiver<()> {
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || run(tx));
+        rx
+    }
+
+    if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+        match wayland::WaylandWatcher::connect() {
+            Ok(watcher) => return Some(("wayland", spawn(move |tx| watcher.run(tx)))),
+            Err(e) => println!("Wayland clipboard events unavailable: {}", e),
+        }
+    }
+    if std::env::var_os("DISPLAY").is_some() {
+        match x11::X11Watcher::connect() {
+            Ok(watcher) => return Some(("x11", spawn(move |tx| watcher.run(tx)))),
+            Err(e) => println!("X11 clipboard events unavailable: {}", e),
+        }
+    }
+    None
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn watch_changes() -> Option<(&'static str, Receiver<()>)> {
+    None
+}
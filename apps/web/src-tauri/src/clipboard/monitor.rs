@@ -0,0 +1,757 @@
+use app_lib::{AppState, ClipboardContent, ClipboardFormats, ClipboardHistoryEntry, OcrJob, SecretAction};
+use base64::Engine as _;
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{Receiver, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::{Emitter, Manager};
+
+use super::formats::files_as_text;
+use super::images;
+use super::retention;
+use super::secrets;
+use super::source::{ArboardSource, ClipboardImage, ClipboardSource, PluginSource};
+use super::watch_changes;
+use crate::rules::{self, Merge, RuleInput, RuleOutcome};
+use crate::{
+    append_to_note, auto_tags_for_text_and_url, compute_text_hash, detect_capture_type, enforce_history_order_and_limit,
+    is_probable_screenshot, is_snipping_window_title, is_url, save_clipboard_history_to_disk, ClipboardImagePayload,
+};
+
+// How long a blocked wait may take to notice `stop`
+const STOP_CHECK_INTERVAL: Duration = Duration::from_millis(250);
+// Images copied this soon after a snipping tool was focused count as screenshots
+const SNIP_WINDOW: Duration = Duration::from_secs(6);
+
+#[derive(Serialize, Clone, Default)]
+pub struct MonitorStatus {
+    pub running: bool,
+    /// "x11", "wayland" or "polling"
+    pub mode: Option<String>,
+    /// Source the last text was read from
+    pub source: Option<String>,
+    pub captured: u64,
+    pub last_capture_at: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+}
+
+/// Something the pipeline picked up that the app should hear about.
+pub enum Capture {
+    /// New clipboard text; `recorded` is false when it was too short or ignored by a rule
+    Text { text: String, outcome: TextOutcome },
+    /// New clipboard image, stored as a history entry and sent to OCR unless a rule
+    /// ignored it
+    Image { recorded: bool, png: Vec<u8>, hash: String },
+    /// An image that looks like a screenshot; offered to the user for the open note
+    Screenshot { png: Vec<u8>, width: u32, height: u32 },
+}
+
+/// Turns clipboard reads into history entries. Holds only what it needs to tell
+/// a change from a re-read, so it can be driven by any `ClipboardSource`.
+pub struct Pipeline {
+    last_text: String,
+    last_image_hash: String,
+    last_snip_seen_at: Instant,
+    last_error: Option<String>,
+    last_source: Option<&'static str>,
+}
+
+impl Default for Pipeline {
+    fn default() -> Self {
+        Self {
+            last_text: String::new(),
+            last_image_hash: String::new(),
+            last_snip_seen_at: Instant::now() - Duration::from_secs(60),
+            last_error: None,
+            last_source: None,
+        }
+    }
+}
+
+impl Pipeline {
+    /// Reads the clipboard once, records new text in the history and returns
+    /// what changed. Nothing here touches the disk or the frontend.
+    pub fn tick(&mut self, sources: &mut [Box<dyn ClipboardSource>], state: &AppState) -> Vec<Capture> {
+        let mut captures = Vec::new();
+        let window = self.sample_window(sources);
+
+        let mut text = None;
+        let mut reader = None;
+        for (i, source) in sources.iter_mut().enumerate() {
+            match source.read_text() {
+                Ok(t) => {
+                    self.last_source = Some(source.name());
+                    text = Some(t);
+                    reader = Some(i);
+                    break;
+                }
+                Err(e) => {
+                    // Only log when the failure changes; an empty clipboard fails on every read
+                    if self.last_error.as_ref() != Some(&e) {
+                        eprintln!("{} ({})", e, source.name());
+                        self.last_error = Some(e);
+                    }
+                }
+            }
+        }
+        if text.is_some() {
+            self.last_error = None;
+        }
+        let mut text = text.unwrap_or_default();
+        // Files copied in a file manager often come without text; their paths stand
+        // in for it, so the file list is all that is read on an unchanged clipboard
+        if text.is_empty() {
+            if let Some(source) = sources.get_mut(reader.unwrap_or(0)) {
+                text = files_as_text(&source.read_files());
+            }
+        }
+        if !text.is_empty() && text != self.last_text {
+            self.last_text = text.clone();
+            // Password managers mark what they copy; that never enters the history
+            // nor reaches the frontend
+            if sources.get_mut(reader.unwrap_or(0)).is_some_and(|s| s.marked_secret()) {
+                println!("Clipboard content skipped: marked as secret by its source");
+            } else {
+                // Rich formats only for new content; reading them all is not cheap
+                let formats = sources.get_mut(reader.unwrap_or(0))
+                    .map(|source| source.read_formats())
+                    .unwrap_or_default();
+                let outcome = record_text(state, &text, formats, window.clone());
+                captures.push(Capture::Text { text, outcome });
+            }
+        }
+
+        let image = sources.iter_mut().enumerate()
+            .find_map(|(i, s)| s.read_image().ok().flatten().map(|image| (i, image)));
+        if let Some((i, image)) = image {
+            captures.extend(self.image(image, sources[i].as_mut(), state, &window));
+        }
+        captures
+    }
+
+    /// Reads the active window and notes when a snipping tool has focus. Called
+    /// between clipboard changes too, since the tool is usually closed by the time
+    /// its image arrives.
+    pub fn sample_window(&mut self, sources: &[Box<dyn ClipboardSource>]) -> (Option<String>, Option<String>) {
+        let window = sources.first().map(|s| s.active_window()).unwrap_or((None, None));
+        if window.0.as_deref().is_some_and(|t| is_snipping_window_title(&t.to_lowercase())) {
+            self.last_snip_seen_at = Instant::now();
+        }
+        window
+    }
+
+    pub fn last_source(&self) -> Option<&'static str> {
+        self.last_source
+    }
+
+    pub fn last_error(&self) -> Option<&str> {
+        self.last_error.as_deref()
+    }
+
+    fn image(&mut self, image: ClipboardImage, source: &mut dyn ClipboardSource, state: &AppState, window: &(Option<String>, Option<String>)) -> Vec<Capture> {
+        let mut hasher = Sha256::new();
+        hasher.update(&image.rgba);
+        let hash = format!("{:x}", hasher.finalize());
+        if hash == self.last_image_hash {
+            return Vec::new();
+        }
+        self.last_image_hash = hash.clone();
+        if source.marked_secret() {
+            println!("Clipboard image skipped: marked as secret by its source");
+            return Vec::new();
+        }
+
+        let png = match images::encode_png(&image) {
+            Ok(png) => png,
+            Err(e) => {
+                eprintln!("{}", e);
+                return Vec::new();
+            }
+        };
+        let recorded = record_image(state, &image, &png, &hash, window.clone());
+
+        let probable_size = is_probable_screenshot(image.width, image.height);
+        let recent_snip = self.last_snip_seen_at.elapsed() <= SNIP_WINDOW;
+        let probable = probable_size || recent_snip;
+        println!("📸 Clipboard image {}x{}, window={:?}, probable_screenshot={}, recent_snip={}", image.width, image.height, window.0, probable, recent_snip);
+        let screenshot = probable.then(|| Capture::Screenshot { png: png.clone(), width: image.width, height: image.height });
+        let mut captures = vec![Capture::Image { recorded, png, hash }];
+        captures.extend(screenshot);
+        captures
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct TextOutcome {
+    /// An entry was added to the history
+    pub recorded: bool,
+    /// The text contains a secret; it must not be logged or passed around
+    pub sensitive: bool,
+    /// A rule appended the text to at least one note
+    pub routed: bool,
+}
+
+// Text and an image recorded in one tick share a millisecond
+static CLIP_SEQUENCE: AtomicU64 = AtomicU64::new(0);
+
+/// History ids double as pagination cursors, so no two captures may share one.
+pub fn new_clip_id(now: DateTime<Utc>) -> String {
+    format!("clip_{}_{}", now.timestamp_millis(), CLIP_SEQUENCE.fetch_add(1, Ordering::Relaxed))
+}
+
+/// Applies min-length, rules, secret detection and dedup, then inserts the entry.
+pub fn record_text(state: &AppState, text: &str, mut formats: ClipboardFormats, window: (Option<String>, Option<String>)) -> TextOutcome {
+    let limit = *state.clipboard_history_limit.lock().unwrap();
+    let min_len = *state.min_clipboard_text_length.lock().unwrap();
+    let dedup_mins = *state.dedup_window_minutes.lock().unwrap();
+    let rules = state.compiled_rules.lock().unwrap().clone();
+    let policy = state.retention.lock().unwrap().clone();
+
+    let trimmed = text.trim().to_string();
+    let cap_type = if formats.files.is_empty() { detect_capture_type(&trimmed) } else { "files".to_string() };
+    // Short snippets are noise unless they are code, a link or files
+    let mut matches = secrets::detect(&trimmed);
+    let mut outcome = TextOutcome { recorded: false, sensitive: !matches.is_empty(), routed: false };
+    if trimmed.len() < min_len && cap_type == "text" {
+        return outcome;
+    }
+    let (win_title, app_name) = window;
+    let mut source_url = if is_url(&trimmed) { Some(trimmed.clone()) } else { None };
+    let mut input = RuleInput {
+        text: trimmed.clone(),
+        url: source_url.as_deref(),
+        app: app_name.as_deref(),
+        window: win_title.as_deref(),
+        capture_type: cap_type,
+    };
+    let RuleOutcome { tags: mut extra_tags, ignore, merge, pin, note_ids } = rules::apply(&rules, &mut input);
+    if ignore {
+        return outcome;
+    }
+    let RuleInput { mut text, capture_type: cap_type, .. } = input;
+    if text != trimmed {
+        // A transform rewrote the text; what gets stored is what has to be checked
+        text = text.trim().to_string();
+        if text.is_empty() {
+            return outcome;
+        }
+        matches = secrets::detect(&text);
+        outcome.sensitive |= !matches.is_empty();
+        source_url = if is_url(&text) { Some(text.clone()) } else { None };
+    }
+
+    let now_ts = Utc::now();
+    let mut sensitive_reason = None;
+    let mut expires_at = None;
+    if !matches.is_empty() {
+        match *state.secret_action.lock().unwrap() {
+            SecretAction::Skip => return outcome,
+            SecretAction::Mask => {
+                text = secrets::mask(&text, &matches);
+                // The rich formats and the URL still hold the secret
+                formats = ClipboardFormats::default();
+                source_url = None;
+            }
+            SecretAction::MemoryOnly => {
+                let ttl = *state.secret_ttl_minutes.lock().unwrap();
+                expires_at = Some(now_ts + ChronoDuration::minutes(ttl as i64));
+            }
+        }
+        sensitive_reason = Some(secrets::reason(&matches));
+    }
+
+    let mut history = state.clipboard_history.lock().unwrap();
+    let merged = match &merge {
+        Some(merge) if sensitive_reason.is_none() => merge_into_previous(&mut history, merge, &text, &extra_tags, pin, now_ts),
+        _ => false,
+    };
+    if !merged {
+        let hash = compute_text_hash(&text);
+        history.retain(|e| {
+            if let Some(h) = &e.content_hash {
+                if *h == hash {
+                    return now_ts - e.timestamp > ChronoDuration::minutes(dedup_mins as i64);
+                }
+            }
+            true
+        });
+        if let Some(url) = &source_url { extra_tags.extend(auto_tags_for_text_and_url(url)); }
+        let mut entry = ClipboardHistoryEntry {
+            id: new_clip_id(now_ts),
+            text: text.clone(),
+            pinned: pin,
+            timestamp: now_ts,
+            source_app: app_name,
+            window_title: win_title,
+            source_url,
+            capture_type: cap_type,
+            tags: extra_tags,
+            content_hash: Some(hash),
+            formats,
+            image: None,
+            sensitive_reason,
+            expires_at,
+            size_bytes: 0,
+        };
+        entry.size_bytes = retention::measure(&entry);
+        history.insert(0, entry);
+    }
+    let mut orphaned = enforce_history_order_and_limit(&mut history, limit);
+    orphaned.extend(retention::apply(&mut history, &policy, now_ts));
+    drop(history);
+    images::remove_files(orphaned);
+    outcome.recorded = true;
+
+    // Notes are always written to disk, so memory-only secrets never reach them
+    if expires_at.is_none() {
+        for id in &note_ids {
+            match append_to_note(state, id, &text) {
+                Ok(()) => outcome.routed = true,
+                Err(e) => eprintln!("Failed to route capture to note {}: {}", id, e),
+            }
+        }
+    }
+    outcome
+}
+
+/// Appends `text` to the most recent capture when that one also meets the merge
+/// rule's condition and is still inside its window. Returns whether it did.
+fn merge_into_previous(history: &mut [ClipboardHistoryEntry], merge: &Merge, text: &str, tags: &[String], pin: bool, now: DateTime<Utc>) -> bool {
+    let window = ChronoDuration::seconds(merge.window_seconds as i64);
+    let prev = match history.iter_mut().max_by_key(|e| e.timestamp) {
+        Some(e) => e,
+        None => return false,
+    };
+    // Pinned, image and sensitive entries keep their content as captured
+    if prev.pinned || prev.image.is_some() || prev.sensitive_reason.is_some() || now - prev.timestamp > window {
+        return false;
+    }
+    let input = RuleInput {
+        text: prev.text.clone(),
+        url: prev.source_url.as_deref(),
+        app: prev.source_app.as_deref(),
+        window: prev.window_title.as_deref(),
+        capture_type: prev.capture_type.clone(),
+    };
+    if !rules::matches(merge.rule, &input) {
+        return false;
+    }
+    prev.text.push_str(merge.separator);
+    prev.text.push_str(text);
+    prev.content_hash = Some(compute_text_hash(&prev.text));
+    prev.timestamp = now;
+    prev.pinned = pin;
+    // Rich formats describe only the first capture and would paste without the rest
+    prev.formats = ClipboardFormats::default();
+    prev.size_bytes = retention::measure(prev);
+    for tag in tags {
+        if !prev.tags.contains(tag) {
+            prev.tags.push(tag.clone());
+        }
+    }
+    true
+}
+
+/// Stores an image capture as a history entry. Copying the same image again moves
+/// its entry back to the top, pinned state and OCR text included.
+pub fn record_image(state: &AppState, image: &ClipboardImage, png: &[u8], hash: &str, window: (Option<String>, Option<String>)) -> bool {
+    let limit = *state.clipboard_history_limit.lock().unwrap();
+    let rules = state.compiled_rules.lock().unwrap().clone();
+    let policy = state.retention.lock().unwrap().clone();
+    let (win_title, app_name) = window;
+    let mut input = RuleInput {
+        text: String::new(),
+        url: None,
+        app: app_name.as_deref(),
+        window: win_title.as_deref(),
+        capture_type: "image".to_string(),
+    };
+    // Images have no text to merge, transform or route; the other actions apply
+    let ruled = rules::apply(&rules, &mut input);
+    if ruled.ignore {
+        return false;
+    }
+    let cap_type = input.capture_type;
+
+    let mut history = state.clipboard_history.lock().unwrap();
+    // Written under the lock so a concurrent cleanup never sees the files unreferenced
+    let image_ref = match images::store(image, png, hash) {
+        Ok(r) => r,
+        Err(e) => {
+            eprintln!("{}", e);
+            return false;
+        }
+    };
+    let now_ts = Utc::now();
+    let mut entry = match history.iter().position(|e| e.content_hash.as_deref() == Some(hash)) {
+        Some(i) => {
+            let mut entry = history.remove(i);
+            entry.timestamp = now_ts;
+            entry.image = Some(image_ref);
+            entry.pinned |= ruled.pin;
+            entry
+        }
+        None => ClipboardHistoryEntry {
+            id: new_clip_id(now_ts),
+            text: String::new(),
+            pinned: ruled.pin,
+            timestamp: now_ts,
+            source_app: app_name,
+            window_title: win_title,
+            source_url: None,
+            capture_type: cap_type,
+            tags: ruled.tags,
+            content_hash: Some(hash.to_string()),
+            formats: ClipboardFormats::default(),
+            image: Some(image_ref),
+            sensitive_reason: None,
+            expires_at: None,
+            size_bytes: 0,
+        },
+    };
+    entry.size_bytes = retention::measure(&entry);
+    history.insert(0, entry);
+    let mut orphaned = enforce_history_order_and_limit(&mut history, limit);
+    orphaned.extend(retention::apply(&mut history, &policy, now_ts));
+    drop(history);
+    images::remove_files(orphaned);
+    true
+}
+
+struct Running {
+    stop: Arc<AtomicBool>,
+}
+
+/// Owns the clipboard monitoring thread. Pausing through
+/// `clipboard_monitoring_enabled` keeps the thread alive; `stop` ends it.
+#[derive(Default)]
+pub struct ClipboardMonitor {
+    running: Mutex<Option<Running>>,
+    status: Arc<Mutex<MonitorStatus>>,
+}
+
+impl ClipboardMonitor {
+    pub fn start(&self, app_handle: tauri::AppHandle) -> Result<(), String> {
+        let mut running = self.running.lock()
+            .map_err(|_| "Failed to lock clipboard monitor".to_string())?;
+        if running.is_some() {
+            return Ok(());
+        }
+        let stop = Arc::new(AtomicBool::new(false));
+        let worker = Worker {
+            app_handle,
+            stop: stop.clone(),
+            status: self.status.clone(),
+            changes: None,
+            pipeline: Pipeline::default(),
+        };
+        thread::Builder::new()
+            .name("clipboard-monitor".into())
+            .spawn(move || worker.run())
+            .map_err(|e| format!("Failed to start clipboard monitor: {}", e))?;
+        *running = Some(Running { stop });
+        if let Ok(mut status) = self.status.lock() {
+            status.running = true;
+        }
+        Ok(())
+    }
+
+    pub fn stop(&self) -> Result<(), String> {
+        let mut running = self.running.lock()
+            .map_err(|_| "Failed to lock clipboard monitor".to_string())?;
+        if let Some(r) = running.take() {
+            r.stop.store(true, Ordering::Relaxed);
+        }
+        if let Ok(mut status) = self.status.lock() {
+            status.running = false;
+            status.mode = None;
+        }
+        Ok(())
+    }
+
+    pub fn status(&self) -> Result<MonitorStatus, String> {
+        self.status.lock()
+            .map(|s| s.clone())
+            .map_err(|_| "Failed to lock clipboard monitor status".to_string())
+    }
+}
+
+fn persist(state: &AppState) {
+    if !*state.persistence_enabled.lock().unwrap() {
+        return;
+    }
+    if let Ok(history) = state.clipboard_history.lock() {
+        if let Err(e) = save_clipboard_history_to_disk(&history) {
+            eprintln!("Failed to save clipboard history: {}", e);
+        }
+    }
+}
+
+struct Worker {
+    app_handle: tauri::AppHandle,
+    stop: Arc<AtomicBool>,
+    status: Arc<Mutex<MonitorStatus>>,
+    // Woken by native change notifications where available, else polls
+    changes: Option<Receiver<()>>,
+    pipeline: Pipeline,
+}
+
+impl Worker {
+    fn run(mut self) {
+        let mode = match watch_changes() {
+            Some((kind, rx)) => {
+                self.changes = Some(rx);
+                kind
+            }
+            None => "polling",
+        };
+        self.set_status(|s| s.mode = Some(mode.to_string()));
+        println!("Clipboard monitoring thread started ({})", mode);
+
+        let mut sources: Vec<Box<dyn ClipboardSource>> = vec![
+            Box::new(PluginSource::new(self.app_handle.clone())),
+            Box::new(ArboardSource),
+        ];
+        while !self.stop.load(Ordering::Relaxed) {
+            let state = match self.app_handle.try_state::<AppState>() {
+                Some(s) => s,
+                None => return,
+            };
+            // Respect the auto-copy/monitoring flag
+            if !*state.clipboard_monitoring_enabled.lock().unwrap() {
+                // Changes made while disabled are not captured later
+                if let Some(rx) = &self.changes {
+                    while rx.try_recv().is_ok() {}
+                }
+                self.sleep(Duration::from_millis(500));
+                continue;
+            }
+
+            let captures = self.pipeline.tick(&mut sources, &state);
+            let source = self.pipeline.last_source().map(|s| s.to_string());
+            let error = self.pipeline.last_error().map(|e| e.to_string());
+            let captured = captures.len() as u64;
+            self.set_status(|s| {
+                s.source = source;
+                s.last_error = error;
+                if captured > 0 {
+                    s.captured += captured;
+                    s.last_capture_at = Some(Utc::now());
+                }
+            });
+            for capture in captures {
+                self.announce(&state, capture);
+            }
+
+            let interval = *state.clipboard_poll_interval_ms.lock().unwrap();
+            self.wait(interval, &sources);
+        }
+        println!("Clipboard monitoring thread stopped");
+    }
+
+    fn announce(&self, state: &AppState, capture: Capture) {
+        match capture {
+            Capture::Text { text, outcome } => {
+                // Secrets stay out of the log and out of the auto-import in the editor
+                let text = if outcome.sensitive {
+                    println!("Clipboard content changed (sensitive, not shown)");
+                    String::new()
+                } else {
+                    println!("Clipboard content changed: {}", text);
+                    text
+                };
+                if outcome.recorded {
+                    persist(state);
+                }
+                if outcome.routed {
+                    let _ = self.app_handle.emit("note-saved", ());
+                }
+                if let Err(e) = self.app_handle.emit("clipboard-changed", ClipboardContent { text, from_app: false }) {
+                    eprintln!("Failed to emit clipboard event: {}", e);
+                }
+            }
+            Capture::Image { recorded, png, hash } => {
+                if recorded {
+                    persist(state);
+                    let _ = self.app_handle.emit("clipboard-changed", ClipboardContent { text: String::new(), from_app: false });
+                    // Recognized text makes the entry searchable
+                    let _ = state.ocr_queue.lock().unwrap().send(OcrJob::Capture { png, hash });
+                }
+            }
+            Capture::Screenshot { png, width, height } => {
+                let b64 = base64::engine::general_purpose::STANDARD.encode(&png);
+                let data_url = format!("data:image/png;base64,{}", b64);
+                let _ = self.app_handle.emit("clipboard-image", ClipboardImagePayload { data_url, width, height });
+            }
+        }
+    }
+
+    // Blocks until the clipboard changes, or for one poll interval without change
+    // events. The active window is sampled meanwhile so snips are still recognized.
+    fn wait(&mut self, interval_ms: u64, sources: &[Box<dyn ClipboardSource>]) {
+        let deadline = Instant::now() + Duration::from_millis(interval_ms);
+        while !self.stop.load(Ordering::Relaxed) {
+            self.pipeline.sample_window(sources);
+            match &self.changes {
+                Some(rx) => match rx.recv_timeout(STOP_CHECK_INTERVAL) {
+                    Ok(()) => return,
+                    Err(RecvTimeoutError::Timeout) => {}
+                    Err(RecvTimeoutError::Disconnected) => {
+                        println!("Clipboard change events stopped; falling back to polling");
+                        self.changes = None;
+                        self.set_status(|s| s.mode = Some("polling".to_string()));
+                        return;
+                    }
+                },
+                None => {
+                    let left = deadline.saturating_duration_since(Instant::now());
+                    if left.is_zero() {
+                        return;
+                    }
+                    thread::sleep(left.min(STOP_CHECK_INTERVAL));
+                }
+            }
+        }
+    }
+
+    fn sleep(&self, duration: Duration) {
+        let deadline = Instant::now() + duration;
+        while !self.stop.load(Ordering::Relaxed) {
+            let left = deadline.saturating_duration_since(Instant::now());
+            if left.is_zero() {
+                return;
+            }
+            thread::sleep(left.min(STOP_CHECK_INTERVAL));
+        }
+    }
+
+    fn set_status(&self, update: impl FnOnce(&mut MonitorStatus)) {
+        if let Ok(mut status) = self.status.lock() {
+            update(&mut status);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::source::{FakeClipboard, FakeSource};
+    use crate::test_app_state;
+    use app_lib::{Rule, RuleAction, RuleCondition, RuleField};
+
+    fn state(limit: usize, min_len: usize, rules: Vec<Rule>) -> AppState {
+        let state = test_app_state();
+        *state.clipboard_history_limit.lock().unwrap() = limit;
+        *state.min_clipboard_text_length.lock().unwrap() = min_len;
+        let compiled = rules.iter().cloned().map(|r| rules::compile(r).unwrap()).collect();
+        *state.rules.lock().unwrap() = rules;
+        *state.compiled_rules.lock().unwrap() = Arc::new(compiled);
+        state
+    }
+
+    // Ticks once per scripted clipboard text
+    fn run(state: &AppState, texts: &[&str]) -> Vec<Capture> {
+        let mut sources: Vec<Box<dyn ClipboardSource>> = vec![Box::new(FakeSource::texts(texts))];
+        let mut pipeline = Pipeline::default();
+        texts.iter().flat_map(|_| pipeline.tick(&mut sources, state)).collect()
+    }
+
+    fn history_texts(state: &AppState) -> Vec<String> {
+        state.clipboard_history.lock().unwrap().iter().map(|e| e.text.clone()).collect()
+    }
+
+    #[test]
+    fn records_new_text_once() {
+        let state = state(50, 8, Vec::new());
+        let captures = run(&state, &["copied some text", "copied some text"]);
+
+        assert_eq!(captures.len(), 1);
+        assert!(matches!(&captures[0], Capture::Text { outcome, .. } if outcome.recorded));
+        assert_eq!(history_texts(&state), vec!["copied some text"]);
+    }
+
+    #[test]
+    fn matching_rule_tags_the_entry() {
+        let rule = Rule {
+            name: None,
+            priority: 0,
+            when: RuleCondition::Match { field: RuleField::Text, pattern: "^TODO".to_string(), negate: false },
+            actions: vec![RuleAction::Tag { tag: "todo".to_string() }],
+            stop_processing: false,
+        };
+        let state = state(50, 8, vec![rule]);
+        run(&state, &["TODO write the report", "nothing to do here"]);
+
+        let history = state.clipboard_history.lock().unwrap();
+        assert_eq!(history.len(), 2);
+        assert!(history[0].tags.is_empty());
+        assert_eq!(history[1].tags, vec!["todo"]);
+    }
+
+    #[test]
+    fn duplicate_within_window_moves_to_top() {
+        let state = state(50, 8, Vec::new());
+        run(&state, &["first capture", "second capture", "first capture"]);
+
+        assert_eq!(history_texts(&state), vec!["first capture", "second capture"]);
+    }
+
+    #[test]
+    fn short_text_is_not_recorded() {
+        let state = state(50, 8, Vec::new());
+        let captures = run(&state, &["short"]);
+
+        assert!(matches!(&captures[0], Capture::Text { outcome, .. } if !outcome.recorded));
+        assert!(history_texts(&state).is_empty());
+    }
+
+    #[test]
+    fn history_is_trimmed_to_limit() {
+        let state = state(2, 8, Vec::new());
+        run(&state, &["capture number one", "capture number two", "capture number three"]);
+
+        assert_eq!(history_texts(&state), vec!["capture number three", "capture number two"]);
+    }
+
+    #[test]
+    fn rich_formats_are_read_only_for_new_content() {
+        let state = state(50, 8, Vec::new());
+        let files = ClipboardFormats { files: vec!["file:///tmp/report.txt".to_string()], ..Default::default() };
+        let steps = vec![
+            FakeClipboard::default(),
+            FakeClipboard::default(),
+            FakeClipboard { formats: files.clone(), ..Default::default() },
+            FakeClipboard { formats: files, ..Default::default() },
+            FakeClipboard { text: Some("copied some text".to_string()), ..Default::default() },
+            FakeClipboard { text: Some("copied some text".to_string()), ..Default::default() },
+        ];
+        let source = FakeSource::new(steps);
+        let format_reads = source.format_reads.clone();
+        let mut sources: Vec<Box<dyn ClipboardSource>> = vec![Box::new(source)];
+        let mut pipeline = Pipeline::default();
+
+        let reads: Vec<usize> = (0..6).map(|_| {
+            pipeline.tick(&mut sources, &state);
+            format_reads.get()
+        }).collect();
+
+        assert_eq!(reads, vec![0, 0, 1, 1, 2, 2]);
+        assert_eq!(history_texts(&state), vec!["copied some text", "/tmp/report.txt"]);
+        assert_eq!(state.clipboard_history.lock().unwrap()[1].formats.files, vec!["file:///tmp/report.txt"]);
+    }
+
+    #[test]
+    fn record_text_keeps_pinned_entries_over_limit() {
+        let state = state(1, 8, Vec::new());
+        record_text(&state, "pin this entry", ClipboardFormats::default(), (None, None));
+        state.clipboard_history.lock().unwrap()[0].pinned = true;
+        record_text(&state, "another capture", ClipboardFormats::default(), (None, None));
+        record_text(&state, "latest capture", ClipboardFormats::default(), (None, None));
+
+        assert_eq!(history_texts(&state), vec!["pin this entry", "latest capture"]);
+    }
+}
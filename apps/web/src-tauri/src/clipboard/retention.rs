@@ -0,0 +1,103 @@
+use app_lib::{AppState, ClipboardContent, ClipboardHistoryEntry, RetentionPolicy};
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use std::fs;
+use std::thread;
+use std::time::Duration;
+use tauri::{Emitter, Manager};
+
+use super::images;
+use crate::save_clipboard_history_to_disk;
+
+// Memory-only entries may outlive their expiry by at most this much
+const SWEEP_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Drops memory-only entries whose time is up.
+fn remove_expired(history: &mut Vec<ClipboardHistoryEntry>, now: DateTime<Utc>) -> Vec<String> {
+    images::retain_entries(history, |e| e.expires_at.map_or(true, |t| t > now))
+}
+
+/// Size of an entry as retention counts it. Reads the image files, so it is meant
+/// for capture time; `size_bytes` keeps the result.
+pub fn measure(entry: &ClipboardHistoryEntry) -> u64 {
+    let formats = &entry.formats;
+    let text = entry.text.len()
+        + formats.html.as_ref().map_or(0, |s| s.len())
+        + formats.rtf.as_ref().map_or(0, |s| s.len())
+        + formats.svg.as_ref().map_or(0, |s| s.len())
+        + formats.files.iter().map(|f| f.len()).sum::<usize>();
+    let files = entry.image.as_ref().map_or(0, |i| {
+        [&i.path, &i.thumbnail].iter()
+            .filter_map(|p| fs::metadata(images::resolve(p)).ok())
+            .map(|m| m.len())
+            .sum::<u64>()
+    });
+    text as u64 + files
+}
+
+/// Applies the age, per-type and size limits to unpinned entries. Expects the
+/// history newest-first. Returns the image files of removed entries.
+pub fn apply(history: &mut Vec<ClipboardHistoryEntry>, policy: &RetentionPolicy, now: DateTime<Utc>) -> Vec<String> {
+    let mut orphaned = images::retain_entries(history, |e| {
+        if e.pinned {
+            return true;
+        }
+        let hours = policy.ttl_hours.get(&e.capture_type).copied().or(policy.max_age_hours);
+        match hours {
+            Some(h) => now - e.timestamp < ChronoDuration::hours(h as i64),
+            None => true,
+        }
+    });
+    if let Some(max_bytes) = policy.max_bytes {
+        let mut total = 0u64;
+        orphaned.extend(images::retain_entries(history, |e| {
+            if e.pinned {
+                return true;
+            }
+            total += e.size_bytes;
+            total <= max_bytes
+        }));
+    }
+    orphaned
+}
+
+/// Applies retention and memory-only expiry right away, saving and notifying the
+/// frontend when entries went away.
+pub fn sweep(app_handle: &tauri::AppHandle) {
+    let state = match app_handle.try_state::<AppState>() {
+        Some(s) => s,
+        None => return,
+    };
+    let policy = state.retention.lock().unwrap().clone();
+    let persist = *state.persistence_enabled.lock().unwrap();
+    let now = Utc::now();
+    let removed = match state.clipboard_history.lock() {
+        Ok(mut history) => {
+            let before = history.len();
+            let mut orphaned = remove_expired(&mut history, now);
+            let after_expiry = history.len();
+            orphaned.extend(apply(&mut history, &policy, now));
+            let expired = after_expiry < before;
+            let retained = history.len() < after_expiry;
+            if retained && persist {
+                if let Err(e) = save_clipboard_history_to_disk(&history) {
+                    eprintln!("Failed to save clipboard history: {}", e);
+                }
+            }
+            drop(history);
+            images::remove_files(orphaned);
+            expired || retained
+        }
+        Err(_) => false,
+    };
+    if removed {
+        let _ = app_handle.emit("clipboard-changed", ClipboardContent { text: String::new(), from_app: false });
+    }
+}
+
+/// Keeps the history within its retention policy while nothing is being copied.
+pub fn spawn_sweeper(app_handle: tauri::AppHandle) {
+    thread::spawn(move || loop {
+        sweep(&app_handle);
+        thread::sleep(SWEEP_INTERVAL);
+    });
+}
@@ -0,0 +1,166 @@
+use app_lib::ClipboardFormats;
+use tauri_plugin_clipboard_manager::ClipboardExt;
+
+use super::formats;
+use crate::get_active_window_info;
+
+/// Raw RGBA pixels as read from the clipboard.
+#[derive(Clone)]
+pub struct ClipboardImage {
+    pub width: u32,
+    pub height: u32,
+    pub rgba: Vec<u8>,
+}
+
+/// Somewhere the monitor can read the current clipboard from. Sources are tried
+/// in order; the first one that does not fail is used for that read.
+pub trait ClipboardSource {
+    fn name(&self) -> &'static str;
+
+    fn read_text(&mut self) -> Result<String, String>;
+
+    fn read_image(&mut self) -> Result<Option<ClipboardImage>, String>;
+
+    /// HTML, RTF, file lists and SVG; neither the plugin nor `arboard` reads all of them.
+    fn read_formats(&mut self) -> ClipboardFormats {
+        formats::read()
+    }
+
+    /// File list only, for telling file copies apart without reading every format.
+    fn read_files(&mut self) -> Vec<String> {
+        formats::read_files()
+    }
+
+    /// The clipboard owner (usually a password manager) marked the content as
+    /// secret or excluded it from history.
+    fn marked_secret(&mut self) -> bool {
+        formats::marked_secret()
+    }
+
+    /// Window title and app name of whatever the user is copying from.
+    fn active_window(&self) -> (Option<String>, Option<String>) {
+        get_active_window_info()
+    }
+}
+
+/// The Tauri clipboard plugin; the regular source.
+pub struct PluginSource {
+    app_handle: tauri::AppHandle,
+}
+
+impl PluginSource {
+    pub fn new(app_handle: tauri::AppHandle) -> Self {
+        Self { app_handle }
+    }
+}
+
+impl ClipboardSource for PluginSource {
+    fn name(&self) -> &'static str {
+        "plugin"
+    }
+
+    fn read_text(&mut self) -> Result<String, String> {
+        self.app_handle.clipboard().read_text()
+            .map_err(|e| format!("Failed to read clipboard: {}", e))
+    }
+
+    fn read_image(&mut self) -> Result<Option<ClipboardImage>, String> {
+        let image = self.app_handle.clipboard().read_image()
+            .map_err(|e| format!("Failed to read clipboard image: {}", e))?;
+        Ok(Some(ClipboardImage { width: image.width(), height: image.height(), rgba: image.rgba().to_vec() }))
+    }
+}
+
+/// Direct `arboard` access, for when the plugin cannot read the clipboard.
+pub struct ArboardSource;
+
+impl ClipboardSource for ArboardSource {
+    fn name(&self) -> &'static str {
+        "arboard"
+    }
+
+    fn read_text(&mut self) -> Result<String, String> {
+        arboard::Clipboard::new()
+            .and_then(|mut cb| cb.get_text())
+            .map_err(|e| format!("Failed to read clipboard: {}", e))
+    }
+
+    fn read_image(&mut self) -> Result<Option<ClipboardImage>, String> {
+        let image = arboard::Clipboard::new()
+            .and_then(|mut cb| cb.get_image())
+            .map_err(|e| format!("Failed to read clipboard image: {}", e))?;
+        Ok(Some(ClipboardImage {
+            width: image.width as u32,
+            height: image.height as u32,
+            rgba: image.bytes.into_owned(),
+        }))
+    }
+}
+
+/// What a `FakeSource` has on the clipboard at one step of its script.
+#[cfg(test)]
+#[derive(Clone, Default)]
+pub struct FakeClipboard {
+    pub text: Option<String>,
+    pub image: Option<ClipboardImage>,
+    pub formats: ClipboardFormats,
+    pub marked_secret: bool,
+    pub window_title: Option<String>,
+    pub app_name: Option<String>,
+}
+
+/// Scripted source for driving the pipeline without a real clipboard. Every text
+/// read moves to the next step; the last step stays on the clipboard.
+#[cfg(test)]
+pub struct FakeSource {
+    steps: std::collections::VecDeque<FakeClipboard>,
+    current: FakeClipboard,
+    /// How often all rich formats were read, shared so tests can check it
+    pub format_reads: std::rc::Rc<std::cell::Cell<usize>>,
+}
+
+#[cfg(test)]
+impl FakeSource {
+    pub fn new(steps: Vec<FakeClipboard>) -> Self {
+        Self { steps: steps.into(), current: FakeClipboard::default(), format_reads: Default::default() }
+    }
+
+    pub fn texts(texts: &[&str]) -> Self {
+        Self::new(texts.iter().map(|t| FakeClipboard { text: Some(t.to_string()), ..Default::default() }).collect())
+    }
+}
+
+#[cfg(test)]
+impl ClipboardSource for FakeSource {
+    fn name(&self) -> &'static str {
+        "fake"
+    }
+
+    fn read_text(&mut self) -> Result<String, String> {
+        if let Some(next) = self.steps.pop_front() {
+            self.current = next;
+        }
+        self.current.text.clone().ok_or_else(|| "Clipboard is empty".to_string())
+    }
+
+    fn read_image(&mut self) -> Result<Option<ClipboardImage>, String> {
+        Ok(self.current.image.clone())
+    }
+
+    fn read_formats(&mut self) -> ClipboardFormats {
+        self.format_reads.set(self.format_reads.get() + 1);
+        self.current.formats.clone()
+    }
+
+    fn read_files(&mut self) -> Vec<String> {
+        self.current.formats.files.clone()
+    }
+
+    fn marked_secret(&mut self) -> bool {
+        self.current.marked_secret
+    }
+
+    fn active_window(&self) -> (Option<String>, Option<String>) {
+        (self.current.window_title.clone(), self.current.app_name.clone())
+    }
+}
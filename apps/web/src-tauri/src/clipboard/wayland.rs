@@ -0,0 +1,159 @@
+use std::sync::mpsc::Sender;
+use wayland_client::protocol::{wl_registry, wl_seat::WlSeat};
+use wayland_client::{delegate_noop, event_created_child, Connection, Dispatch, EventQueue, QueueHandle};
+use wayland_protocols::ext::data_control::v1::client::{
+    ext_data_control_device_v1::{self, ExtDataControlDeviceV1},
+    ext_data_control_manager_v1::ExtDataControlManagerV1,
+    ext_data_control_offer_v1::ExtDataControlOfferV1,
+};
+use wayland_protocols_wlr::data_control::v1::client::{
+    zwlr_data_control_device_v1::{self, ZwlrDataControlDeviceV1},
+    zwlr_data_control_manager_v1::ZwlrDataControlManagerV1,
+    zwlr_data_control_offer_v1::ZwlrDataControlOfferV1,
+};
+
+#[derive(Default)]
+struct State {
+    seat: Option<WlSeat>,
+    ext_manager: Option<ExtDataControlManagerV1>,
+    wlr_manager: Option<ZwlrDataControlManagerV1>,
+    changed: bool,
+    finished: bool,
+}
+
+impl Dispatch<wl_registry::WlRegistry, ()> for State {
+    fn event(
+        state: &mut Self,
+        registry: &wl_registry::WlRegistry,
+        event: wl_registry::Event,
+        _: &(),
+        _: &Connection,
+        qh: &QueueHandle<Self>,
+    ) {
+        if let wl_registry::Event::Global { name, interface, version } = event {
+            match interface.as_str() {
+                // Only the first seat; that is where the user's clipboard lives
+                "wl_seat" if state.seat.is_none() => state.seat = Some(registry.bind(name, version.min(2), qh, ())),
+                "ext_data_control_manager_v1" => state.ext_manager = Some(registry.bind(name, 1, qh, ())),
+                "zwlr_data_control_manager_v1" => state.wlr_manager = Some(registry.bind(name, version.min(2), qh, ())),
+                _ => {}
+            }
+        }
+    }
+}
+
+delegate_noop!(State: ignore WlSeat);
+delegate_noop!(State: ExtDataControlManagerV1);
+delegate_noop!(State: ZwlrDataControlManagerV1);
+// We never read through the offers; the regular clipboard readers fetch the content
+delegate_noop!(State: ignore ExtDataControlOfferV1);
+delegate_noop!(State: ignore ZwlrDataControlOfferV1);
+
+impl Dispatch<ExtDataControlDeviceV1, ()> for State {
+    fn event(
+        state: &mut Self,
+        _: &ExtDataControlDeviceV1,
+        event: ext_data_control_device_v1::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        match event {
+            ext_data_control_device_v1::Event::Selection { id } => {
+                if let Some(offer) = id {
+                    offer.destroy();
+                }
+                state.changed = true;
+            }
+            ext_data_control_device_v1::Event::PrimarySelection { id: Some(offer) } => offer.destroy(),
+            ext_data_control_device_v1::Event::Finished => state.finished = true,
+            _ => {}
+        }
+    }
+
+    event_created_child!(State, ExtDataControlDeviceV1, [
+        ext_data_control_device_v1::EVT_DATA_OFFER_OPCODE => (ExtDataControlOfferV1, ()),
+    ]);
+}
+
+impl Dispatch<ZwlrDataControlDeviceV1, ()> for State {
+    fn event(
+        state: &mut Self,
+        _: &ZwlrDataControlDeviceV1,
+        event: zwlr_data_control_device_v1::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        match event {
+            zwlr_data_control_device_v1::Event::Selection { id } => {
+                if let Some(offer) = id {
+                    offer.destroy();
+                }
+                state.changed = true;
+            }
+            zwlr_data_control_device_v1::Event::PrimarySelection { id: Some(offer) } => offer.destroy(),
+            zwlr_data_control_device_v1::Event::Finished => state.finished = true,
+            _ => {}
+        }
+    }
+
+    event_created_child!(State, ZwlrDataControlDeviceV1, [
+        zwlr_data_control_device_v1::EVT_DATA_OFFER_OPCODE => (ZwlrDataControlOfferV1, ()),
+    ]);
+}
+
+/// Listens for selection changes through the data-control protocol (the `ext`
+/// version when the compositor has it, else the wlroots one). Compositors without
+/// either, such as GNOME, make `connect` fail so the caller can fall back to polling.
+pub struct WaylandWatcher {
+    // Keeps the socket open for the queue
+    _conn: Connection,
+    queue: EventQueue<State>,
+    state: State,
+}
+
+impl WaylandWatcher {
+    pub fn connect() -> Result<Self, String> {
+        let conn = Connection::connect_to_env()
+            .map_err(|e| format!("Failed to connect to Wayland: {}", e))?;
+        let mut queue = conn.new_event_queue();
+        let qh = queue.handle();
+        conn.display().get_registry(&qh, ());
+
+        let mut state = State::default();
+        queue.roundtrip(&mut state)
+            .map_err(|e| format!("Wayland roundtrip failed: {}", e))?;
+        let seat = state.seat.clone().ok_or("No Wayland seat")?;
+        if let Some(manager) = &state.ext_manager {
+            manager.get_data_device(&seat, &qh, ());
+        } else if let Some(manager) = &state.wlr_manager {
+            manager.get_data_device(&seat, &qh, ());
+        } else {
+            return Err("Compositor does not support the data-control protocol".to_string());
+        }
+
+        // The compositor announces the current selection right away; that is not a change
+        queue.roundtrip(&mut state)
+            .map_err(|e| format!("Wayland roundtrip failed: {}", e))?;
+        state.changed = false;
+        Ok(Self { _conn: conn, queue, state })
+    }
+
+    /// Blocks until the compositor drops the device or nobody listens anymore.
+    pub fn run(mut self, changes: Sender<()>) {
+        loop {
+            if let Err(e) = self.queue.blocking_dispatch(&mut self.state) {
+                eprintln!("Wayland clipboard listener stopped: {}", e);
+                return;
+            }
+            if std::mem::take(&mut self.state.changed) && changes.send(()).is_err() {
+                return;
+            }
+            if self.state.finished {
+                eprintln!("Wayland clipboard listener stopped: data device finished");
+                return;
+            }
+        }
+    }
+}
@@ -0,0 +1,80 @@
+use std::fmt::Display;
+use std::sync::mpsc::Sender;
+use x11rb::connection::Connection;
+use x11rb::protocol::xfixes::{ConnectionExt as _, SelectionEventMask};
+use x11rb::protocol::xproto::{ConnectionExt as _, CreateWindowAux, WindowClass};
+use x11rb::protocol::Event;
+use x11rb::rust_connection::RustConnection;
+
+fn x11_error(e: impl Display) -> String {
+    format!("X11 error: {}", e)
+}
+
+/// Listens for XFixes selection-owner changes on CLIPBOARD. Every copy makes the
+/// copying client take ownership, so each copy produces one event.
+pub struct X11Watcher {
+    conn: RustConnection,
+}
+
+impl X11Watcher {
+    pub fn connect() -> Result<Self, String> {
+        let (conn, screen_num) = x11rb::connect(None)
+            .map_err(|e| format!("Failed to connect to X server: {}", e))?;
+        let root = conn.setup().roots[screen_num].root;
+
+        // XFixes wants a window to deliver events to; an unmapped input-only one will do
+        let window = conn.generate_id().map_err(x11_error)?;
+        conn.create_window(
+            x11rb::COPY_DEPTH_FROM_PARENT,
+            window,
+            root,
+            0,
+            0,
+            1,
+            1,
+            0,
+            WindowClass::INPUT_ONLY,
+            x11rb::COPY_FROM_PARENT,
+            &CreateWindowAux::new(),
+        )
+        .map_err(x11_error)?;
+
+        conn.xfixes_query_version(5, 0)
+            .map_err(x11_error)?
+            .reply()
+            .map_err(|e| format!("XFixes is not available: {}", e))?;
+        let clipboard = conn.intern_atom(false, b"CLIPBOARD")
+            .map_err(x11_error)?
+            .reply()
+            .map_err(x11_error)?
+            .atom;
+        conn.xfixes_select_selection_input(
+            window,
+            clipboard,
+            SelectionEventMask::SET_SELECTION_OWNER
+                | SelectionEventMask::SELECTION_WINDOW_DESTROY
+                | SelectionEventMask::SELECTION_CLIENT_CLOSE,
+        )
+        .map_err(x11_error)?;
+        conn.flush().map_err(x11_error)?;
+        Ok(Self { conn })
+    }
+
+    /// Blocks until the connection fails or nobody listens anymore.
+    pub fn run(self, changes: Sender<()>) {
+        loop {
+            match self.conn.wait_for_event() {
+                Ok(Event::XfixesSelectionNotify(_)) => {
+                    if changes.send(()).is_err() {
+                        return;
+                    }
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    eprintln!("X11 clipboard listener stopped: {}", e);
+                    return;
+                }
+            }
+        }
+    }
+}
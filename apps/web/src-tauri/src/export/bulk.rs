@@ -0,0 +1,168 @@
+use app_lib::{BulkExportOptions, ExportFormat, ExportGrouping, Note};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::{html, pdf, rewrite_markdown_images};
+
+const ASSETS_DIR: &str = "assets";
+
+/// Writes notes into one output folder, keeping file names unique per folder and
+/// sharing a single `assets/` folder for every image the notes reference.
+pub struct BulkExporter {
+    root: PathBuf,
+    format: ExportFormat,
+    options: BulkExportOptions,
+    used_names: HashMap<PathBuf, HashSet<String>>,
+    copied_assets: HashMap<PathBuf, String>,
+    asset_names: HashSet<String>,
+    missing_chars: BTreeSet<char>,
+}
+
+fn extension(format: ExportFormat) -> &'static str {
+    match format {
+        ExportFormat::Markdown => "md",
+        ExportFormat::Html => "html",
+        ExportFormat::Pdf => "pdf",
+        ExportFormat::Json => "json",
+    }
+}
+
+fn safe_component(name: &str, fallback: &str) -> String {
+    let cleaned = sanitize_filename::sanitize(name.trim());
+    let cleaned = cleaned.trim_matches(|c: char| c == '.' || c.is_whitespace());
+    if cleaned.is_empty() { fallback.to_string() } else { cleaned.to_string() }
+}
+
+// "name.ext", then "name (2).ext", "name (3).ext", ... compared case-insensitively
+// so the result is also unique on case-insensitive file systems. Names of files
+// already in `dir`, e.g. from an earlier export, are skipped as well.
+fn unique_name(taken: &mut HashSet<String>, dir: &Path, stem: &str, ext: &str) -> String {
+    let mut n = 1;
+    loop {
+        let candidate = if n == 1 {
+            format!("{}.{}", stem, ext)
+        } else {
+            format!("{} ({}).{}", stem, n, ext)
+        };
+        if !taken.contains(&candidate.to_lowercase()) && !dir.join(&candidate).exists() {
+            taken.insert(candidate.to_lowercase());
+            return candidate;
+        }
+        n += 1;
+    }
+}
+
+impl BulkExporter {
+    pub fn new(root: PathBuf, format: ExportFormat, options: BulkExportOptions) -> Result<Self, String> {
+        fs::create_dir_all(&root)
+            .map_err(|e| format!("Failed to create export directory: {}", e))?;
+        Ok(Self {
+            root,
+            format,
+            options,
+            used_names: HashMap::new(),
+            copied_assets: HashMap::new(),
+            asset_names: HashSet::new(),
+            missing_chars: BTreeSet::new(),
+        })
+    }
+
+    fn folder_for(&self, note: &Note) -> PathBuf {
+        let folder: PathBuf = match self.options.group_by {
+            ExportGrouping::None => PathBuf::new(),
+            ExportGrouping::Tag => note
+                .tags
+                .iter()
+                .map(|t| t.trim().trim_start_matches('#'))
+                .find(|t| !t.is_empty())
+                .map(|t| PathBuf::from(safe_component(t, "Untagged")))
+                .unwrap_or_default(),
+            ExportGrouping::Notebook => note
+                .notebook
+                .as_deref()
+                .unwrap_or("")
+                .split('/')
+                .filter(|part| !part.trim().is_empty())
+                .map(|part| safe_component(part, "Notebook"))
+                .collect(),
+        };
+        // A tag or notebook called "assets" must not share the images folder
+        match folder.components().next() {
+            Some(first) if first.as_os_str().eq_ignore_ascii_case(ASSETS_DIR) => {
+                let rest: PathBuf = folder.components().skip(1).collect();
+                PathBuf::from(format!("{} (notes)", ASSETS_DIR)).join(rest)
+            }
+            _ => folder,
+        }
+    }
+
+    // Copies an image into assets/ once and returns its path relative to a note
+    // written `depth` folders below the export root
+    fn asset_ref(&mut self, source: &Path, depth: usize) -> Option<String> {
+        let name = match self.copied_assets.get(source) {
+            Some(name) => name.clone(),
+            None => {
+                let file_name = source.file_name()?.to_str()?;
+                let (stem, ext) = match file_name.rsplit_once('.') {
+                    Some((s, e)) => (s.to_string(), e.to_string()),
+                    None => (file_name.to_string(), "png".to_string()),
+                };
+                let assets_dir = self.root.join(ASSETS_DIR);
+                let name = unique_name(&mut self.asset_names, &assets_dir, &safe_component(&stem, "image"), &ext);
+                fs::create_dir_all(&assets_dir).ok()?;
+                fs::copy(source, assets_dir.join(&name)).ok()?;
+                self.copied_assets.insert(source.to_path_buf(), name.clone());
+                name
+            }
+        };
+        Some(format!("{}{}/{}", "../".repeat(depth), ASSETS_DIR, name.replace(' ', "%20")))
+    }
+
+    /// Writes a single note and returns the path of the created file.
+    pub fn export_note(&mut self, note: &Note) -> Result<PathBuf, String> {
+        let folder = self.folder_for(note);
+        let depth = folder.components().count();
+        let ext = extension(self.format);
+        let dir = self.root.join(&folder);
+        let file_name = {
+            let taken = self.used_names.entry(folder.clone()).or_default();
+            unique_name(taken, &dir, &safe_component(&note.title, "Untitled"), ext)
+        };
+        fs::create_dir_all(&dir)
+            .map_err(|e| format!("Failed to create folder: {}", e))?;
+        let path = dir.join(file_name);
+
+        match self.format {
+            ExportFormat::Markdown => {
+                let markdown = crate::markdown::note_to_markdown(note);
+                let markdown = rewrite_markdown_images(&markdown, |p| self.asset_ref(p, depth));
+                fs::write(&path, markdown)
+                    .map_err(|e| format!("Failed to write file: {}", e))?;
+            }
+            ExportFormat::Html => {
+                let theme = self.options.theme.clone();
+                let document = html::render_note_document_with(note, theme.as_deref(), |p| self.asset_ref(p, depth))?;
+                fs::write(&path, document)
+                    .map_err(|e| format!("Failed to write file: {}", e))?;
+            }
+            ExportFormat::Pdf => {
+                let options = self.options.pdf.clone().unwrap_or_default();
+                let missing = pdf::write_notes_pdf(std::slice::from_ref(note), &path, &options)?;
+                self.missing_chars.extend(missing);
+            }
+            ExportFormat::Json => {
+                let json = serde_json::to_string_pretty(note)
+                    .map_err(|e| format!("Failed to serialize note: {}", e))?;
+                fs::write(&path, json)
+                    .map_err(|e| format!("Failed to write file: {}", e))?;
+            }
+        }
+        Ok(path)
+    }
+
+    /// Characters left out of the PDFs written so far because no font could draw them.
+    pub fn missing_chars(&self) -> Vec<char> {
+        self.missing_chars.iter().copied().collect()
+    }
+}
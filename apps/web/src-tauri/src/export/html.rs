@@ -0,0 +1,170 @@
+use app_lib::{HtmlExportOptions, ImageExportMode, Note};
+use pulldown_cmark::{CodeBlockKind, CowStr, Event, Options, Parser, Tag, TagEnd};
+use regex::{Captures, Regex};
+use std::fs;
+use std::path::Path;
+use std::sync::OnceLock;
+use syntect::highlighting::{Theme, ThemeSet};
+use syntect::html::highlighted_html_for_string;
+use syntect::parsing::SyntaxSet;
+
+use super::{escape_html, image_data_url, rewrite_image_sources, unescape_html};
+
+pub const DEFAULT_THEME: &str = "InspiredGitHub";
+
+fn syntax_set() -> &'static SyntaxSet {
+    static SET: OnceLock<SyntaxSet> = OnceLock::new();
+    SET.get_or_init(SyntaxSet::load_defaults_newlines)
+}
+
+fn theme_set() -> &'static ThemeSet {
+    static SET: OnceLock<ThemeSet> = OnceLock::new();
+    SET.get_or_init(ThemeSet::load_defaults)
+}
+
+pub fn available_themes() -> Vec<String> {
+    theme_set().themes.keys().cloned().collect()
+}
+
+pub fn resolve_theme(name: Option<&str>) -> Result<&'static Theme, String> {
+    let name = name.unwrap_or(DEFAULT_THEME);
+    theme_set()
+        .themes
+        .get(name)
+        .ok_or_else(|| format!("Unknown theme: {}", name))
+}
+
+pub fn highlight_code(code: &str, lang: Option<&str>, theme: &Theme) -> String {
+    let ss = syntax_set();
+    let syntax = lang
+        .map(|l| l.trim())
+        .filter(|l| !l.is_empty())
+        .and_then(|l| ss.find_syntax_by_token(l))
+        .unwrap_or_else(|| ss.find_syntax_plain_text());
+    highlighted_html_for_string(code, ss, syntax, theme)
+        .unwrap_or_else(|_| format!("<pre><code>{}</code></pre>", escape_html(code)))
+}
+
+// Code blocks the editor stores as HTML rather than fenced Markdown
+fn html_code_block_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(r#"(?is)<pre[^>]*>\s*<code(?:\s+class="(?:language-)?([^"\s]*)[^"]*")?[^>]*>(.*?)</code>\s*</pre>"#).unwrap()
+    })
+}
+
+/// Renders note content (Markdown, possibly with inline editor HTML) to an HTML
+/// fragment with server-side highlighted code blocks.
+pub fn render_content(content: &str, theme: &Theme) -> String {
+    let mut options = Options::empty();
+    options.insert(Options::ENABLE_TABLES);
+    options.insert(Options::ENABLE_STRIKETHROUGH);
+    options.insert(Options::ENABLE_TASKLISTS);
+    options.insert(Options::ENABLE_FOOTNOTES);
+
+    let mut events: Vec<Event> = Vec::new();
+    let mut code: Option<(Option<String>, String)> = None;
+    for event in Parser::new_ext(content, options) {
+        match event {
+            Event::Start(Tag::CodeBlock(kind)) => {
+                let lang = match kind {
+                    CodeBlockKind::Fenced(info) => info.split_whitespace().next().map(|s| s.to_string()),
+                    CodeBlockKind::Indented => None,
+                };
+                code = Some((lang, String::new()));
+            }
+            Event::Text(text) if code.is_some() => {
+                if let Some((_, buf)) = code.as_mut() { buf.push_str(&text); }
+            }
+            Event::End(TagEnd::CodeBlock) => {
+                if let Some((lang, buf)) = code.take() {
+                    events.push(Event::Html(CowStr::from(highlight_code(&buf, lang.as_deref(), theme))));
+                }
+            }
+            other => events.push(other),
+        }
+    }
+    let mut body = String::new();
+    pulldown_cmark::html::push_html(&mut body, events.into_iter());
+
+    html_code_block_regex()
+        .replace_all(&body, |caps: &Captures| {
+            let lang = caps.get(1).map(|m| m.as_str());
+            highlight_code(&unescape_html(&caps[2]), lang, theme)
+        })
+        .to_string()
+}
+
+fn theme_colors(theme: &Theme) -> (String, String) {
+    let to_css = |c: syntect::highlighting::Color| format!("#{:02x}{:02x}{:02x}", c.r, c.g, c.b);
+    let bg = theme.settings.background.map(to_css).unwrap_or_else(|| "#ffffff".to_string());
+    let fg = theme.settings.foreground.map(to_css).unwrap_or_else(|| "#1f2328".to_string());
+    (bg, fg)
+}
+
+/// Builds a standalone HTML document for the note. Images from the images directory
+/// are inlined or copied into `<stem>_files/` next to `output` depending on the options.
+pub fn render_note_document(note: &Note, options: &HtmlExportOptions, output: &Path) -> Result<String, String> {
+    match options.images {
+        ImageExportMode::Embed => render_note_document_with(note, options.theme.as_deref(), |p| image_data_url(p).ok()),
+        ImageExportMode::Copy => {
+            let stem = output.file_stem().and_then(|s| s.to_str()).unwrap_or("note");
+            let assets_name = format!("{}_files", stem);
+            let assets_dir = output.with_file_name(&assets_name);
+            render_note_document_with(note, options.theme.as_deref(), |p| {
+                let name = p.file_name()?.to_str()?.to_string();
+                fs::create_dir_all(&assets_dir).ok()?;
+                fs::copy(p, assets_dir.join(&name)).ok()?;
+                Some(format!("{}/{}", assets_name, name.replace(' ', "%20")))
+            })
+        }
+    }
+}
+
+/// Same as `render_note_document`, with the image `src` rewriting left to the caller.
+pub fn render_note_document_with(note: &Note, theme: Option<&str>, rewrite: impl FnMut(&Path) -> Option<String>) -> Result<String, String> {
+    let theme = resolve_theme(theme)?;
+    let body = rewrite_image_sources(&render_content(&note.content, theme), rewrite);
+
+    let (code_bg, code_fg) = theme_colors(theme);
+    let mut html = String::new();
+    html.push_str("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n");
+    html.push_str(&format!("<title>{}</title>\n", escape_html(&note.title)));
+    html.push_str("<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n<style>\n");
+    html.push_str("body { font-family: -apple-system, BlinkMacSystemFont, \"Segoe UI\", Roboto, sans-serif; line-height: 1.6; color: #1f2328; max-width: 820px; margin: 2rem auto; padding: 0 1.5rem; }\n");
+    html.push_str("img { max-width: 100%; height: auto; }\n");
+    html.push_str("table { border-collapse: collapse; } th, td { border: 1px solid #d0d7de; padding: 4px 10px; }\n");
+    html.push_str("blockquote { margin: 0; padding-left: 1rem; border-left: 4px solid #d0d7de; color: #57606a; }\n");
+    html.push_str(&format!("pre {{ padding: 12px 16px; border-radius: 6px; overflow-x: auto; background: {}; color: {}; }}\n", code_bg, code_fg));
+    html.push_str("pre, code { font-family: ui-monospace, SFMono-Regular, Menlo, Consolas, monospace; font-size: 0.9em; }\n");
+    html.push_str(".note-meta { color: #57606a; font-size: 0.85em; border-top: 1px solid #d0d7de; margin-top: 2rem; padding-top: 0.5rem; }\n");
+    html.push_str(".note-tags span { display: inline-block; background: #eaeef2; border-radius: 10px; padding: 0 8px; margin-right: 4px; }\n");
+    html.push_str("</style>\n</head>\n<body>\n<article>\n");
+    html.push_str(&format!("<h1>{}</h1>\n", escape_html(&note.title)));
+    html.push_str(&body);
+
+    if !note.links.is_empty() {
+        html.push_str("<h2>Links</h2>\n<ul>\n");
+        for link in &note.links {
+            let l = escape_html(link);
+            html.push_str(&format!("<li><a href=\"{}\">{}</a></li>\n", l, l));
+        }
+        html.push_str("</ul>\n");
+    }
+
+    html.push_str("<footer class=\"note-meta\">\n");
+    if !note.tags.is_empty() {
+        html.push_str("<div class=\"note-tags\">");
+        for tag in &note.tags {
+            html.push_str(&format!("<span>#{}</span>", escape_html(tag)));
+        }
+        html.push_str("</div>\n");
+    }
+    html.push_str(&format!(
+        "<div>Created {} · Updated {}</div>\n",
+        note.created_at.format("%Y-%m-%d %H:%M:%S"),
+        note.updated_at.format("%Y-%m-%d %H:%M:%S")
+    ));
+    html.push_str("</footer>\n</article>\n</body>\n</html>\n");
+    Ok(html)
+}
@@ -0,0 +1,182 @@
+pub mod bulk;
+pub mod html;
+pub mod pdf;
+
+use base64::{engine::general_purpose, Engine as _};
+use regex::{Captures, Regex};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use crate::get_notes_dir;
+
+pub fn get_images_dir() -> Result<PathBuf, String> {
+    Ok(get_notes_dir()?.join("images"))
+}
+
+pub fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+pub fn unescape_html(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&#x27;", "'")
+        .replace("&nbsp;", " ")
+        .replace("&amp;", "&")
+}
+
+pub fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut out: Vec<u8> = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            let hex = std::str::from_utf8(&bytes[i + 1..i + 3])
+                .ok()
+                .and_then(|h| u8::from_str_radix(h, 16).ok());
+            if let Some(b) = hex {
+                out.push(b);
+                i += 3;
+                continue;
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).to_string()
+}
+
+/// Maps an image reference (absolute path, `file://` or Tauri asset URL, or a path
+/// relative to the notes folder) to a file inside the images directory. References
+/// that point anywhere else, including remote URLs and data URLs, resolve to `None`.
+pub fn resolve_image_src(src: &str) -> Option<PathBuf> {
+    let src = src.trim();
+    if src.is_empty() || src.starts_with("data:") {
+        return None;
+    }
+    let local = if let Some(rest) = src.strip_prefix("file://") {
+        percent_decode(rest)
+    } else if let Some(rest) = src
+        .strip_prefix("asset://localhost/")
+        .or_else(|| src.strip_prefix("http://asset.localhost/"))
+        .or_else(|| src.strip_prefix("https://asset.localhost/"))
+    {
+        percent_decode(rest)
+    } else if src.contains("://") {
+        return None;
+    } else {
+        percent_decode(src)
+    };
+
+    let images_dir = get_images_dir().ok()?;
+    let local_path = PathBuf::from(&local);
+    let candidate = if local_path.is_absolute() {
+        local_path
+    } else if local.starts_with("images/") || local.starts_with("images\\") {
+        get_notes_dir().ok()?.join(local_path)
+    } else {
+        images_dir.join(local_path)
+    };
+
+    let images_dir = images_dir.canonicalize().ok()?;
+    let candidate = candidate.canonicalize().ok()?;
+    if candidate.starts_with(&images_dir) && candidate.is_file() {
+        Some(candidate)
+    } else {
+        None
+    }
+}
+
+pub fn mime_for_path(path: &Path) -> &'static str {
+    match path.extension().and_then(|e| e.to_str()).map(|e| e.to_lowercase()).as_deref() {
+        Some("jpg") | Some("jpeg") => "image/jpeg",
+        Some("gif") => "image/gif",
+        Some("webp") => "image/webp",
+        Some("svg") => "image/svg+xml",
+        Some("bmp") => "image/bmp",
+        _ => "image/png",
+    }
+}
+
+pub fn image_data_url(path: &Path) -> Result<String, String> {
+    let bytes = fs::read(path)
+        .map_err(|e| format!("Failed to read image: {}", e))?;
+    let b64 = general_purpose::STANDARD.encode(bytes);
+    Ok(format!("data:{};base64,{}", mime_for_path(path), b64))
+}
+
+fn img_tag_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"(?is)<img\b[^>]*>").unwrap())
+}
+
+fn src_attr_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r#"(?i)\s(src|data-path)\s*=\s*("[^"]*"|'[^']*')"#).unwrap())
+}
+
+/// Rewrites the `src` of every `<img>` that references the images directory.
+/// `rewrite` receives the resolved file and returns the new src, or `None` to keep it.
+pub fn rewrite_image_sources(html: &str, mut rewrite: impl FnMut(&Path) -> Option<String>) -> String {
+    img_tag_regex()
+        .replace_all(html, |tag_caps: &Captures| {
+            let tag = &tag_caps[0];
+            // Prefer the editor's persisted path over an inline data URL
+            let mut resolved: Option<PathBuf> = None;
+            for attr in src_attr_regex().captures_iter(tag) {
+                let value = unescape_html(attr[2].trim_matches(|c| c == '"' || c == '\''));
+                if let Some(p) = resolve_image_src(&value) {
+                    let is_data_path = attr[1].eq_ignore_ascii_case("data-path");
+                    if resolved.is_none() || is_data_path {
+                        resolved = Some(p);
+                    }
+                }
+            }
+            let new_src = match resolved.as_deref().and_then(&mut rewrite) {
+                Some(s) => s,
+                None => return tag.to_string(),
+            };
+            src_attr_regex()
+                .replace_all(tag, |attr: &Captures| {
+                    if attr[1].eq_ignore_ascii_case("src") {
+                        format!(" src=\"{}\"", escape_html(&new_src))
+                    } else {
+                        String::new()
+                    }
+                })
+                .to_string()
+        })
+        .to_string()
+}
+
+fn markdown_image_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r#"!\[([^\]]*)\]\(\s*(<[^>]*>|[^)\s]+)(\s+"[^"]*")?\s*\)"#).unwrap())
+}
+
+/// Markdown counterpart of `rewrite_image_sources`: rewrites `![alt](src)` targets
+/// as well as any inline `<img>` tags left in the content by the editor.
+pub fn rewrite_markdown_images(markdown: &str, mut rewrite: impl FnMut(&Path) -> Option<String>) -> String {
+    let replaced = markdown_image_regex()
+        .replace_all(markdown, |caps: &Captures| {
+            let src = caps[2].trim_start_matches('<').trim_end_matches('>');
+            match resolve_image_src(src).as_deref().and_then(&mut rewrite) {
+                Some(new_src) => format!(
+                    "![{}]({}{})",
+                    &caps[1],
+                    new_src,
+                    caps.get(3).map(|m| m.as_str()).unwrap_or("")
+                ),
+                None => caps[0].to_string(),
+            }
+        })
+        .to_string();
+    rewrite_image_sources(&replaced, rewrite)
+}
@@ -0,0 +1,882 @@
+use app_lib::{Note, PdfExportOptions, PdfPageSize};
+use printpdf::image_crate::{self, DynamicImage, GenericImageView};
+use printpdf::path::PaintMode;
+use printpdf::{
+    Actions, BorderArray, BuiltinFont, Color, Image, ImageTransform, IndirectFontRef, LinkAnnotation, Mm,
+    PdfDocument, PdfDocumentReference, PdfLayerIndex, PdfLayerReference, PdfPageIndex, Rect, Rgb,
+};
+use pulldown_cmark::{Event, HeadingLevel, Options, Parser, Tag, TagEnd};
+use regex::Regex;
+use std::collections::{BTreeSet, HashMap};
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use super::{resolve_image_src, unescape_html};
+
+// Text is rendered with a Unicode TrueType font from the system when one is found,
+// else with the PDF base-14 fonts, which only cover the WinAnsi character set.
+// Characters the chosen fonts cannot draw are reported back to the caller.
+
+// Regular, bold, italic, bold italic and monospace faces of the fonts looked for;
+// a face that is missing falls back to the regular one
+const SYSTEM_FONTS: &[[&str; 5]] = &[
+    [
+        "/usr/share/fonts/truetype/dejavu/DejaVuSans.ttf",
+        "/usr/share/fonts/truetype/dejavu/DejaVuSans-Bold.ttf",
+        "/usr/share/fonts/truetype/dejavu/DejaVuSans-Oblique.ttf",
+        "/usr/share/fonts/truetype/dejavu/DejaVuSans-BoldOblique.ttf",
+        "/usr/share/fonts/truetype/dejavu/DejaVuSansMono.ttf",
+    ],
+    [
+        "/usr/share/fonts/TTF/DejaVuSans.ttf",
+        "/usr/share/fonts/TTF/DejaVuSans-Bold.ttf",
+        "/usr/share/fonts/TTF/DejaVuSans-Oblique.ttf",
+        "/usr/share/fonts/TTF/DejaVuSans-BoldOblique.ttf",
+        "/usr/share/fonts/TTF/DejaVuSansMono.ttf",
+    ],
+    [
+        "/usr/share/fonts/truetype/noto/NotoSans-Regular.ttf",
+        "/usr/share/fonts/truetype/noto/NotoSans-Bold.ttf",
+        "/usr/share/fonts/truetype/noto/NotoSans-Italic.ttf",
+        "/usr/share/fonts/truetype/noto/NotoSans-BoldItalic.ttf",
+        "/usr/share/fonts/truetype/noto/NotoSansMono-Regular.ttf",
+    ],
+    [
+        "/System/Library/Fonts/Supplemental/Arial Unicode.ttf",
+        "/System/Library/Fonts/Supplemental/Arial Bold.ttf",
+        "/System/Library/Fonts/Supplemental/Arial Italic.ttf",
+        "/System/Library/Fonts/Supplemental/Arial Bold Italic.ttf",
+        "/System/Library/Fonts/Supplemental/Courier New.ttf",
+    ],
+    [
+        "C:\\Windows\\Fonts\\arial.ttf",
+        "C:\\Windows\\Fonts\\arialbd.ttf",
+        "C:\\Windows\\Fonts\\ariali.ttf",
+        "C:\\Windows\\Fonts\\arialbi.ttf",
+        "C:\\Windows\\Fonts\\consola.ttf",
+    ],
+];
+
+const PT_TO_MM: f32 = 0.352_778;
+const BODY_SIZE: f32 = 11.0;
+const CODE_SIZE: f32 = 9.0;
+const META_SIZE: f32 = 9.0;
+const LINE_FACTOR: f32 = 1.45;
+const IMAGE_SCREEN_DPI: f32 = 96.0;
+
+#[derive(Clone, Default)]
+struct Span {
+    text: String,
+    bold: bool,
+    italic: bool,
+    code: bool,
+    link: Option<String>,
+}
+
+enum Block {
+    Heading(u8, Vec<Span>),
+    Paragraph { spans: Vec<Span>, indent: usize, marker: Option<String>, quote: bool },
+    Code(String),
+    Image(PathBuf),
+    Rule,
+}
+
+fn page_dimensions(size: PdfPageSize) -> (f32, f32) {
+    match size {
+        PdfPageSize::A4 => (210.0, 297.0),
+        PdfPageSize::A5 => (148.0, 210.0),
+        PdfPageSize::Letter => (215.9, 279.4),
+        PdfPageSize::Legal => (215.9, 355.6),
+    }
+}
+
+// Approximate Helvetica advance widths (in em); Courier is fixed at 0.6 em. Only
+// used for the base-14 fonts and for characters an embedded font lacks.
+fn char_width_em(ch: char, mono: bool) -> f32 {
+    if mono { return 0.6; }
+    match ch {
+        'i' | 'j' | 'l' | '\'' | '|' | '.' | ',' | ':' | ';' | '!' => 0.25,
+        'f' | 't' | 'r' | 'I' | ' ' | '(' | ')' | '[' | ']' | '-' | '"' | '/' => 0.33,
+        'm' | 'w' | 'M' | 'W' | '@' | '%' => 0.85,
+        c if c.is_ascii_uppercase() => 0.68,
+        c if c.is_ascii_digit() => 0.56,
+        // CJK ideographs, kana, hangul and full-width forms
+        '\u{2e80}'..='\u{9fff}' | '\u{ac00}'..='\u{d7a3}' | '\u{f900}'..='\u{faff}' | '\u{ff00}'..='\u{ff60}' => 1.0,
+        _ => 0.54,
+    }
+}
+
+fn html_break_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"(?i)<br\s*/?>|</(?:p|div|li|h[1-6]|pre|blockquote|tr)>").unwrap())
+}
+
+fn html_img_src_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r#"(?is)<img\b[^>]*?\s(?:data-path|src)\s*=\s*["']([^"']+)["']"#).unwrap())
+}
+
+fn html_tag_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"(?s)<[^>]+>").unwrap())
+}
+
+struct BlockBuilder {
+    blocks: Vec<Block>,
+    spans: Vec<Span>,
+    heading: Option<u8>,
+    bold: usize,
+    italic: usize,
+    links: Vec<String>,
+    lists: Vec<Option<u64>>,
+    marker: Option<String>,
+    quote: usize,
+    code: Option<String>,
+    in_image: bool,
+    include_images: bool,
+}
+
+impl BlockBuilder {
+    fn push_text(&mut self, text: &str, code: bool) {
+        if text.is_empty() { return; }
+        self.spans.push(Span {
+            text: text.to_string(),
+            bold: self.bold > 0 || self.heading.is_some(),
+            italic: self.italic > 0,
+            code,
+            link: self.links.last().cloned(),
+        });
+    }
+
+    fn flush(&mut self) {
+        let has_text = self.spans.iter().any(|s| !s.text.trim().is_empty());
+        if !has_text {
+            self.spans.clear();
+            return;
+        }
+        let spans = std::mem::take(&mut self.spans);
+        match self.heading {
+            Some(level) => self.blocks.push(Block::Heading(level, spans)),
+            None => self.blocks.push(Block::Paragraph {
+                spans,
+                indent: self.lists.len(),
+                marker: self.marker.take(),
+                quote: self.quote > 0,
+            }),
+        }
+    }
+
+    fn push_image(&mut self, src: &str) {
+        if !self.include_images { return; }
+        if let Some(path) = resolve_image_src(src) {
+            self.flush();
+            self.blocks.push(Block::Image(path));
+        }
+    }
+
+    // Editor content may contain raw HTML; keep its images and text, drop the markup
+    fn push_html(&mut self, html: &str) {
+        for caps in html_img_src_regex().captures_iter(html) {
+            let src = unescape_html(&caps[1]);
+            self.push_image(&src);
+        }
+        let mut first = true;
+        for segment in html_break_regex().split(html) {
+            if !first { self.flush(); }
+            first = false;
+            let text = unescape_html(&html_tag_regex().replace_all(segment, ""));
+            let text = text.replace('\n', " ");
+            if !text.trim().is_empty() {
+                self.push_text(&text, false);
+            }
+        }
+    }
+}
+
+fn content_blocks(content: &str, include_images: bool) -> Vec<Block> {
+    let mut options = Options::empty();
+    options.insert(Options::ENABLE_TABLES);
+    options.insert(Options::ENABLE_STRIKETHROUGH);
+    options.insert(Options::ENABLE_TASKLISTS);
+
+    let mut b = BlockBuilder {
+        blocks: Vec::new(),
+        spans: Vec::new(),
+        heading: None,
+        bold: 0,
+        italic: 0,
+        links: Vec::new(),
+        lists: Vec::new(),
+        marker: None,
+        quote: 0,
+        code: None,
+        in_image: false,
+        include_images,
+    };
+
+    for event in Parser::new_ext(content, options) {
+        if let Some(buf) = b.code.as_mut() {
+            match event {
+                Event::Text(t) => buf.push_str(&t),
+                Event::End(TagEnd::CodeBlock) => {
+                    let code = b.code.take().unwrap_or_default();
+                    b.blocks.push(Block::Code(code));
+                }
+                _ => {}
+            }
+            continue;
+        }
+        if b.in_image {
+            if let Event::End(TagEnd::Image) = event { b.in_image = false; }
+            continue;
+        }
+        match event {
+            Event::Start(Tag::Heading { level, .. }) => {
+                b.flush();
+                b.heading = Some(match level {
+                    HeadingLevel::H1 => 1,
+                    HeadingLevel::H2 => 2,
+                    HeadingLevel::H3 => 3,
+                    _ => 4,
+                });
+            }
+            Event::End(TagEnd::Heading(_)) => {
+                b.flush();
+                b.heading = None;
+            }
+            Event::Start(Tag::BlockQuote(_)) => { b.flush(); b.quote += 1; }
+            Event::End(TagEnd::BlockQuote(_)) => { b.flush(); b.quote = b.quote.saturating_sub(1); }
+            Event::Start(Tag::List(start)) => { b.flush(); b.lists.push(start); }
+            Event::End(TagEnd::List(_)) => { b.flush(); b.lists.pop(); }
+            Event::Start(Tag::Item) => {
+                b.flush();
+                b.marker = Some(match b.lists.last_mut() {
+                    Some(Some(n)) => { let m = format!("{}.", n); *n += 1; m }
+                    _ => "•".to_string(),
+                });
+            }
+            Event::TaskListMarker(checked) => {
+                b.marker = Some(if checked { "[x]".to_string() } else { "[ ]".to_string() });
+            }
+            Event::End(TagEnd::Item) | Event::End(TagEnd::Paragraph) | Event::End(TagEnd::TableRow)
+            | Event::End(TagEnd::TableHead) => b.flush(),
+            Event::End(TagEnd::TableCell) => b.push_text("   ", false),
+            Event::Start(Tag::CodeBlock(_)) => {
+                b.flush();
+                b.code = Some(String::new());
+            }
+            Event::Start(Tag::Strong) => b.bold += 1,
+            Event::End(TagEnd::Strong) => b.bold = b.bold.saturating_sub(1),
+            Event::Start(Tag::Emphasis) => b.italic += 1,
+            Event::End(TagEnd::Emphasis) => b.italic = b.italic.saturating_sub(1),
+            Event::Start(Tag::Link { dest_url, .. }) => b.links.push(dest_url.to_string()),
+            Event::End(TagEnd::Link) => { b.links.pop(); }
+            Event::Start(Tag::Image { dest_url, .. }) => {
+                b.push_image(&dest_url);
+                b.in_image = true;
+            }
+            Event::Text(t) => b.push_text(&t, false),
+            Event::Code(t) => b.push_text(&t, true),
+            Event::SoftBreak => b.push_text(" ", false),
+            Event::HardBreak => b.flush(),
+            Event::Html(h) | Event::InlineHtml(h) => b.push_html(&h),
+            Event::Rule => { b.flush(); b.blocks.push(Block::Rule); }
+            _ => {}
+        }
+    }
+    b.flush();
+    b.blocks
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Face {
+    Regular,
+    Bold,
+    Italic,
+    BoldItalic,
+    Mono,
+}
+
+// Advance widths, in em, of the characters an embedded face can draw
+type Widths = HashMap<char, f32>;
+
+struct Fonts {
+    // Indexed by `Face`
+    faces: [IndirectFontRef; 5],
+    // Widths of the embedded faces; `None` for the base-14 fonts
+    widths: Option<[Widths; 5]>,
+}
+
+impl Fonts {
+    fn font(&self, face: Face) -> &IndirectFontRef {
+        &self.faces[face as usize]
+    }
+
+    fn can_draw(&self, ch: char, face: Face) -> bool {
+        match &self.widths {
+            Some(widths) => widths[face as usize].contains_key(&ch),
+            None => is_win_ansi(ch),
+        }
+    }
+
+    fn text_width_mm(&self, text: &str, size: f32, face: Face) -> f32 {
+        let mono = face == Face::Mono;
+        let em: f32 = match &self.widths {
+            Some(widths) => {
+                let widths = &widths[face as usize];
+                text.chars().map(|c| widths.get(&c).copied().unwrap_or_else(|| char_width_em(c, mono))).sum()
+            }
+            None => {
+                let bold = matches!(face, Face::Bold | Face::BoldItalic);
+                text.chars().map(|c| char_width_em(c, mono)).sum::<f32>() * if bold { 1.06 } else { 1.0 }
+            }
+        };
+        em * size * PT_TO_MM
+    }
+
+    // Breaks a word wider than `max_w` into pieces that each fit, at least one
+    // character per piece
+    fn split_to_width(&self, word: &str, size: f32, face: Face, max_w: f32) -> Vec<String> {
+        let mut pieces = Vec::new();
+        let mut piece = String::new();
+        for ch in word.chars() {
+            piece.push(ch);
+            if piece.chars().count() > 1 && self.text_width_mm(&piece, size, face) > max_w {
+                piece.pop();
+                pieces.push(std::mem::take(&mut piece));
+                piece.push(ch);
+            }
+        }
+        pieces.push(piece);
+        pieces
+    }
+}
+
+// Windows-1252, the encoding the base-14 fonts are written with
+fn is_win_ansi(ch: char) -> bool {
+    matches!(ch, ' '..='~' | '\u{a0}'..='\u{ff}') || "€‚ƒ„…†‡ˆ‰Š‹ŒŽ‘’“”•–—˜™š›œžŸ".contains(ch)
+}
+
+fn font_widths(bytes: &[u8]) -> Option<Widths> {
+    let face = ttf_parser::Face::parse(bytes, 0).ok()?;
+    let units_per_em = face.units_per_em() as f32;
+    let mut widths = HashMap::new();
+    for subtable in face.tables().cmap?.subtables {
+        if subtable.is_unicode() {
+            subtable.codepoints(|c| {
+                let advance = subtable.glyph_index(c).and_then(|g| face.glyph_hor_advance(g));
+                if let (Some(ch), Some(advance)) = (char::from_u32(c), advance) {
+                    widths.insert(ch, advance as f32 / units_per_em);
+                }
+            });
+        }
+    }
+    Some(widths)
+}
+
+// `NOTES_PDF_FONT` names a TrueType font to use for every style
+fn font_families() -> Vec<[PathBuf; 5]> {
+    let mut families = Vec::new();
+    if let Ok(path) = std::env::var("NOTES_PDF_FONT") {
+        families.push([(); 5].map(|_| PathBuf::from(&path)));
+    }
+    families.extend(SYSTEM_FONTS.iter().map(|family| family.map(PathBuf::from)));
+    families
+}
+
+fn external_fonts(doc: &PdfDocumentReference) -> Option<Fonts> {
+    for family in font_families() {
+        let regular = match fs::read(&family[0]) {
+            Ok(bytes) => bytes,
+            Err(_) => continue,
+        };
+        let regular_widths = match font_widths(&regular) {
+            Some(widths) => widths,
+            None => continue,
+        };
+        let faces: Vec<Vec<u8>> = family.iter().map(|p| fs::read(p).unwrap_or_else(|_| regular.clone())).collect();
+        let added: Result<Vec<IndirectFontRef>, _> = faces.iter().map(|bytes| doc.add_external_font(bytes.as_slice())).collect();
+        let added = match added {
+            Ok(fonts) => fonts,
+            Err(e) => {
+                eprintln!("Failed to load PDF font {}: {}", family[0].display(), e);
+                continue;
+            }
+        };
+        let widths: Vec<Widths> = faces.iter().map(|bytes| font_widths(bytes).unwrap_or_else(|| regular_widths.clone())).collect();
+        return Some(Fonts {
+            faces: added.try_into().ok()?,
+            widths: Some(widths.try_into().ok()?),
+        });
+    }
+    None
+}
+
+fn builtin_fonts(doc: &PdfDocumentReference) -> Result<Fonts, String> {
+    let font = |f: BuiltinFont| doc.add_builtin_font(f).map_err(|e| format!("Failed to load PDF font: {}", e));
+    Ok(Fonts {
+        faces: [
+            font(BuiltinFont::Helvetica)?,
+            font(BuiltinFont::HelveticaBold)?,
+            font(BuiltinFont::HelveticaOblique)?,
+            font(BuiltinFont::HelveticaBoldOblique)?,
+            font(BuiltinFont::Courier)?,
+        ],
+        widths: None,
+    })
+}
+
+struct PdfWriter {
+    doc: PdfDocumentReference,
+    fonts: Fonts,
+    missing: BTreeSet<char>,
+    page_w: f32,
+    page_h: f32,
+    margin: f32,
+    pages: Vec<(PdfPageIndex, PdfLayerIndex)>,
+    layer: PdfLayerReference,
+    y: f32,          // distance from the top edge, in mm
+    fresh_page: bool,
+}
+
+fn rgb(r: f32, g: f32, b: f32) -> Color {
+    Color::Rgb(Rgb::new(r, g, b, None))
+}
+
+impl PdfWriter {
+    // `system_fonts: false` sticks to the base-14 fonts
+    fn new(title: &str, options: &PdfExportOptions, system_fonts: bool) -> Result<Self, String> {
+        let (page_w, page_h) = page_dimensions(options.page_size);
+        let margin = options.margin_mm.clamp(5.0, page_w / 4.0);
+        let (doc, page, layer_idx) = PdfDocument::new(title, Mm(page_w), Mm(page_h), "Layer 1");
+        let fonts = match system_fonts.then(|| external_fonts(&doc)).flatten() {
+            Some(fonts) => fonts,
+            None => builtin_fonts(&doc)?,
+        };
+        let layer = doc.get_page(page).get_layer(layer_idx);
+        Ok(PdfWriter {
+            doc,
+            fonts,
+            missing: BTreeSet::new(),
+            page_w,
+            page_h,
+            margin,
+            pages: vec![(page, layer_idx)],
+            layer,
+            y: margin,
+            fresh_page: true,
+        })
+    }
+
+    fn content_width(&self) -> f32 {
+        self.page_w - 2.0 * self.margin
+    }
+
+    fn new_page(&mut self) {
+        if self.fresh_page { return; }
+        let (page, layer_idx) = self.doc.add_page(Mm(self.page_w), Mm(self.page_h), "Layer 1");
+        self.layer = self.doc.get_page(page).get_layer(layer_idx);
+        self.pages.push((page, layer_idx));
+        self.y = self.margin;
+        self.fresh_page = true;
+    }
+
+    fn ensure_space(&mut self, height: f32) {
+        if self.y + height > self.page_h - self.margin {
+            self.new_page();
+        }
+    }
+
+    fn face_for(span: &Span) -> Face {
+        match (span.code, span.bold, span.italic) {
+            (true, _, _) => Face::Mono,
+            (false, true, true) => Face::BoldItalic,
+            (false, true, false) => Face::Bold,
+            (false, false, true) => Face::Italic,
+            _ => Face::Regular,
+        }
+    }
+
+    fn note_missing(&mut self, text: &str, face: Face) {
+        for ch in text.chars() {
+            if !self.fonts.can_draw(ch, face) {
+                self.missing.insert(ch);
+            }
+        }
+    }
+
+    // Draws on any layer; `draw_text` is the shorthand for the current one
+    fn draw_text_on(&mut self, layer: &PdfLayerReference, text: &str, size: f32, x: f32, baseline_from_top: f32, face: Face) {
+        self.note_missing(text, face);
+        layer.use_text(text, size, Mm(x), Mm(self.page_h - baseline_from_top), self.fonts.font(face));
+    }
+
+    fn draw_text(&mut self, text: &str, size: f32, x: f32, baseline_from_top: f32, face: Face) {
+        let layer = self.layer.clone();
+        self.draw_text_on(&layer, text, size, x, baseline_from_top, face);
+        self.fresh_page = false;
+    }
+
+    fn add_link(&self, url: &str, x: f32, top: f32, width: f32, height: f32) {
+        let rect = Rect::new(
+            Mm(x),
+            Mm(self.page_h - top - height),
+            Mm(x + width),
+            Mm(self.page_h - top),
+        );
+        self.layer.add_link_annotation(LinkAnnotation::new(
+            rect,
+            Some(BorderArray::Solid([0.0, 0.0, 0.0])),
+            None,
+            Actions::uri(url.to_string()),
+            None,
+        ));
+    }
+
+    /// Word-wraps styled spans into the content box, starting a new page as needed.
+    fn write_spans(&mut self, spans: &[Span], size: f32, indent: f32, marker: Option<&str>) {
+        let line_h = size * PT_TO_MM * LINE_FACTOR;
+        let left = self.margin + indent;
+        let right = self.page_w - self.margin;
+
+        // Split into words that keep their style; whitespace becomes a trailing gap.
+        // Words wider than the line are broken into pieces that fit.
+        let mut words: Vec<(String, &Span, bool)> = Vec::new();
+        for span in spans {
+            let face = Self::face_for(span);
+            let mut parts = span.text.split(' ').peekable();
+            while let Some(part) = parts.next() {
+                let gap = parts.peek().is_some();
+                if part.is_empty() {
+                    if let Some(last) = words.last_mut() { last.2 = true; }
+                    continue;
+                }
+                if self.fonts.text_width_mm(part, size, face) > right - left {
+                    let mut pieces = self.fonts.split_to_width(part, size, face, right - left).into_iter().peekable();
+                    while let Some(piece) = pieces.next() {
+                        let last = pieces.peek().is_none();
+                        words.push((piece, span, gap && last));
+                    }
+                } else {
+                    words.push((part.to_string(), span, gap));
+                }
+            }
+        }
+        if words.is_empty() { return; }
+
+        self.ensure_space(line_h);
+        if let Some(m) = marker {
+            let w = self.fonts.text_width_mm(m, size, Face::Regular);
+            self.draw_text(m, size, left - w - 1.5, self.y + size * PT_TO_MM, Face::Regular);
+        }
+
+        let space_w = self.fonts.text_width_mm(" ", size, Face::Regular);
+        let mut x = left;
+        for (word, span, gap) in words {
+            let face = Self::face_for(span);
+            let w = self.fonts.text_width_mm(&word, size, face);
+            if x > left && x + w > right {
+                self.y += line_h;
+                self.ensure_space(line_h);
+                x = left;
+            }
+            let baseline = self.y + size * PT_TO_MM;
+            if span.link.is_some() {
+                self.layer.set_fill_color(rgb(0.05, 0.35, 0.8));
+            }
+            self.draw_text(&word, size, x, baseline, face);
+            if let Some(url) = &span.link {
+                self.layer.set_fill_color(rgb(0.0, 0.0, 0.0));
+                self.add_link(url, x, self.y, w, line_h);
+            }
+            x += w + if gap { space_w } else { 0.0 };
+        }
+        self.y += line_h;
+    }
+
+    fn write_code(&mut self, code: &str) {
+        let line_h = CODE_SIZE * PT_TO_MM * LINE_FACTOR;
+        let char_w = self.fonts.text_width_mm("m", CODE_SIZE, Face::Mono);
+        let max_chars = ((self.content_width() - 4.0) / char_w).max(10.0) as usize;
+        self.y += 1.0;
+        for raw_line in code.trim_end_matches('\n').split('\n') {
+            let line = raw_line.replace('\t', "    ");
+            let chars: Vec<char> = line.chars().collect();
+            let chunks: Vec<String> = if chars.is_empty() {
+                vec![String::new()]
+            } else {
+                chars.chunks(max_chars).map(|c| c.iter().collect()).collect()
+            };
+            for chunk in chunks {
+                self.ensure_space(line_h);
+                let top = self.y;
+                self.layer.set_fill_color(rgb(0.95, 0.95, 0.96));
+                self.layer.add_rect(
+                    Rect::new(
+                        Mm(self.margin),
+                        Mm(self.page_h - top - line_h),
+                        Mm(self.page_w - self.margin),
+                        Mm(self.page_h - top),
+                    )
+                    .with_mode(PaintMode::Fill),
+                );
+                self.layer.set_fill_color(rgb(0.0, 0.0, 0.0));
+                self.draw_text(&chunk, CODE_SIZE, self.margin + 2.0, top + CODE_SIZE * PT_TO_MM * 1.1, Face::Mono);
+                self.y += line_h;
+            }
+        }
+        self.y += 2.0;
+    }
+
+    fn write_image(&mut self, path: &Path) {
+        let img = match image_crate::open(path) {
+            Ok(img) => img,
+            Err(e) => {
+                eprintln!("Skipping image {:?} in PDF: {}", path, e);
+                return;
+            }
+        };
+        let (px_w, px_h) = img.dimensions();
+        if px_w == 0 || px_h == 0 { return; }
+        let max_w = self.content_width();
+        let max_h = self.page_h - 2.0 * self.margin;
+        let natural_w = px_w as f32 / IMAGE_SCREEN_DPI * 25.4;
+        let natural_h = px_h as f32 / IMAGE_SCREEN_DPI * 25.4;
+        let scale = (max_w / natural_w).min(max_h / natural_h).min(1.0);
+        let (w, h) = (natural_w * scale, natural_h * scale);
+
+        self.ensure_space(h + 2.0);
+        let dpi = px_w as f32 * 25.4 / w;
+        // Flatten alpha; transparent PNGs otherwise render with a black background
+        let rgb_img = DynamicImage::ImageRgb8(img.to_rgb8());
+        Image::from_dynamic_image(&rgb_img).add_to_layer(
+            self.layer.clone(),
+            ImageTransform {
+                translate_x: Some(Mm(self.margin)),
+                translate_y: Some(Mm(self.page_h - self.y - h)),
+                dpi: Some(dpi),
+                ..Default::default()
+            },
+        );
+        self.fresh_page = false;
+        self.y += h + 3.0;
+    }
+
+    fn write_rule(&mut self) {
+        self.ensure_space(4.0);
+        self.layer.set_fill_color(rgb(0.8, 0.8, 0.8));
+        let y = self.page_h - self.y - 2.0;
+        self.layer.add_rect(
+            Rect::new(Mm(self.margin), Mm(y - 0.2), Mm(self.page_w - self.margin), Mm(y)).with_mode(PaintMode::Fill),
+        );
+        self.layer.set_fill_color(rgb(0.0, 0.0, 0.0));
+        self.y += 4.0;
+    }
+
+    fn write_note(&mut self, note: &Note, options: &PdfExportOptions) {
+        let title = Span { text: note.title.clone(), bold: true, ..Default::default() };
+        self.write_spans(&[title], 20.0, 0.0, None);
+        self.y += 1.0;
+
+        if options.include_metadata {
+            let mut meta = format!(
+                "Created {} · Updated {}",
+                note.created_at.format("%Y-%m-%d %H:%M"),
+                note.updated_at.format("%Y-%m-%d %H:%M")
+            );
+            if !note.tags.is_empty() {
+                meta.push_str(&format!(" · Tags: {}", note.tags.join(", ")));
+            }
+            self.layer.set_fill_color(rgb(0.4, 0.4, 0.45));
+            self.write_spans(&[Span { text: meta, ..Default::default() }], META_SIZE, 0.0, None);
+            self.layer.set_fill_color(rgb(0.0, 0.0, 0.0));
+        }
+        self.y += 4.0;
+
+        for block in content_blocks(&note.content, options.include_images) {
+            match block {
+                Block::Heading(level, spans) => {
+                    let size = match level { 1 => 16.0, 2 => 14.0, 3 => 12.5, _ => BODY_SIZE };
+                    self.y += 2.0;
+                    self.ensure_space(size * PT_TO_MM * LINE_FACTOR * 2.0);
+                    self.write_spans(&spans, size, 0.0, None);
+                    self.y += 1.0;
+                }
+                Block::Paragraph { spans, indent, marker, quote } => {
+                    let indent_mm = indent as f32 * 6.0 + if quote { 5.0 } else { 0.0 };
+                    if quote { self.layer.set_fill_color(rgb(0.35, 0.35, 0.4)); }
+                    self.write_spans(&spans, BODY_SIZE, indent_mm, marker.as_deref());
+                    if quote { self.layer.set_fill_color(rgb(0.0, 0.0, 0.0)); }
+                    self.y += if indent > 0 { 0.5 } else { 2.0 };
+                }
+                Block::Code(code) => self.write_code(&code),
+                Block::Image(path) => self.write_image(&path),
+                Block::Rule => self.write_rule(),
+            }
+        }
+
+        if !note.links.is_empty() {
+            self.y += 2.0;
+            let heading = Span { text: "Links".to_string(), bold: true, ..Default::default() };
+            self.write_spans(&[heading], 14.0, 0.0, None);
+            for link in &note.links {
+                let span = Span { text: link.clone(), link: Some(link.clone()), ..Default::default() };
+                self.write_spans(&[span], BODY_SIZE, 6.0, Some("•"));
+            }
+        }
+    }
+
+    fn write_toc(&mut self, entries: &[(String, usize)], toc_pages: &[(PdfPageIndex, PdfLayerIndex)]) {
+        let line_h = BODY_SIZE * PT_TO_MM * LINE_FACTOR;
+        let mut page_iter = toc_pages.iter();
+        let mut y = self.page_h; // force a page switch on the first entry
+        let mut layer = self.layer.clone();
+        for (i, (title, page_no)) in entries.iter().enumerate() {
+            if y + line_h > self.page_h - self.margin {
+                let (page, layer_idx) = match page_iter.next() { Some(p) => *p, None => break };
+                layer = self.doc.get_page(page).get_layer(layer_idx);
+                y = self.margin;
+                if i == 0 {
+                    self.draw_text_on(&layer, "Contents", 20.0, self.margin, y + 20.0 * PT_TO_MM, Face::Bold);
+                    y += 20.0 * PT_TO_MM * LINE_FACTOR + 4.0;
+                }
+            }
+            let baseline = y + BODY_SIZE * PT_TO_MM;
+            let number = page_no.to_string();
+            let number_w = self.fonts.text_width_mm(&number, BODY_SIZE, Face::Regular);
+            let max_title_w = self.content_width() - number_w - 10.0;
+            let mut shown = title.clone();
+            while self.fonts.text_width_mm(&shown, BODY_SIZE, Face::Regular) > max_title_w && shown.chars().count() > 4 {
+                shown.pop();
+            }
+            if shown.len() < title.len() { shown.push('…'); }
+            self.draw_text_on(&layer, &shown, BODY_SIZE, self.margin, baseline, Face::Regular);
+            self.draw_text_on(&layer, &number, BODY_SIZE, self.page_w - self.margin - number_w, baseline, Face::Regular);
+            y += line_h;
+        }
+    }
+
+    fn write_page_numbers(&self) {
+        let total = self.pages.len();
+        for (i, (page, layer_idx)) in self.pages.iter().enumerate() {
+            let layer = self.doc.get_page(*page).get_layer(*layer_idx);
+            let label = format!("{} / {}", i + 1, total);
+            let w = self.fonts.text_width_mm(&label, 8.0, Face::Regular);
+            layer.set_fill_color(rgb(0.5, 0.5, 0.5));
+            layer.use_text(label, 8.0, Mm((self.page_w - w) / 2.0), Mm(self.margin / 2.0), self.fonts.font(Face::Regular));
+        }
+    }
+
+    fn save(self, path: &Path) -> Result<(), String> {
+        let file = File::create(path)
+            .map_err(|e| format!("Failed to create PDF file: {}", e))?;
+        self.doc
+            .save(&mut BufWriter::new(file))
+            .map_err(|e| format!("Failed to write PDF: {}", e))
+    }
+}
+
+/// Renders one note, or several notes with a table of contents, into a single PDF.
+/// Returns the characters no available font could draw; they are missing from the PDF.
+pub fn write_notes_pdf(notes: &[Note], path: &Path, options: &PdfExportOptions) -> Result<Vec<char>, String> {
+    write_pdf(notes, path, options, true)
+}
+
+fn write_pdf(notes: &[Note], path: &Path, options: &PdfExportOptions, system_fonts: bool) -> Result<Vec<char>, String> {
+    let doc_title = match notes {
+        [single] => single.title.clone(),
+        _ => format!("{} notes", notes.len()),
+    };
+    let mut writer = PdfWriter::new(&doc_title, options, system_fonts)?;
+
+    // Reserve pages for the table of contents; it is filled in once page numbers are known
+    let mut toc_pages: Vec<(PdfPageIndex, PdfLayerIndex)> = Vec::new();
+    if notes.len() > 1 {
+        let line_h = BODY_SIZE * PT_TO_MM * LINE_FACTOR;
+        let usable = writer.page_h - 2.0 * writer.margin - 12.0;
+        let per_page = ((usable / line_h).floor() as usize).max(1);
+        let needed = notes.len().div_ceil(per_page);
+        for _ in 0..needed {
+            writer.new_page();
+            writer.fresh_page = false;
+            toc_pages.push(*writer.pages.last().unwrap());
+        }
+    }
+
+    let mut entries: Vec<(String, usize)> = Vec::new();
+    for note in notes {
+        writer.new_page();
+        let (page, _) = *writer.pages.last().unwrap();
+        entries.push((note.title.clone(), writer.pages.len()));
+        writer.doc.add_bookmark(note.title.clone(), page);
+        writer.write_note(note, options);
+    }
+
+    if !toc_pages.is_empty() {
+        writer.write_toc(&entries, &toc_pages);
+    }
+    writer.write_page_numbers();
+    let missing = writer.missing.iter().copied().collect();
+    writer.save(path)?;
+    Ok(missing)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn note(title: &str, content: &str) -> Note {
+        serde_json::from_value(serde_json::json!({
+            "id": "note_pdf",
+            "title": title,
+            "content": content,
+            "links": [],
+            "created_at": "2024-01-01T00:00:00Z",
+            "updated_at": "2024-01-01T00:00:00Z",
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn reports_characters_no_font_can_draw() {
+        let path = std::env::temp_dir().join(format!("notes_pdf_test_{}.pdf", std::process::id()));
+        let notes = [note("Café", "Naïve text with `code` and 日本語"), note("Second 你好", "Plain")];
+        // The base-14 fonts, so the result does not depend on the fonts installed
+        let missing = write_pdf(&notes, &path, &PdfExportOptions::default(), false).unwrap();
+        let written = fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+        let _ = fs::remove_file(&path);
+
+        assert!(written > 0);
+        assert_eq!(missing, vec!['你', '好', '日', '本', '語']);
+    }
+
+    #[test]
+    fn table_of_contents_reports_missing_characters() {
+        let options = PdfExportOptions::default();
+        let mut writer = PdfWriter::new("TOC", &options, false).unwrap();
+        let toc_page = *writer.pages.last().unwrap();
+        writer.write_toc(&[("Ελληνικά".to_string(), 2)], &[toc_page]);
+        assert!(writer.missing.contains(&'Ε') && writer.missing.contains(&'λ'));
+    }
+
+    #[test]
+    fn long_words_are_broken_to_fit_the_line() {
+        let options = PdfExportOptions::default();
+        let writer = PdfWriter::new("Wrap", &options, false).unwrap();
+        let max_w = writer.content_width();
+        let word = "https://example.com/".to_string() + &"a".repeat(400);
+
+        let pieces = writer.fonts.split_to_width(&word, BODY_SIZE, Face::Regular, max_w);
+        assert!(pieces.len() > 1);
+        assert_eq!(pieces.concat(), word);
+        for piece in &pieces {
+            assert!(writer.fonts.text_width_mm(piece, BODY_SIZE, Face::Regular) <= max_w);
+        }
+        // Bold and monospace text is measured with its own widths
+        let mono = writer.fonts.text_width_mm("iiii", BODY_SIZE, Face::Mono);
+        assert!(mono > writer.fonts.text_width_mm("iiii", BODY_SIZE, Face::Regular));
+        assert!(writer.fonts.text_width_mm("word", BODY_SIZE, Face::Bold) > writer.fonts.text_width_mm("word", BODY_SIZE, Face::Regular));
+    }
+}
@@ -0,0 +1,742 @@
+use base64::{engine::general_purpose, Engine as _};
+use chrono::Utc;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use regex::{Captures, Regex};
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::Path;
+use std::sync::OnceLock;
+use zip::ZipArchive;
+
+use super::folder::{file_dates, normalize};
+use super::{html_to_markdown, markdown_url, new_note, ImportContext};
+use crate::export::{escape_html, percent_decode, unescape_html};
+use crate::markdown::parse_date;
+
+/// Whether `import_document` knows how to convert this file.
+pub fn is_document(path: &Path) -> bool {
+    matches!(
+        path.extension().and_then(|e| e.to_str()).map(|e| e.to_lowercase()).as_deref(),
+        Some("html" | "htm" | "xhtml" | "docx")
+    )
+}
+
+fn title_tag_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"(?is)<title\b[^>]*>(.*?)</title>").unwrap())
+}
+
+// Elements whose text must not end up in the note
+fn non_content_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(r"(?is)<!--.*?-->|<head\b.*?</head>|<script\b.*?</script>|<style\b.*?</style>|<noscript\b.*?</noscript>|<template\b.*?</template>").unwrap()
+    })
+}
+
+fn img_src_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r#"(?is)(<img\b[^>]*?\bsrc\s*=\s*)(?:"([^"]*)"|'([^']*)')"#).unwrap())
+}
+
+fn data_url_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"(?is)^data:image/([a-z0-9.+-]+);base64,(.*)$").unwrap())
+}
+
+fn leading_heading_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"^#{1,6}[ \t]+([^\n]+?)[ \t]*(?:\n|$)").unwrap())
+}
+
+fn first_heading_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"(?m)^#{1,6}[ \t]+([^\n]+?)[ \t]*$").unwrap())
+}
+
+// Moves inline (data URL) and local images into the images store; remote images stay as they are
+fn store_html_images(html: &str, base_dir: &Path, source: &str, ctx: &mut ImportContext) -> String {
+    img_src_regex()
+        .replace_all(html, |caps: &Captures| {
+            let src = unescape_html(caps.get(2).or(caps.get(3)).map(|m| m.as_str()).unwrap_or("").trim());
+            let stored = if let Some(data) = data_url_regex().captures(&src) {
+                let ext = match data[1].to_lowercase().as_str() {
+                    "jpeg" => "jpg".to_string(),
+                    "svg+xml" => "svg".to_string(),
+                    other => other.to_string(),
+                };
+                let cleaned: String = data[2].chars().filter(|c| !c.is_whitespace()).collect();
+                general_purpose::STANDARD
+                    .decode(cleaned)
+                    .map_err(|e| format!("Failed to decode inline image: {}", e))
+                    .and_then(|bytes| ctx.store_image(&bytes, &format!("image.{}", ext)))
+                    .map(Some)
+            } else if src.is_empty() || src.contains("://") && !src.starts_with("file://") {
+                Ok(None)
+            } else {
+                let local = percent_decode(src.trim_start_matches("file://"));
+                let path = base_dir.join(local.split(['?', '#']).next().unwrap_or(""));
+                ctx.copy_image(&path).map(Some)
+            };
+            match stored {
+                Ok(Some(path)) => format!("{}\"{}\"", &caps[1], escape_html(&markdown_url(&path))),
+                Ok(None) => caps[0].to_string(),
+                Err(e) => {
+                    ctx.skip(source, format!("Image {}: {}", src.chars().take(80).collect::<String>(), e));
+                    caps[0].to_string()
+                }
+            }
+        })
+        .to_string()
+}
+
+// Title from the document metadata, else the first heading; a leading heading
+// repeating the title is dropped from the body
+fn split_title(title: Option<String>, markdown: String) -> (Option<String>, String) {
+    let title = title
+        .map(|t| t.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|t| !t.is_empty())
+        .or_else(|| first_heading_regex().captures(&markdown).map(|c| c[1].trim().to_string()));
+    let mut body = markdown;
+    if let Some(caps) = leading_heading_regex().captures(&body) {
+        if title.as_deref() == Some(caps[1].trim()) {
+            let end = caps.get(0).map(|m| m.end()).unwrap_or(0);
+            body = body[end..].trim_start().to_string();
+        }
+    }
+    (title, body)
+}
+
+fn html_document(path: &Path, source: &str, ctx: &mut ImportContext) -> Result<(Option<String>, String), String> {
+    let bytes = fs::read(path)
+        .map_err(|e| format!("Failed to read file: {}", e))?;
+    let html = String::from_utf8_lossy(&bytes);
+    let title = title_tag_regex().captures(&html).map(|c| unescape_html(&c[1]));
+    let body = non_content_regex().replace_all(&html, "");
+    let base_dir = path.parent().unwrap_or(Path::new("."));
+    let body = store_html_images(&body, base_dir, source, ctx);
+    Ok(split_title(title, html_to_markdown(&body)))
+}
+
+fn attr(e: &BytesStart, name: &[u8]) -> Option<String> {
+    e.try_get_attribute(name)
+        .ok()
+        .flatten()
+        .and_then(|a| a.unescape_value().ok().map(|v| v.to_string()))
+}
+
+// w:b, w:i and friends are on unless their w:val says otherwise
+fn toggle_on(e: &BytesStart) -> bool {
+    !matches!(attr(e, b"w:val").as_deref(), Some("0" | "false" | "none"))
+}
+
+fn read_entry(archive: &mut ZipArchive<File>, names: &HashMap<String, String>, name: &str) -> Option<Vec<u8>> {
+    let actual = names.get(&name.to_lowercase())?;
+    let mut entry = archive.by_name(actual).ok()?;
+    let mut bytes = Vec::new();
+    entry.read_to_end(&mut bytes).ok()?;
+    Some(bytes)
+}
+
+fn read_xml(archive: &mut ZipArchive<File>, names: &HashMap<String, String>, name: &str) -> Option<String> {
+    read_entry(archive, names, name).map(|b| String::from_utf8_lossy(&b).to_string())
+}
+
+// Relationship id -> (target, external)
+fn parse_relationships(xml: &str) -> HashMap<String, (String, bool)> {
+    let mut rels = HashMap::new();
+    let mut reader = Reader::from_str(xml);
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(e)) | Ok(Event::Empty(e)) if e.local_name().as_ref() == b"Relationship" => {
+                if let (Some(id), Some(target)) = (attr(&e, b"Id"), attr(&e, b"Target")) {
+                    let external = attr(&e, b"TargetMode").as_deref() == Some("External");
+                    rels.insert(id, (target, external));
+                }
+            }
+            Ok(Event::Eof) | Err(_) => break,
+            _ => {}
+        }
+    }
+    rels
+}
+
+// Style id -> lowercase style name ("heading 1", "title", "quote", ...)
+fn parse_styles(xml: &str) -> HashMap<String, String> {
+    let mut styles = HashMap::new();
+    let mut reader = Reader::from_str(xml);
+    let mut current: Option<String> = None;
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(e)) if e.name().as_ref() == b"w:style" => current = attr(&e, b"w:styleId"),
+            Ok(Event::Empty(e)) if e.name().as_ref() == b"w:name" => {
+                if let (Some(id), Some(name)) = (current.clone(), attr(&e, b"w:val")) {
+                    styles.insert(id, name.to_lowercase());
+                }
+            }
+            Ok(Event::End(e)) if e.name().as_ref() == b"w:style" => current = None,
+            Ok(Event::Eof) | Err(_) => break,
+            _ => {}
+        }
+    }
+    styles
+}
+
+// (numId, level) -> ordered list?
+fn parse_numbering(xml: &str) -> HashMap<(String, u32), bool> {
+    let mut abstract_levels: HashMap<String, HashMap<u32, bool>> = HashMap::new();
+    let mut num_to_abstract: HashMap<String, String> = HashMap::new();
+    let mut reader = Reader::from_str(xml);
+    let mut abstract_id: Option<String> = None;
+    let mut level: Option<u32> = None;
+    let mut num_id: Option<String> = None;
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(e)) => match e.name().as_ref() {
+                b"w:abstractNum" => abstract_id = attr(&e, b"w:abstractNumId"),
+                b"w:lvl" => level = attr(&e, b"w:ilvl").and_then(|l| l.parse().ok()),
+                b"w:num" => num_id = attr(&e, b"w:numId"),
+                _ => {}
+            },
+            Ok(Event::Empty(e)) => match e.name().as_ref() {
+                b"w:numFmt" => {
+                    if let (Some(a), Some(l)) = (abstract_id.clone(), level) {
+                        let ordered = !matches!(attr(&e, b"w:val").as_deref(), Some("bullet" | "none") | None);
+                        abstract_levels.entry(a).or_default().insert(l, ordered);
+                    }
+                }
+                b"w:abstractNumId" => {
+                    if let (Some(n), Some(a)) = (num_id.clone(), attr(&e, b"w:val")) {
+                        num_to_abstract.insert(n, a);
+                    }
+                }
+                _ => {}
+            },
+            Ok(Event::End(e)) => match e.name().as_ref() {
+                b"w:abstractNum" => abstract_id = None,
+                b"w:lvl" => level = None,
+                b"w:num" => num_id = None,
+                _ => {}
+            },
+            Ok(Event::Eof) | Err(_) => break,
+            _ => {}
+        }
+    }
+    let mut numbering = HashMap::new();
+    for (num, abs) in num_to_abstract {
+        for (level, ordered) in abstract_levels.get(&abs).cloned().unwrap_or_default() {
+            numbering.insert((num.clone(), level), ordered);
+        }
+    }
+    numbering
+}
+
+fn heading_level(style_name: &str) -> Option<usize> {
+    if style_name == "title" {
+        return Some(1);
+    }
+    style_name
+        .strip_prefix("heading ")
+        .and_then(|n| n.trim().parse::<usize>().ok())
+        .map(|n| n.clamp(1, 6))
+}
+
+fn is_code_style(style_name: &str) -> bool {
+    ["code", "preformatted", "source", "verbatim"].iter().any(|s| style_name.contains(s))
+}
+
+#[derive(Default)]
+struct Paragraph {
+    style: String,
+    num_id: Option<String>,
+    level: u32,
+    html: String,
+}
+
+#[derive(Default)]
+struct Run {
+    bold: bool,
+    italic: bool,
+    strike: bool,
+    code: bool,
+    html: String,
+}
+
+/// Builds the HTML for a DOCX body: paragraphs become headings, list items, quotes,
+/// code blocks or plain paragraphs; tables keep their cell text.
+#[derive(Default)]
+struct DocxHtml {
+    out: String,
+    // Open lists, innermost last; true for ordered
+    lists: Vec<bool>,
+    code: Vec<String>,
+}
+
+impl DocxHtml {
+    fn close_code(&mut self) {
+        if !self.code.is_empty() {
+            self.out.push_str(&format!("<pre><code>{}</code></pre>\n", self.code.join("\n")));
+            self.code.clear();
+        }
+    }
+
+    fn close_lists(&mut self, depth: usize) {
+        while self.lists.len() > depth {
+            let ordered = self.lists.pop().unwrap_or(false);
+            self.out.push_str(if ordered { "</li></ol>\n" } else { "</li></ul>\n" });
+        }
+    }
+
+    fn list_item(&mut self, level: usize, ordered: bool, html: &str) {
+        self.close_code();
+        self.close_lists(level + 1);
+        if self.lists.len() == level + 1 {
+            if self.lists.last() == Some(&ordered) {
+                self.out.push_str("</li>\n");
+            } else {
+                self.close_lists(level);
+            }
+        }
+        // Skipped levels get an empty item so nesting stays valid
+        while self.lists.len() < level + 1 {
+            let is_target = self.lists.len() == level;
+            let list_ordered = is_target && ordered;
+            self.out.push_str(if list_ordered { "<ol>" } else { "<ul>" });
+            self.lists.push(list_ordered);
+            if !is_target {
+                self.out.push_str("<li>");
+            }
+        }
+        self.out.push_str(&format!("<li>{}", html));
+    }
+
+    fn block(&mut self, html: String) {
+        self.close_lists(0);
+        self.close_code();
+        self.out.push_str(&html);
+        self.out.push('\n');
+    }
+
+    fn finish(mut self) -> String {
+        self.close_lists(0);
+        self.close_code();
+        self.out
+    }
+}
+
+struct DocxConverter<'a, 'b> {
+    archive: &'a mut ZipArchive<File>,
+    names: &'a HashMap<String, String>,
+    rels: HashMap<String, (String, bool)>,
+    styles: HashMap<String, String>,
+    numbering: HashMap<(String, u32), bool>,
+    ctx: &'a mut ImportContext<'b>,
+    source: &'a str,
+}
+
+impl DocxConverter<'_, '_> {
+    fn image(&mut self, rel_id: &str) -> String {
+        let target = match self.rels.get(rel_id) {
+            Some((target, false)) => target.clone(),
+            _ => return String::new(),
+        };
+        let name = normalize("word", &target);
+        let file_name = target.rsplit('/').next().unwrap_or("image.png").to_string();
+        let bytes = match read_entry(self.archive, self.names, &name) {
+            Some(b) => b,
+            None => {
+                self.ctx.skip(self.source, format!("Missing image: {}", target));
+                return String::new();
+            }
+        };
+        match self.ctx.store_image(&bytes, &file_name) {
+            Ok(path) => format!("<img src=\"{}\" alt=\"\">", escape_html(&markdown_url(&path))),
+            Err(e) => {
+                self.ctx.skip(self.source, e);
+                String::new()
+            }
+        }
+    }
+
+    fn style_name(&self, id: &str) -> String {
+        self.styles.get(id).cloned().unwrap_or_else(|| id.to_lowercase())
+    }
+
+    fn paragraph_html(&self, p: &Paragraph, doc: &mut DocxHtml) {
+        let style = self.style_name(&p.style);
+        let text = p.html.trim();
+        if let Some(level) = heading_level(&style) {
+            if !text.is_empty() {
+                doc.block(format!("<h{0}>{1}</h{0}>", level, text));
+            }
+            return;
+        }
+        if is_code_style(&style) {
+            doc.close_lists(0);
+            doc.code.push(p.html.replace("<br>", "\n"));
+            return;
+        }
+        if let Some(num_id) = p.num_id.as_ref().filter(|n| n.as_str() != "0") {
+            let ordered = self.numbering.get(&(num_id.clone(), p.level)).copied().unwrap_or(false);
+            doc.list_item(p.level as usize, ordered, text);
+            return;
+        }
+        if style.starts_with("list bullet") || style.starts_with("list number") {
+            doc.list_item(0, style.starts_with("list number"), text);
+            return;
+        }
+        if text.is_empty() {
+            doc.close_code();
+            return;
+        }
+        if style.contains("quote") {
+            doc.block(format!("<blockquote><p>{}</p></blockquote>", text));
+        } else {
+            doc.block(format!("<p>{}</p>", text));
+        }
+    }
+
+    fn convert(&mut self, xml: &str) -> String {
+        let mut reader = Reader::from_str(xml);
+        let mut doc = DocxHtml::default();
+        let mut paragraph: Option<Paragraph> = None;
+        let mut run: Option<Run> = None;
+        let mut in_text = false;
+        let mut links: Vec<bool> = Vec::new();
+        // Table nesting depth; nested tables are flattened into their cell
+        let mut table_depth = 0usize;
+        let mut rows: Vec<Vec<String>> = Vec::new();
+        let mut cell: Option<String> = None;
+
+        loop {
+            let event = match reader.read_event() {
+                Ok(Event::Eof) | Err(_) => break,
+                Ok(e) => e,
+            };
+            match event {
+                Event::Start(e) => match e.name().as_ref() {
+                    b"w:p" => paragraph = Some(Paragraph::default()),
+                    b"w:r" => run = Some(Run::default()),
+                    b"w:t" => in_text = true,
+                    b"w:hyperlink" => {
+                        // Internal anchors (bookmarks) have no relationship and stay plain text
+                        let href = attr(&e, b"r:id").and_then(|id| self.rels.get(&id).cloned()).map(|(t, _)| t);
+                        match (paragraph.as_mut(), href) {
+                            (Some(p), Some(href)) => {
+                                p.html.push_str(&format!("<a href=\"{}\">", escape_html(&href)));
+                                links.push(true);
+                            }
+                            _ => links.push(false),
+                        }
+                    }
+                    b"w:tbl" => {
+                        table_depth += 1;
+                        if table_depth == 1 {
+                            doc.close_lists(0);
+                            doc.close_code();
+                            rows.clear();
+                        }
+                    }
+                    b"w:tr" if table_depth == 1 => rows.push(Vec::new()),
+                    b"w:tc" if table_depth == 1 => cell = Some(String::new()),
+                    _ => {}
+                },
+                Event::Empty(e) => match e.name().as_ref() {
+                    b"w:pStyle" => {
+                        if let (Some(p), Some(v)) = (paragraph.as_mut(), attr(&e, b"w:val")) {
+                            p.style = v;
+                        }
+                    }
+                    b"w:numId" => {
+                        if let Some(p) = paragraph.as_mut() {
+                            p.num_id = attr(&e, b"w:val");
+                        }
+                    }
+                    b"w:ilvl" => {
+                        if let Some(p) = paragraph.as_mut() {
+                            p.level = attr(&e, b"w:val").and_then(|v| v.parse().ok()).unwrap_or(0).min(8);
+                        }
+                    }
+                    b"w:b" => if let Some(r) = run.as_mut() { r.bold = toggle_on(&e) },
+                    b"w:i" => if let Some(r) = run.as_mut() { r.italic = toggle_on(&e) },
+                    b"w:strike" | b"w:dstrike" => if let Some(r) = run.as_mut() { r.strike = toggle_on(&e) },
+                    b"w:rStyle" => {
+                        if let (Some(r), Some(v)) = (run.as_mut(), attr(&e, b"w:val")) {
+                            r.code = is_code_style(&self.style_name(&v));
+                        }
+                    }
+                    b"w:rFonts" => {
+                        let mono = ["Courier", "Consolas", "Menlo", "Monaco", "Mono"];
+                        let font = attr(&e, b"w:ascii").unwrap_or_default();
+                        if let Some(r) = run.as_mut() {
+                            r.code |= mono.iter().any(|m| font.contains(m));
+                        }
+                    }
+                    b"w:tab" => if let Some(r) = run.as_mut() { r.html.push(' ') },
+                    b"w:br" | b"w:cr" => if let Some(r) = run.as_mut() { r.html.push_str("<br>") },
+                    b"a:blip" | b"v:imagedata" => {
+                        let rel = attr(&e, b"r:embed").or_else(|| attr(&e, b"r:id"));
+                        if let Some(rel) = rel {
+                            let img = self.image(&rel);
+                            if let Some(p) = paragraph.as_mut() {
+                                p.html.push_str(&img);
+                            }
+                        }
+                    }
+                    _ => {}
+                },
+                Event::Text(t) if in_text => {
+                    if let (Some(r), Ok(text)) = (run.as_mut(), t.unescape()) {
+                        r.html.push_str(&escape_html(&text));
+                    }
+                }
+                Event::End(e) => match e.name().as_ref() {
+                    b"w:t" => in_text = false,
+                    b"w:r" => {
+                        if let (Some(r), Some(p)) = (run.take(), paragraph.as_mut()) {
+                            let mut html = r.html;
+                            if !html.trim().is_empty() && !is_code_style(&self.style_name(&p.style)) {
+                                if r.code { html = format!("<code>{}</code>", html); }
+                                if r.strike { html = format!("<del>{}</del>", html); }
+                                if r.italic { html = format!("<em>{}</em>", html); }
+                                if r.bold { html = format!("<strong>{}</strong>", html); }
+                            }
+                            p.html.push_str(&html);
+                        }
+                    }
+                    b"w:hyperlink" => {
+                        if let (Some(true), Some(p)) = (links.pop(), paragraph.as_mut()) {
+                            p.html.push_str("</a>");
+                        }
+                    }
+                    b"w:p" => {
+                        if let Some(p) = paragraph.take() {
+                            match cell.as_mut() {
+                                Some(c) => {
+                                    if !c.is_empty() && !p.html.trim().is_empty() {
+                                        c.push_str("<br>");
+                                    }
+                                    c.push_str(p.html.trim());
+                                }
+                                None => self.paragraph_html(&p, &mut doc),
+                            }
+                        }
+                    }
+                    b"w:tc" if table_depth == 1 => {
+                        if let (Some(c), Some(row)) = (cell.take(), rows.last_mut()) {
+                            row.push(c);
+                        }
+                    }
+                    b"w:tbl" => {
+                        table_depth = table_depth.saturating_sub(1);
+                        if table_depth == 0 {
+                            doc.block(table_html(&rows));
+                            rows.clear();
+                        }
+                    }
+                    _ => {}
+                },
+                _ => {}
+            }
+        }
+        doc.finish()
+    }
+}
+
+// The first row becomes the header, which is how Word tables are usually laid out
+fn table_html(rows: &[Vec<String>]) -> String {
+    let mut html = String::from("<table>");
+    for (i, row) in rows.iter().enumerate() {
+        let tag = if i == 0 { "th" } else { "td" };
+        html.push_str("<tr>");
+        for cell in row {
+            // Line breaks would split the Markdown table row
+            html.push_str(&format!("<{0}>{1}</{0}>", tag, cell.replace("<br>", " ")));
+        }
+        html.push_str("</tr>");
+    }
+    html.push_str("</table>");
+    html
+}
+
+fn core_property(xml: &str, name: &[u8]) -> Option<String> {
+    let mut reader = Reader::from_str(xml);
+    let mut inside = false;
+    let mut value = String::new();
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(e)) if e.name().as_ref() == name => inside = true,
+            Ok(Event::Text(t)) if inside => value.push_str(&t.unescape().unwrap_or_default()),
+            Ok(Event::End(e)) if e.name().as_ref() == name => break,
+            Ok(Event::Eof) | Err(_) => break,
+            _ => {}
+        }
+    }
+    Some(value.trim().to_string()).filter(|v| !v.is_empty())
+}
+
+type DocxResult = (Option<String>, String, Option<String>, Option<String>);
+
+// Returns (title, markdown, created, modified)
+fn docx_document(path: &Path, source: &str, ctx: &mut ImportContext) -> Result<DocxResult, String> {
+    let file = File::open(path)
+        .map_err(|e| format!("Failed to open file: {}", e))?;
+    let mut archive = ZipArchive::new(file)
+        .map_err(|e| format!("Not a DOCX file: {}", e))?;
+    let names: HashMap<String, String> = archive
+        .file_names()
+        .map(|n| (n.to_lowercase(), n.to_string()))
+        .collect();
+
+    let document = read_xml(&mut archive, &names, "word/document.xml")
+        .ok_or("Not a DOCX file: word/document.xml is missing")?;
+    let rels = read_xml(&mut archive, &names, "word/_rels/document.xml.rels")
+        .map(|x| parse_relationships(&x))
+        .unwrap_or_default();
+    let styles = read_xml(&mut archive, &names, "word/styles.xml")
+        .map(|x| parse_styles(&x))
+        .unwrap_or_default();
+    let numbering = read_xml(&mut archive, &names, "word/numbering.xml")
+        .map(|x| parse_numbering(&x))
+        .unwrap_or_default();
+    let core = read_xml(&mut archive, &names, "docProps/core.xml").unwrap_or_default();
+
+    let html = DocxConverter {
+        archive: &mut archive,
+        names: &names,
+        rels,
+        styles,
+        numbering,
+        ctx,
+        source,
+    }
+    .convert(&document);
+
+    let (title, markdown) = split_title(core_property(&core, b"dc:title"), html_to_markdown(&html));
+    Ok((
+        title,
+        markdown,
+        core_property(&core, b"dcterms:created"),
+        core_property(&core, b"dcterms:modified"),
+    ))
+}
+
+/// Imports a single HTML or DOCX file as a note. The title comes from `<title>` or
+/// the document properties, else the first heading, else the file name; images are
+/// copied into the images directory.
+pub fn import_document(path: &Path, ctx: &mut ImportContext) -> Result<(), String> {
+    let source = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    let ext = path.extension().and_then(|e| e.to_str()).map(|e| e.to_lowercase()).unwrap_or_default();
+    let (file_created, file_modified) = file_dates(path);
+
+    let (title, content, created, modified) = if ext == "docx" {
+        docx_document(path, &source, ctx)?
+    } else {
+        let (title, content) = html_document(path, &source, ctx)?;
+        (title, content, None, None)
+    };
+
+    let now = Utc::now();
+    let created_at = created.and_then(|d| parse_date(&d)).or(file_created).unwrap_or(now);
+    let updated_at = modified.and_then(|d| parse_date(&d)).or(file_modified).unwrap_or(created_at);
+    let title = title.unwrap_or_else(|| {
+        path.file_stem().and_then(|s| s.to_str()).unwrap_or("Imported Note").to_string()
+    });
+
+    let id = ctx.allocate_id(None);
+    let note = new_note(id, title, content, created_at, updated_at);
+    ctx.save_note(&note, &source);
+    ctx.progress(1, 1, &source);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::import::{fixture_dir, import_for_test, write_zip};
+
+    #[test]
+    fn html_document_keeps_content_and_local_images() {
+        let dir = fixture_dir("html_document");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("chart.png"), b"\x89PNG\r\n\x1a\nhtml test image").unwrap();
+        let path = dir.join("page.html");
+        fs::write(&path, r#"<html><head><title>Trip &amp; Plans</title><style>p { color: red }</style></head>
+<body><h1>Trip &amp; Plans</h1><script>alert(1)</script>
+<p>Pack <strong>light</strong> and read <a href="https://example.com/guide">the guide</a>.</p>
+<ul><li>Passport</li><li>Tickets</li></ul>
+<img src="chart.png"><img src="missing.png"></body></html>"#).unwrap();
+
+        let (notes, report) = import_for_test(&path, |ctx| import_document(&path, ctx));
+        let _ = fs::remove_dir_all(&dir);
+
+        assert_eq!(notes.len(), 1);
+        let note = &notes[0];
+        assert_eq!(note.title, "Trip & Plans");
+        // The heading repeating the title, the script and the style are gone
+        assert!(note.content.starts_with(
+            "Pack **light** and read [the guide](https://example.com/guide).\n\n* Passport\n* Tickets\n\n<img src=\"data:image/png;base64,"
+        ));
+        assert!(note.content.ends_with("![](missing.png)"));
+        assert!(!note.content.contains("alert"));
+        assert_eq!(report.images, 1);
+        assert_eq!(report.skipped.len(), 1);
+        assert!(report.skipped[0].reason.starts_with("Image missing.png: "));
+    }
+
+    #[test]
+    fn docx_document_maps_styles_lists_tables_and_images() {
+        let dir = fixture_dir("docx_document");
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("report.docx");
+        let document = r#"<w:document xmlns:w="w" xmlns:r="r" xmlns:a="a"><w:body>
+<w:p><w:pPr><w:pStyle w:val="Title"/></w:pPr><w:r><w:t>Quarterly report</w:t></w:r></w:p>
+<w:p><w:pPr><w:pStyle w:val="Heading2"/></w:pPr><w:r><w:t>Summary</w:t></w:r></w:p>
+<w:p><w:r><w:t xml:space="preserve">Sales were </w:t></w:r><w:r><w:rPr><w:b/></w:rPr><w:t>up</w:t></w:r><w:r><w:t xml:space="preserve"> &amp; see </w:t></w:r><w:hyperlink r:id="rLink"><w:r><w:t>the site</w:t></w:r></w:hyperlink></w:p>
+<w:p><w:pPr><w:numPr><w:ilvl w:val="0"/><w:numId w:val="1"/></w:numPr></w:pPr><w:r><w:t>First</w:t></w:r></w:p>
+<w:p><w:pPr><w:numPr><w:ilvl w:val="0"/><w:numId w:val="1"/></w:numPr></w:pPr><w:r><w:t>Second</w:t></w:r></w:p>
+<w:tbl><w:tr><w:tc><w:p><w:r><w:t>Region</w:t></w:r></w:p></w:tc><w:tc><w:p><w:r><w:t>Total</w:t></w:r></w:p></w:tc></w:tr>
+<w:tr><w:tc><w:p><w:r><w:t>North</w:t></w:r></w:p></w:tc><w:tc><w:p><w:r><w:t>42</w:t></w:r></w:p></w:tc></w:tr></w:tbl>
+<w:p><w:r><w:drawing><a:blip r:embed="rImage"/></w:drawing></w:r></w:p>
+</w:body></w:document>"#;
+        let rels = r#"<Relationships>
+<Relationship Id="rLink" Target="https://example.com/" TargetMode="External"/>
+<Relationship Id="rImage" Target="media/image1.png"/>
+</Relationships>"#;
+        let styles = r#"<w:styles xmlns:w="w">
+<w:style w:styleId="Title"><w:name w:val="Title"/></w:style>
+<w:style w:styleId="Heading2"><w:name w:val="heading 2"/></w:style>
+</w:styles>"#;
+        let numbering = r#"<w:numbering xmlns:w="w">
+<w:abstractNum w:abstractNumId="7"><w:lvl w:ilvl="0"><w:numFmt w:val="decimal"/></w:lvl></w:abstractNum>
+<w:num w:numId="1"><w:abstractNumId w:val="7"/></w:num>
+</w:numbering>"#;
+        let core = r#"<cp:coreProperties xmlns:cp="cp" xmlns:dcterms="dcterms"><dcterms:created>2024-05-06T07:08:09Z</dcterms:created></cp:coreProperties>"#;
+        write_zip(&path, &[
+            ("word/document.xml", document.as_bytes()),
+            ("word/_rels/document.xml.rels", rels.as_bytes()),
+            ("word/styles.xml", styles.as_bytes()),
+            ("word/numbering.xml", numbering.as_bytes()),
+            ("word/media/image1.png", b"\x89PNG\r\n\x1a\ndocx test image"),
+            ("docProps/core.xml", core.as_bytes()),
+        ]);
+
+        let (notes, report) = import_for_test(&path, |ctx| import_document(&path, ctx));
+        let _ = fs::remove_dir_all(&dir);
+
+        assert_eq!(notes.len(), 1);
+        let note = &notes[0];
+        assert_eq!(note.title, "Quarterly report");
+        assert!(note.content.starts_with(concat!(
+            "## Summary\n\nSales were **up** & see [the site](https://example.com/)\n\n",
+            "1. First\n2. Second\n\n",
+            "|Region|Total|\n|------|-----|\n|North | 42  |\n\n",
+            "<img src=\"data:image/png;base64,",
+        )));
+        assert_eq!(note.created_at, parse_date("2024-05-06T07:08:09Z").unwrap());
+        assert_eq!(report.images, 1);
+        assert!(report.skipped.is_empty());
+    }
+}
@@ -0,0 +1,370 @@
+use base64::{engine::general_purpose, Engine as _};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use md5::{Digest, Md5};
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use regex::{Captures, Regex};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+use std::sync::OnceLock;
+
+use super::{file_url, html_to_markdown, is_image_file, markdown_url, new_note, ImportContext};
+use crate::export::escape_html;
+
+#[derive(Default)]
+struct EnexResource {
+    data: String,
+    mime: String,
+    file_name: Option<String>,
+}
+
+#[derive(Default)]
+struct EnexNote {
+    title: String,
+    content: String,
+    created: String,
+    updated: String,
+    tags: Vec<String>,
+    source_url: String,
+    resources: Vec<EnexResource>,
+}
+
+// Stored attachment for an <en-media> hash: (markdown url, is image, display name)
+type MediaMap = HashMap<String, (String, bool, String)>;
+
+fn open_reader(path: &Path) -> Result<Reader<BufReader<File>>, String> {
+    let file = File::open(path)
+        .map_err(|e| format!("Failed to open file: {}", e))?;
+    let mut reader = Reader::from_reader(BufReader::new(file));
+    reader.config_mut().trim_text(false);
+    Ok(reader)
+}
+
+// Evernote writes timestamps as 20231231T235959Z
+fn parse_enex_date(text: &str) -> Option<DateTime<Utc>> {
+    NaiveDateTime::parse_from_str(text.trim(), "%Y%m%dT%H%M%SZ")
+        .ok()
+        .map(|d| d.and_utc())
+}
+
+fn extension_for_mime(mime: &str) -> &'static str {
+    match mime.trim().to_lowercase().as_str() {
+        "image/png" => "png",
+        "image/jpeg" | "image/jpg" => "jpg",
+        "image/gif" => "gif",
+        "image/webp" => "webp",
+        "image/svg+xml" => "svg",
+        "image/bmp" => "bmp",
+        "application/pdf" => "pdf",
+        "text/plain" => "txt",
+        _ => "bin",
+    }
+}
+
+fn preamble_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"(?is)<\?xml.*?\?>|<!DOCTYPE[^>]*>").unwrap())
+}
+
+fn en_note_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"(?i)<(/?)en-note\b[^>]*>").unwrap())
+}
+
+fn en_media_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"(?is)<en-media\b([^>]*?)/?>(?:\s*</en-media>)?").unwrap())
+}
+
+fn en_todo_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"(?is)<en-todo\b([^>]*?)/?>(?:\s*</en-todo>)?").unwrap())
+}
+
+fn en_crypt_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"(?is)<en-crypt\b.*?</en-crypt>").unwrap())
+}
+
+fn attr_regex(name: &str) -> Regex {
+    Regex::new(&format!(r#"(?i)\b{}\s*=\s*"([^"]*)""#, name)).unwrap()
+}
+
+fn hash_attr_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| attr_regex("hash"))
+}
+
+fn checked_attr_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| attr_regex("checked"))
+}
+
+// Checkbox markers survive the HTML conversion as plain words and are turned
+// into Markdown tasks afterwards
+const TODO_OPEN: &str = "XENTODOX0X";
+const TODO_DONE: &str = "XENTODOX1X";
+
+fn todo_line_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"(?m)^([ \t]*)(?:[*+-][ \t]+)?XENTODOX([01])X[ \t]*").unwrap())
+}
+
+/// Converts ENML (Evernote's XHTML dialect) to Markdown, replacing `<en-media>`
+/// with the stored attachments and `<en-todo>` with task checkboxes.
+fn enml_to_markdown(enml: &str, media: &MediaMap, encrypted: &mut bool) -> String {
+    let html = preamble_regex().replace_all(enml, "");
+    let html = en_note_regex().replace_all(&html, "<${1}div>");
+    let html = en_crypt_regex().replace_all(&html, |_: &Captures| {
+        *encrypted = true;
+        "<p>[Encrypted content not imported]</p>".to_string()
+    });
+    let html = en_todo_regex().replace_all(&html, |caps: &Captures| {
+        let checked = checked_attr_regex()
+            .captures(&caps[1])
+            .map(|c| c[1].eq_ignore_ascii_case("true"))
+            .unwrap_or(false);
+        format!("{} ", if checked { TODO_DONE } else { TODO_OPEN })
+    });
+    let html = en_media_regex().replace_all(&html, |caps: &Captures| {
+        let hash = hash_attr_regex().captures(&caps[1]).map(|c| c[1].to_lowercase());
+        match hash.and_then(|h| media.get(&h)) {
+            Some((url, true, name)) => format!("<img src=\"{}\" alt=\"{}\">", escape_html(url), escape_html(name)),
+            Some((url, false, name)) => format!("<a href=\"{}\">{}</a>", escape_html(url), escape_html(name)),
+            None => String::new(),
+        }
+    });
+
+    let markdown = html_to_markdown(&html);
+    let markdown = todo_line_regex().replace_all(&markdown, |caps: &Captures| {
+        format!("{}- [{}] ", &caps[1], if &caps[2] == "1" { "x" } else { " " })
+    });
+    markdown.replace(TODO_DONE, "[x]").replace(TODO_OPEN, "[ ]")
+}
+
+// Returns the title the note was saved under
+fn save_enex_note(enex: EnexNote, index: usize, ctx: &mut ImportContext) -> String {
+    let title = match enex.title.trim() {
+        "" => format!("Evernote note {}", index),
+        t => t.to_string(),
+    };
+
+    let mut media: MediaMap = HashMap::new();
+    for resource in &enex.resources {
+        let compact: String = resource.data.chars().filter(|c| !c.is_whitespace()).collect();
+        let bytes = match general_purpose::STANDARD.decode(compact) {
+            Ok(b) => b,
+            Err(e) => {
+                ctx.skip(&title, format!("Failed to decode attachment: {}", e));
+                continue;
+            }
+        };
+        let hash = format!("{:x}", Md5::digest(&bytes));
+        let ext = extension_for_mime(&resource.mime);
+        let name = match resource.file_name.as_deref().map(str::trim) {
+            Some(n) if n.contains('.') => n.to_string(),
+            Some(n) if !n.is_empty() => format!("{}.{}", n, ext),
+            _ => format!("evernote_{}.{}", &hash[..12], ext),
+        };
+        match ctx.store_attachment(&bytes, &name) {
+            Ok(stored) => {
+                let image = is_image_file(&stored);
+                let url = if image { markdown_url(&stored) } else { file_url(&stored) };
+                media.insert(hash, (url, image, name));
+            }
+            Err(e) => ctx.skip(&title, e),
+        }
+    }
+
+    let mut encrypted = false;
+    let content = enml_to_markdown(&enex.content, &media, &mut encrypted);
+    if encrypted {
+        ctx.skip(&title, "Encrypted sections were not imported");
+    }
+
+    let now = Utc::now();
+    let created_at = parse_enex_date(&enex.created).unwrap_or(now);
+    let updated_at = parse_enex_date(&enex.updated).unwrap_or(created_at);
+    let id = ctx.allocate_id(None);
+    let mut note = new_note(id, title.clone(), content, created_at, updated_at);
+    for tag in enex.tags {
+        let tag = tag.trim().to_string();
+        if !tag.is_empty() && !note.tags.contains(&tag) {
+            note.tags.push(tag);
+        }
+    }
+    let source_url = enex.source_url.trim();
+    if !source_url.is_empty() {
+        note.links.push(source_url.to_string());
+    }
+    ctx.save_note(&note, &title);
+    title
+}
+
+fn count_notes(path: &Path) -> usize {
+    let mut reader = match open_reader(path) { Ok(r) => r, Err(_) => return 0 };
+    let mut buf = Vec::new();
+    let mut count = 0;
+    loop {
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Start(e)) if e.local_name().as_ref() == b"note" => count += 1,
+            Ok(Event::Eof) | Err(_) => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+    count
+}
+
+/// Streams an Evernote `.enex` export one note at a time, so only the note being
+/// converted (and its attachments) is held in memory.
+pub fn import_enex(path: &Path, ctx: &mut ImportContext) -> Result<(), String> {
+    let total = count_notes(path);
+    let mut reader = open_reader(path)?;
+    let mut buf = Vec::new();
+    let mut stack: Vec<String> = Vec::new();
+    let mut note: Option<EnexNote> = None;
+    let mut resource: Option<EnexResource> = None;
+    let mut done = 0;
+
+    loop {
+        let event = match reader.read_event_into(&mut buf) {
+            Ok(event) => event,
+            Err(e) => {
+                ctx.skip(
+                    path.to_string_lossy().to_string(),
+                    format!("Failed to parse ENEX after {} note(s): {}", done, e),
+                );
+                break;
+            }
+        };
+        let text = match event {
+            Event::Start(e) => {
+                let name = String::from_utf8_lossy(e.local_name().as_ref()).to_string();
+                match name.as_str() {
+                    "note" => note = Some(EnexNote::default()),
+                    "resource" if note.is_some() => resource = Some(EnexResource::default()),
+                    // Tag text may arrive in several events; each tag starts empty
+                    "tag" => {
+                        if let Some(n) = note.as_mut() { n.tags.push(String::new()); }
+                    }
+                    _ => {}
+                }
+                stack.push(name);
+                None
+            }
+            Event::End(e) => {
+                match e.local_name().as_ref() {
+                    b"resource" => {
+                        if let (Some(n), Some(r)) = (note.as_mut(), resource.take()) {
+                            n.resources.push(r);
+                        }
+                    }
+                    b"note" => {
+                        if let Some(n) = note.take() {
+                            done += 1;
+                            let title = save_enex_note(n, done, ctx);
+                            ctx.progress(done, total.max(done), &title);
+                        }
+                    }
+                    _ => {}
+                }
+                stack.pop();
+                None
+            }
+            Event::Text(t) => Some(
+                t.unescape()
+                    .map(|s| s.to_string())
+                    .unwrap_or_else(|_| String::from_utf8_lossy(&t).to_string()),
+            ),
+            Event::CData(c) => Some(String::from_utf8_lossy(&c.into_inner()).to_string()),
+            Event::Eof => break,
+            _ => None,
+        };
+
+        if let (Some(text), Some(n)) = (text, note.as_mut()) {
+            let path: Vec<&str> = stack.iter().rev().take(3).map(|s| s.as_str()).collect();
+            match (path.first().copied(), path.get(1).copied(), path.get(2).copied()) {
+                (Some("title"), Some("note"), _) => n.title.push_str(&text),
+                (Some("content"), Some("note"), _) => n.content.push_str(&text),
+                (Some("created"), Some("note"), _) => n.created.push_str(&text),
+                (Some("updated"), Some("note"), _) => n.updated.push_str(&text),
+                (Some("tag"), Some("note"), _) => {
+                    if let Some(tag) = n.tags.last_mut() { tag.push_str(&text); }
+                }
+                (Some("source-url"), Some("note-attributes"), _) => n.source_url.push_str(&text),
+                (Some("data"), Some("resource"), _) => {
+                    if let Some(r) = resource.as_mut() { r.data.push_str(&text); }
+                }
+                (Some("mime"), Some("resource"), _) => {
+                    if let Some(r) = resource.as_mut() { r.mime.push_str(&text); }
+                }
+                (Some("file-name"), Some("resource-attributes"), Some("resource")) => {
+                    if let Some(r) = resource.as_mut() {
+                        r.file_name.get_or_insert_with(String::new).push_str(&text);
+                    }
+                }
+                _ => {}
+            }
+        }
+        buf.clear();
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::import::{fixture_dir, import_for_test};
+
+    #[test]
+    fn enex_note_becomes_markdown_with_tasks_and_attachments() {
+        let png = b"\x89PNG\r\n\x1a\nenex test image";
+        let pdf = b"%PDF-1.4 enex test receipt";
+        let enex = format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE en-export SYSTEM "http://xml.evernote.com/pub/evernote-export3.dtd">
+<en-export>
+<note>
+<title>Groceries &amp; more</title>
+<content><![CDATA[<?xml version="1.0" encoding="UTF-8"?><!DOCTYPE en-note SYSTEM "http://xml.evernote.com/pub/enml2.dtd">
+<en-note><div><b>Buy</b> these:</div><div><en-todo checked="true"/>milk</div><div><en-todo/>eggs</div><div><en-media type="image/png" hash="{:x}"/></div><div><en-media type="application/pdf" hash="{:x}"/></div></en-note>]]></content>
+<created>20240102T030405Z</created>
+<updated>20240103T000000Z</updated>
+<tag>home<!-- split -->work</tag>
+<tag>errands</tag>
+<note-attributes><source-url>https://example.com/list</source-url></note-attributes>
+<resource><data encoding="base64">{}</data><mime>image/png</mime><resource-attributes><file-name>photo.png</file-name></resource-attributes></resource>
+<resource><data encoding="base64">{}</data><mime>application/pdf</mime><resource-attributes><file-name>receipt.pdf</file-name></resource-attributes></resource>
+</note>
+</en-export>"#,
+            Md5::digest(png),
+            Md5::digest(pdf),
+            general_purpose::STANDARD.encode(png),
+            general_purpose::STANDARD.encode(pdf),
+        );
+        let dir = fixture_dir("enex");
+        let path = dir.join("export.enex");
+        std::fs::write(&path, enex).unwrap();
+
+        let (notes, report) = import_for_test(&path, |ctx| import_enex(&path, ctx));
+        let _ = std::fs::remove_dir_all(&dir);
+
+        assert_eq!(notes.len(), 1);
+        let note = &notes[0];
+        assert_eq!(note.title, "Groceries & more");
+        assert_eq!(note.tags, vec!["homework", "errands"]);
+        assert_eq!(note.links, vec!["https://example.com/list"]);
+        assert_eq!(note.created_at, parse_enex_date("20240102T030405Z").unwrap());
+        assert!(note.content.starts_with("**Buy** these:"));
+        assert!(note.content.contains("- [x] milk"));
+        assert!(note.content.contains("- [ ] eggs"));
+        assert!(note.content.contains("<img src=\"data:image/png;base64,"));
+        assert!(note.content.contains("photo.png\">"));
+        assert!(note.content.contains("[receipt.pdf](file:///"));
+        assert_eq!((report.notes.len(), report.images, report.attachments), (1, 1, 1));
+        assert!(report.skipped.is_empty());
+    }
+}
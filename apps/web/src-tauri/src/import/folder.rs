@@ -0,0 +1,397 @@
+use app_lib::{FolderImportOptions, FolderMapping};
+use chrono::{DateTime, Utc};
+use regex::{Captures, Regex};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use super::{file_url, is_image_file, markdown_url, new_note, note_href, ImportContext};
+use crate::export::percent_decode;
+use crate::markdown::{parse_markdown, ParsedMarkdown};
+
+struct VaultNote {
+    path: PathBuf,
+    rel: String,
+    dir: String,
+    id: String,
+    parsed: ParsedMarkdown,
+}
+
+fn is_note_file(path: &Path) -> bool {
+    matches!(
+        path.extension().and_then(|e| e.to_str()).map(|e| e.to_lowercase()).as_deref(),
+        Some("md" | "markdown" | "txt")
+    )
+}
+
+pub(super) fn rel_path(root: &Path, path: &Path) -> String {
+    path.strip_prefix(root)
+        .unwrap_or(path)
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().to_string())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+pub(super) fn collect_files(root: &Path, dir: &Path, files: &mut Vec<PathBuf>, ctx: &mut ImportContext) -> Result<(), String> {
+    let entries = fs::read_dir(dir)
+        .map_err(|e| format!("Failed to read folder: {}", e))?;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let name = entry.file_name().to_string_lossy().to_string();
+        let file_type = match entry.file_type() { Ok(t) => t, Err(_) => continue };
+        // .obsidian, .trash, .git and friends
+        if name.starts_with('.') {
+            if file_type.is_dir() {
+                ctx.skip(format!("{}/", rel_path(root, &path)), "Hidden folder");
+            }
+            continue;
+        }
+        if file_type.is_symlink() {
+            ctx.skip(rel_path(root, &path), "Symbolic links are not followed");
+        } else if file_type.is_dir() {
+            if let Err(e) = collect_files(root, &path, files, ctx) {
+                ctx.skip(format!("{}/", rel_path(root, &path)), e);
+            }
+        } else {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+// Resolves "a/./b/../c" against a vault-relative directory, lowercased for lookups
+pub(super) fn normalize(dir: &str, target: &str) -> String {
+    let mut parts: Vec<&str> = if target.starts_with('/') {
+        Vec::new()
+    } else {
+        dir.split('/').filter(|p| !p.is_empty()).collect()
+    };
+    for part in target.split(['/', '\\']) {
+        match part {
+            "" | "." => {}
+            ".." => { parts.pop(); }
+            p => parts.push(p),
+        }
+    }
+    parts.join("/").to_lowercase()
+}
+
+fn strip_note_ext(path: &str) -> &str {
+    for ext in [".md", ".markdown", ".txt"] {
+        if path.len() > ext.len() && path[path.len() - ext.len()..].eq_ignore_ascii_case(ext) {
+            return &path[..path.len() - ext.len()];
+        }
+    }
+    path
+}
+
+fn last_segment(path: &str) -> &str {
+    path.rsplit('/').next().unwrap_or(path)
+}
+
+/// Lookup tables for resolving wikilinks and relative links the way Obsidian does:
+/// relative to the linking note, then from the vault root, then by bare name.
+struct Resolver {
+    notes_by_path: HashMap<String, String>,
+    notes_by_name: HashMap<String, String>,
+    files_by_path: HashMap<String, PathBuf>,
+    files_by_name: HashMap<String, PathBuf>,
+}
+
+impl Resolver {
+    fn note(&self, dir: &str, target: &str) -> Option<&String> {
+        let target = strip_note_ext(target.trim());
+        let relative = normalize(dir, target);
+        let absolute = normalize("", target);
+        self.notes_by_path
+            .get(&relative)
+            .or_else(|| self.notes_by_path.get(&absolute))
+            .or_else(|| self.notes_by_name.get(last_segment(&absolute)))
+    }
+
+    fn file(&self, dir: &str, target: &str) -> Option<&PathBuf> {
+        let relative = normalize(dir, target.trim());
+        let absolute = normalize("", target.trim());
+        self.files_by_path
+            .get(&relative)
+            .or_else(|| self.files_by_path.get(&absolute))
+            .or_else(|| self.files_by_name.get(last_segment(&absolute)))
+    }
+}
+
+fn wikilink_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"(!?)\[\[([^\[\]\n]+?)\]\]").unwrap())
+}
+
+pub(super) fn md_link_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r#"(!?)\[([^\]\n]*)\]\(\s*(<[^>\n]+>|[^)\s]+)(\s+"[^"]*")?\s*\)"#).unwrap())
+}
+
+// Obsidian image sizes: ![[pic.png|300]] or ![[pic.png|300x200]]
+fn is_size_hint(text: &str) -> bool {
+    let t = text.trim();
+    !t.is_empty() && t.chars().all(|c| c.is_ascii_digit() || c == 'x')
+}
+
+/// Applies `convert` to the parts of `content` outside fenced code blocks.
+pub fn map_outside_code(content: &str, mut convert: impl FnMut(&str) -> String) -> String {
+    let mut out = String::with_capacity(content.len());
+    let mut segment = String::new();
+    let mut in_fence = false;
+    for line in content.split_inclusive('\n') {
+        let t = line.trim_start();
+        let is_fence = t.starts_with("```") || t.starts_with("~~~");
+        if in_fence {
+            out.push_str(line);
+            if is_fence { in_fence = false; }
+        } else if is_fence {
+            out.push_str(&convert(&segment));
+            segment.clear();
+            out.push_str(line);
+            in_fence = true;
+        } else {
+            segment.push_str(line);
+        }
+    }
+    out.push_str(&convert(&segment));
+    out
+}
+
+fn convert_links(note: &VaultNote, resolver: &Resolver, used: &mut HashSet<PathBuf>, ctx: &mut ImportContext) -> String {
+    let dir = note.dir.as_str();
+    let mut missing: Vec<String> = Vec::new();
+
+    let mut link_attachment = |path: &PathBuf, ctx: &mut ImportContext| -> Option<String> {
+        used.insert(path.clone());
+        match ctx.copy_attachment(path) {
+            Ok(stored) if is_image_file(&stored) => Some(markdown_url(&stored)),
+            Ok(stored) => Some(file_url(&stored)),
+            Err(e) => {
+                ctx.skip(note.rel.clone(), e);
+                None
+            }
+        }
+    };
+
+    let content = map_outside_code(&note.parsed.content, |segment| {
+        let segment = wikilink_regex().replace_all(segment, |caps: &Captures| {
+            let embed = !caps[1].is_empty();
+            let (target, alias) = match caps[2].split_once('|') {
+                Some((t, a)) => (t.trim(), Some(a.trim())),
+                None => (caps[2].trim(), None),
+            };
+            let name = target.split('#').next().unwrap_or("").trim();
+            let label = alias
+                .filter(|a| !a.is_empty() && !is_size_hint(a))
+                .unwrap_or(if name.is_empty() { target.trim_start_matches('#') } else { name })
+                .to_string();
+
+            if name.is_empty() {
+                return label;
+            }
+            if let Some(path) = resolver.file(dir, name) {
+                let image = is_image_file(path);
+                return match link_attachment(path, ctx) {
+                    Some(url) if embed && image => format!("![{}]({})", label, url),
+                    Some(url) => format!("[{}]({})", label, url),
+                    None => caps[0].to_string(),
+                };
+            }
+            if let Some(id) = resolver.note(dir, name) {
+                return format!("[{}]({})", label, note_href(id));
+            }
+            if embed {
+                missing.push(name.to_string());
+                return caps[0].to_string();
+            }
+            // Unresolved links become plain text, like an unlinked mention
+            label
+        });
+
+        md_link_regex()
+            .replace_all(&segment, |caps: &Captures| {
+                let raw = caps[3].trim_start_matches('<').trim_end_matches('>');
+                if raw.contains("://") || raw.starts_with('#') || raw.starts_with("mailto:") {
+                    return caps[0].to_string();
+                }
+                let target = percent_decode(raw.split('#').next().unwrap_or(raw));
+                let title = caps.get(4).map(|m| m.as_str()).unwrap_or("");
+                if let Some(path) = resolver.file(dir, &target) {
+                    return match link_attachment(path, ctx) {
+                        Some(url) => format!("{}[{}]({}{})", &caps[1], &caps[2], url, title),
+                        None => caps[0].to_string(),
+                    };
+                }
+                if let Some(id) = resolver.note(dir, &target) {
+                    return format!("[{}]({})", &caps[2], note_href(id));
+                }
+                if !caps[1].is_empty() {
+                    missing.push(target);
+                }
+                caps[0].to_string()
+            })
+            .to_string()
+    });
+
+    for name in missing {
+        ctx.skip(note.rel.clone(), format!("Missing attachment: {}", name));
+    }
+    content
+}
+
+pub(super) fn file_dates(path: &Path) -> (Option<DateTime<Utc>>, Option<DateTime<Utc>>) {
+    let meta = match fs::metadata(path) { Ok(m) => m, Err(_) => return (None, None) };
+    let modified = meta.modified().ok().map(DateTime::<Utc>::from);
+    let created = meta.created().ok().map(DateTime::<Utc>::from).or(modified);
+    (created, modified)
+}
+
+/// Imports every Markdown file below `root`. Folders become notebooks or tags,
+/// front matter is honoured, wikilinks and embeds are rewritten to note links and
+/// images in the images store. Unsupported and unreadable files end up in the report.
+pub fn import_folder(root: &Path, options: &FolderImportOptions, ctx: &mut ImportContext) -> Result<(), String> {
+    if !root.is_dir() {
+        return Err(format!("Not a folder: {}", root.display()));
+    }
+    let mut files = Vec::new();
+    collect_files(root, root, &mut files, ctx)?;
+    files.sort();
+
+    let mut resolver = Resolver {
+        notes_by_path: HashMap::new(),
+        notes_by_name: HashMap::new(),
+        files_by_path: HashMap::new(),
+        files_by_name: HashMap::new(),
+    };
+    let mut note_files = Vec::new();
+    for path in files {
+        let rel = rel_path(root, &path);
+        if is_note_file(&path) {
+            note_files.push((path, rel));
+        } else {
+            let key = rel.to_lowercase();
+            resolver.files_by_name.entry(last_segment(&key).to_string()).or_insert_with(|| path.clone());
+            resolver.files_by_path.insert(key, path);
+        }
+    }
+
+    // First pass: parse and assign ids so links can point at notes not saved yet
+    let mut notes: Vec<VaultNote> = Vec::new();
+    for (path, rel) in note_files {
+        let text = match fs::read_to_string(&path) {
+            Ok(t) => t,
+            Err(e) => {
+                ctx.skip(rel, format!("Failed to read file: {}", e));
+                continue;
+            }
+        };
+        let parsed = parse_markdown(&text);
+        let id = ctx.allocate_id(parsed.id.as_deref());
+        let key = strip_note_ext(&rel).to_lowercase();
+        resolver.notes_by_name.entry(last_segment(&key).to_string()).or_insert_with(|| id.clone());
+        resolver.notes_by_path.insert(key, id.clone());
+        let dir = rel.rsplit_once('/').map(|(d, _)| d.to_string()).unwrap_or_default();
+        notes.push(VaultNote { path, rel, dir, id, parsed });
+    }
+
+    let mut used: HashSet<PathBuf> = HashSet::new();
+    let total = notes.len();
+    for (i, vault_note) in notes.iter().enumerate() {
+        let content = convert_links(vault_note, &resolver, &mut used, ctx);
+        let parsed = &vault_note.parsed;
+        let (file_created, file_modified) = file_dates(&vault_note.path);
+        let now = Utc::now();
+        let created_at = parsed.created_at.or(file_created).unwrap_or(now);
+        let updated_at = parsed.updated_at.or(file_modified).unwrap_or(created_at);
+        let stem = vault_note.path.file_stem().and_then(|s| s.to_str()).unwrap_or("Imported Note");
+        let title = parsed.title.clone().unwrap_or_else(|| stem.to_string());
+
+        let mut note = new_note(vault_note.id.clone(), title, content, created_at, updated_at);
+        note.tags = parsed.tags.clone();
+        note.links = parsed.links.clone();
+        note.pinned = parsed.pinned;
+        note.favorite = parsed.favorite;
+        note.archived = parsed.archived;
+        note.notebook = parsed.notebook.clone();
+        if let Some(capture_type) = &parsed.capture_type { note.capture_type = Some(capture_type.clone()); }
+        note.source_app = parsed.source_app.clone();
+
+        let folders: Vec<&str> = vault_note.dir.split('/').filter(|p| !p.is_empty()).collect();
+        match options.folders {
+            FolderMapping::Notebook if note.notebook.is_none() && !folders.is_empty() => {
+                note.notebook = Some(folders.join("/"));
+            }
+            FolderMapping::Tag => {
+                for folder in folders {
+                    let tag = folder.split_whitespace().collect::<Vec<_>>().join("-");
+                    if !note.tags.contains(&tag) { note.tags.push(tag); }
+                }
+            }
+            _ => {}
+        }
+
+        ctx.save_note(&note, &vault_note.rel);
+        ctx.progress(i + 1, total, &vault_note.rel);
+    }
+
+    let mut leftovers: Vec<(&String, &PathBuf)> = resolver
+        .files_by_path
+        .iter()
+        .filter(|(_, path)| !used.contains(*path))
+        .collect();
+    leftovers.sort();
+    for (_, path) in leftovers {
+        let reason = if is_image_file(path) { "Attachment not referenced by any note" } else { "Unsupported file type" };
+        ctx.skip(rel_path(root, path), reason);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::import::{fixture_dir, import_for_test};
+
+    #[test]
+    fn vault_links_and_embeds_point_at_imported_notes_and_files() {
+        let dir = fixture_dir("folder");
+        fs::create_dir_all(dir.join("Projects")).unwrap();
+        fs::create_dir_all(dir.join(".obsidian")).unwrap();
+        fs::write(
+            dir.join("Projects").join("Plan.md"),
+            "See [[Ideas|my ideas]], ![[diagram.png|300]], [spec](../spec.pdf) and [[Nowhere]]\n\n```\n[[Ideas]]\n```\n",
+        ).unwrap();
+        fs::write(dir.join("Ideas.md"), "Back to [the plan](Projects/Plan.md)\n").unwrap();
+        fs::write(dir.join("diagram.png"), b"\x89PNG\r\n\x1a\nfolder test image").unwrap();
+        fs::write(dir.join("spec.pdf"), b"%PDF-1.4 folder test").unwrap();
+        fs::write(dir.join("stray.png"), b"\x89PNG\r\n\x1a\nunused").unwrap();
+        fs::write(dir.join("data.csv"), "a,b").unwrap();
+
+        let options = FolderImportOptions { folders: FolderMapping::Notebook, dry_run: false };
+        let (notes, report) = import_for_test(&dir, |ctx| import_folder(&dir, &options, ctx));
+        let _ = fs::remove_dir_all(&dir);
+
+        assert_eq!(notes.len(), 2);
+        let plan = notes.iter().find(|n| n.title == "Plan").unwrap();
+        let ideas = notes.iter().find(|n| n.title == "Ideas").unwrap();
+        assert!(plan.content.starts_with(&format!("See [my ideas](/note/{}), <img src=\"data:image/png;base64,", ideas.id)));
+        assert!(plan.content.contains("alt=\"diagram.png\""));
+        assert!(plan.content.contains(", [spec](file:///"));
+        assert!(plan.content.ends_with("/attachments/spec.pdf) and Nowhere\n\n```\n[[Ideas]]\n```"));
+        assert_eq!(ideas.content, format!("Back to [the plan](/note/{})", plan.id));
+        assert_eq!(plan.notebook.as_deref(), Some("Projects"));
+        assert_eq!(ideas.notebook, None);
+
+        let reasons: Vec<(&str, &str)> = report.skipped.iter().map(|s| (s.source.as_str(), s.reason.as_str())).collect();
+        assert_eq!(reasons, vec![
+            (".obsidian/", "Hidden folder"),
+            ("data.csv", "Unsupported file type"),
+            ("stray.png", "Attachment not referenced by any note"),
+        ]);
+        assert_eq!((report.images, report.attachments), (1, 1));
+    }
+}
@@ -0,0 +1,277 @@
+use chrono::{DateTime, Utc};
+use regex::{Captures, Regex};
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use super::folder::map_outside_code;
+use super::{file_url, is_image_file, markdown_url, new_note, note_href, remove_temp_dir, temp_import_dir, ImportContext};
+use crate::markdown::parse_date;
+
+// Joplin item types (the `type_` property)
+const TYPE_NOTE: &str = "1";
+const TYPE_FOLDER: &str = "2";
+const TYPE_RESOURCE: &str = "4";
+const TYPE_TAG: &str = "5";
+const TYPE_NOTE_TAG: &str = "6";
+
+/// One item of a RAW export: a title line, an optional body and a trailing block
+/// of `key: value` properties.
+struct JoplinItem {
+    title: String,
+    body: String,
+    props: HashMap<String, String>,
+    source: String,
+}
+
+impl JoplinItem {
+    fn prop(&self, key: &str) -> &str {
+        self.props.get(key).map(|s| s.as_str()).unwrap_or("")
+    }
+
+    fn date(&self, keys: &[&str]) -> Option<DateTime<Utc>> {
+        keys.iter().find_map(|k| parse_date(self.prop(k)))
+    }
+}
+
+fn prop_line_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"^([a-z_]+):\s?(.*)$").unwrap())
+}
+
+fn item_link_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r":/([0-9a-f]{32})\b").unwrap())
+}
+
+fn parse_item(text: &str, source: String) -> Option<JoplinItem> {
+    let lines: Vec<&str> = text.lines().collect();
+    let mut start = lines.len();
+    while start > 0 && prop_line_regex().is_match(lines[start - 1]) {
+        start -= 1;
+    }
+    let props: HashMap<String, String> = lines[start..]
+        .iter()
+        .filter_map(|l| prop_line_regex().captures(l))
+        .map(|c| (c[1].to_string(), c[2].trim().to_string()))
+        .collect();
+    if !props.contains_key("id") || !props.contains_key("type_") {
+        return None;
+    }
+    let head = &lines[..start];
+    let title = head.first().map(|l| l.trim().to_string()).unwrap_or_default();
+    let body = head.iter().skip(1).copied().collect::<Vec<_>>().join("\n").trim().to_string();
+    Some(JoplinItem { title, body, props, source })
+}
+
+fn folder_path(id: &str, folders: &HashMap<String, (String, String)>) -> Option<String> {
+    let mut parts = Vec::new();
+    let mut current = id.to_string();
+    // Bounded walk in case of a parent cycle
+    for _ in 0..64 {
+        match folders.get(&current) {
+            Some((title, parent)) => {
+                parts.push(title.clone());
+                current = parent.clone();
+            }
+            None => break,
+        }
+    }
+    if parts.is_empty() {
+        return None;
+    }
+    parts.reverse();
+    Some(parts.join("/"))
+}
+
+fn find_resource_file(resources_dir: &Path, item: &JoplinItem) -> Option<PathBuf> {
+    let id = item.prop("id");
+    let ext = item.prop("file_extension");
+    if !ext.is_empty() {
+        let p = resources_dir.join(format!("{}.{}", id, ext));
+        if p.is_file() { return Some(p); }
+    }
+    fs::read_dir(resources_dir).ok()?.flatten().map(|e| e.path()).find(|p| {
+        p.file_stem().and_then(|s| s.to_str()) == Some(id)
+    })
+}
+
+/// Imports a Joplin RAW export directory or a `.jex` archive (a tar of the same).
+pub fn import_joplin(path: &Path, ctx: &mut ImportContext) -> Result<(), String> {
+    if path.is_dir() {
+        return import_joplin_dir(path, ctx);
+    }
+    let file = File::open(path)
+        .map_err(|e| format!("Failed to open file: {}", e))?;
+    let temp = temp_import_dir("joplin")?;
+    // unpack() refuses entries that would land outside the target folder
+    let result = tar::Archive::new(file)
+        .unpack(&temp)
+        .map_err(|e| format!("Failed to unpack JEX archive: {}", e))
+        .and_then(|_| import_joplin_dir(&temp, ctx));
+    remove_temp_dir(&temp);
+    result
+}
+
+fn import_joplin_dir(root: &Path, ctx: &mut ImportContext) -> Result<(), String> {
+    let mut files: Vec<PathBuf> = fs::read_dir(root)
+        .map_err(|e| format!("Failed to read folder: {}", e))?
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.extension().and_then(|e| e.to_str()) == Some("md"))
+        .collect();
+    files.sort();
+
+    let mut notes: Vec<JoplinItem> = Vec::new();
+    let mut folders: HashMap<String, (String, String)> = HashMap::new();
+    let mut resources: HashMap<String, JoplinItem> = HashMap::new();
+    let mut tags: HashMap<String, String> = HashMap::new();
+    let mut note_tags: Vec<(String, String)> = Vec::new();
+
+    for path in files {
+        let source = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+        let text = match fs::read_to_string(&path) {
+            Ok(t) => t,
+            Err(e) => {
+                ctx.skip(source, format!("Failed to read file: {}", e));
+                continue;
+            }
+        };
+        let item = match parse_item(&text, source.clone()) {
+            Some(i) => i,
+            None => {
+                ctx.skip(source, "Not a Joplin item");
+                continue;
+            }
+        };
+        if item.prop("encryption_applied") == "1" {
+            ctx.skip(source, "Encrypted item; export with encryption disabled");
+            continue;
+        }
+        let id = item.prop("id").to_string();
+        match item.prop("type_") {
+            TYPE_NOTE => notes.push(item),
+            TYPE_FOLDER => { folders.insert(id, (item.title.clone(), item.prop("parent_id").to_string())); }
+            TYPE_RESOURCE => { resources.insert(id, item); }
+            TYPE_TAG => { tags.insert(id, item.title.clone()); }
+            TYPE_NOTE_TAG => note_tags.push((item.prop("note_id").to_string(), item.prop("tag_id").to_string())),
+            _ => {} // revisions, settings and other internal items
+        }
+    }
+
+    let mut new_ids: HashMap<String, String> = HashMap::new();
+    for note in &notes {
+        new_ids.insert(note.prop("id").to_string(), ctx.allocate_id(None));
+    }
+    let mut tags_by_note: HashMap<String, Vec<String>> = HashMap::new();
+    for (note_id, tag_id) in note_tags {
+        if let Some(tag) = tags.get(&tag_id) {
+            tags_by_note.entry(note_id).or_default().push(tag.clone());
+        }
+    }
+
+    let resources_dir = root.join("resources");
+    let mut resource_urls: HashMap<String, Result<String, String>> = HashMap::new();
+    let total = notes.len();
+    let now = Utc::now();
+
+    for (i, item) in notes.iter().enumerate() {
+        if item.prop("is_conflict") == "1" || !item.prop("deleted_time").trim_start_matches('0').is_empty() {
+            ctx.skip(item.source.clone(), "Conflict or deleted note");
+            ctx.progress(i + 1, total, &item.source);
+            continue;
+        }
+        let content = map_outside_code(&item.body, |segment| {
+            item_link_regex()
+                .replace_all(segment, |caps: &Captures| {
+                    let target = &caps[1];
+                    if let Some(id) = new_ids.get(target) {
+                        return note_href(id);
+                    }
+                    if let Some(resource) = resources.get(target) {
+                        let url = resource_urls.entry(target.to_string()).or_insert_with(|| {
+                            let file = find_resource_file(&resources_dir, resource)
+                                .ok_or_else(|| format!("Missing resource: {}", resource.title))?;
+                            let stored = ctx.copy_attachment(&file)?;
+                            Ok(if is_image_file(&stored) { markdown_url(&stored) } else { file_url(&stored) })
+                        });
+                        match url {
+                            Ok(url) => return url.clone(),
+                            Err(e) => ctx.skip(item.source.clone(), e.clone()),
+                        }
+                    }
+                    caps[0].to_string()
+                })
+                .to_string()
+        });
+
+        let created_at = item.date(&["user_created_time", "created_time"]).unwrap_or(now);
+        let updated_at = item.date(&["user_updated_time", "updated_time"]).unwrap_or(created_at);
+        let title = if item.title.is_empty() { "Untitled".to_string() } else { item.title.clone() };
+        let mut note = new_note(new_ids[item.prop("id")].clone(), title, content, created_at, updated_at);
+        note.notebook = folder_path(item.prop("parent_id"), &folders);
+        note.tags = tags_by_note.remove(item.prop("id")).unwrap_or_default();
+        let source_url = item.prop("source_url");
+        if !source_url.is_empty() {
+            note.links.push(source_url.to_string());
+        }
+        ctx.save_note(&note, &item.source);
+        ctx.progress(i + 1, total, &item.source);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::import::{fixture_dir, import_for_test};
+
+    const FOLDER: &str = "f0000000000000000000000000000001";
+    const PLAN: &str = "a0000000000000000000000000000001";
+    const OTHER: &str = "a0000000000000000000000000000002";
+    const CHART: &str = "b0000000000000000000000000000001";
+    const GONE: &str = "b0000000000000000000000000000002";
+    const TAG: &str = "c0000000000000000000000000000001";
+
+    #[test]
+    fn joplin_raw_export_rewrites_item_links() {
+        let dir = fixture_dir("joplin");
+        fs::create_dir_all(dir.join("resources")).unwrap();
+        let items = [
+            (FOLDER, format!("Work\n\nid: {}\nparent_id: \ntype_: 2", FOLDER)),
+            (PLAN, format!(
+                "Plan\n\nSee [Other](:/{}), ![chart](:/{}) and [report](:/{})\n\nid: {}\nparent_id: {}\ncreated_time: 2024-01-02T03:04:05.000Z\nsource_url: https://example.com/plan\ntype_: 1",
+                OTHER, CHART, GONE, PLAN, FOLDER
+            )),
+            (OTHER, format!("Other\n\nBack to [Plan](:/{})\n\nid: {}\nparent_id: {}\ntype_: 1", PLAN, OTHER, FOLDER)),
+            (CHART, format!("chart.png\n\nid: {}\nfile_extension: png\ntype_: 4", CHART)),
+            (GONE, format!("report.pdf\n\nid: {}\nfile_extension: pdf\ntype_: 4", GONE)),
+            (TAG, format!("urgent\n\nid: {}\ntype_: 5", TAG)),
+            ("d0000000000000000000000000000001", format!("id: d0000000000000000000000000000001\nnote_id: {}\ntag_id: {}\ntype_: 6", PLAN, TAG)),
+        ];
+        for (id, text) in items {
+            fs::write(dir.join(format!("{}.md", id)), text).unwrap();
+        }
+        fs::write(dir.join("resources").join(format!("{}.png", CHART)), b"\x89PNG\r\n\x1a\njoplin test image").unwrap();
+
+        let (notes, report) = import_for_test(&dir, |ctx| import_joplin(&dir, ctx));
+        let _ = fs::remove_dir_all(&dir);
+
+        assert_eq!(notes.len(), 2);
+        let plan = notes.iter().find(|n| n.title == "Plan").unwrap();
+        let other = notes.iter().find(|n| n.title == "Other").unwrap();
+        assert!(plan.content.starts_with(&format!("See [Other](/note/{}), <img src=\"data:image/png;base64,", other.id)));
+        // The resource file is missing, so its link is left alone
+        assert!(plan.content.ends_with(&format!("and [report](:/{})", GONE)));
+        assert_eq!(other.content, format!("Back to [Plan](/note/{})", plan.id));
+        assert_eq!(plan.notebook.as_deref(), Some("Work"));
+        assert_eq!(plan.tags, vec!["urgent"]);
+        assert_eq!(plan.links, vec!["https://example.com/plan"]);
+        assert_eq!(plan.created_at, parse_date("2024-01-02T03:04:05Z").unwrap());
+
+        assert_eq!(report.images, 1);
+        assert_eq!(report.skipped.len(), 1);
+        assert_eq!(report.skipped[0].reason, "Missing resource: report.pdf");
+    }
+}
@@ -0,0 +1,232 @@
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::{file_url, is_image_file, markdown_url, new_note, ImportContext};
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+struct KeepNote {
+    title: String,
+    text_content: String,
+    list_content: Vec<KeepListItem>,
+    labels: Vec<KeepLabel>,
+    attachments: Vec<KeepAttachment>,
+    annotations: Vec<KeepAnnotation>,
+    is_pinned: bool,
+    is_archived: bool,
+    is_trashed: bool,
+    created_timestamp_usec: Option<i64>,
+    user_edited_timestamp_usec: Option<i64>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+struct KeepListItem {
+    text: String,
+    is_checked: bool,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct KeepLabel {
+    name: String,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+struct KeepAttachment {
+    file_path: String,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct KeepAnnotation {
+    url: String,
+}
+
+fn from_usec(usec: Option<i64>) -> Option<DateTime<Utc>> {
+    usec.filter(|u| *u > 0).and_then(DateTime::from_timestamp_micros)
+}
+
+// Takeout sometimes renames attachments (".jpeg" on disk for a ".jpg" reference)
+fn find_attachment(dir: &Path, file_path: &str) -> Option<PathBuf> {
+    let direct = dir.join(file_path);
+    if direct.is_file() {
+        return Some(direct);
+    }
+    let stem = Path::new(file_path).file_stem()?.to_str()?;
+    ["jpg", "jpeg", "png", "gif", "webp", "3gp", "m4a"]
+        .iter()
+        .map(|ext| dir.join(format!("{}.{}", stem, ext)))
+        .find(|p| p.is_file())
+}
+
+// Keep notes often have no title; fall back to the first line of text
+fn title_for(keep: &KeepNote) -> String {
+    let title = keep.title.trim();
+    if !title.is_empty() {
+        return title.to_string();
+    }
+    let first_line = keep
+        .text_content
+        .lines()
+        .chain(keep.list_content.iter().map(|i| i.text.as_str()))
+        .map(str::trim)
+        .find(|l| !l.is_empty())
+        .unwrap_or("Keep note");
+    first_line.chars().take(80).collect()
+}
+
+fn import_keep_note(path: &Path, source: &str, ctx: &mut ImportContext) {
+    let keep: KeepNote = match fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read file: {}", e))
+        .and_then(|text| serde_json::from_str(&text).map_err(|e| format!("Not a Keep note: {}", e)))
+    {
+        Ok(k) => k,
+        Err(e) => {
+            ctx.skip(source, e);
+            return;
+        }
+    };
+    // Every field defaults, so anything without Keep's timestamps is some other JSON
+    if keep.created_timestamp_usec.is_none() && keep.user_edited_timestamp_usec.is_none() {
+        ctx.skip(source, "Not a Keep note");
+        return;
+    }
+    if keep.is_trashed {
+        ctx.skip(source, "Note is in the trash");
+        return;
+    }
+
+    let mut blocks: Vec<String> = Vec::new();
+    if !keep.text_content.trim().is_empty() {
+        blocks.push(keep.text_content.trim_end().to_string());
+    }
+    if !keep.list_content.is_empty() {
+        let tasks: Vec<String> = keep
+            .list_content
+            .iter()
+            .map(|item| format!("- [{}] {}", if item.is_checked { "x" } else { " " }, item.text.trim()))
+            .collect();
+        blocks.push(tasks.join("\n"));
+    }
+
+    let dir = path.parent().unwrap_or(Path::new("."));
+    for attachment in &keep.attachments {
+        let found = match find_attachment(dir, &attachment.file_path) {
+            Some(p) => p,
+            None => {
+                ctx.skip(source, format!("Missing attachment: {}", attachment.file_path));
+                continue;
+            }
+        };
+        match ctx.copy_attachment(&found) {
+            Ok(stored) if is_image_file(&stored) => blocks.push(format!("![]({})", markdown_url(&stored))),
+            Ok(stored) => {
+                let name = stored.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+                blocks.push(format!("[{}]({})", name, file_url(&stored)));
+            }
+            Err(e) => ctx.skip(source, e),
+        }
+    }
+
+    let now = Utc::now();
+    let created_at = from_usec(keep.created_timestamp_usec)
+        .or(from_usec(keep.user_edited_timestamp_usec))
+        .unwrap_or(now);
+    let updated_at = from_usec(keep.user_edited_timestamp_usec).unwrap_or(created_at);
+
+    let id = ctx.allocate_id(None);
+    let mut note = new_note(id, title_for(&keep), blocks.join("\n\n"), created_at, updated_at);
+    for label in &keep.labels {
+        let tag = label.name.trim().to_string();
+        if !tag.is_empty() && !note.tags.contains(&tag) {
+            note.tags.push(tag);
+        }
+    }
+    for annotation in &keep.annotations {
+        let url = annotation.url.trim().to_string();
+        if !url.is_empty() && !note.links.contains(&url) {
+            note.links.push(url);
+        }
+    }
+    note.pinned = keep.is_pinned;
+    note.archived = keep.is_archived;
+    ctx.save_note(&note, source);
+}
+
+/// Imports the JSON notes of a Google Takeout export. Accepts either the Takeout
+/// folder itself or its `Keep` subfolder.
+pub fn import_google_keep(takeout_dir: &Path, ctx: &mut ImportContext) -> Result<(), String> {
+    let keep_dir = if takeout_dir.join("Keep").is_dir() {
+        takeout_dir.join("Keep")
+    } else {
+        takeout_dir.to_path_buf()
+    };
+    let entries = fs::read_dir(&keep_dir)
+        .map_err(|e| format!("Failed to read folder: {}", e))?;
+    let mut files: Vec<PathBuf> = entries
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.extension().and_then(|e| e.to_str()).map(|e| e.eq_ignore_ascii_case("json")).unwrap_or(false))
+        .collect();
+    files.sort();
+    if files.is_empty() {
+        return Err("No Keep notes found in this folder".to_string());
+    }
+
+    let total = files.len();
+    for (i, path) in files.iter().enumerate() {
+        let source = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+        import_keep_note(path, &source, ctx);
+        ctx.progress(i + 1, total, &source);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::import::{fixture_dir, import_for_test};
+
+    #[test]
+    fn keep_notes_become_tasks_labels_and_attachments() {
+        let dir = fixture_dir("keep");
+        let keep = dir.join("Keep");
+        fs::create_dir_all(&keep).unwrap();
+        fs::write(keep.join("list.json"), r#"{
+            "title": "",
+            "listContent": [{ "text": "Milk", "isChecked": true }, { "text": "Bread ", "isChecked": false }],
+            "labels": [{ "name": "home" }],
+            "annotations": [{ "url": "https://example.com/recipe" }],
+            "attachments": [{ "filePath": "photo.jpg" }],
+            "isPinned": true,
+            "createdTimestampUsec": 1700000000000000,
+            "userEditedTimestampUsec": 1700000100000000
+        }"#).unwrap();
+        // Takeout stored the attachment under another extension
+        fs::write(keep.join("photo.jpeg"), b"\xff\xd8\xffkeep test photo").unwrap();
+        fs::write(keep.join("trashed.json"), r#"{ "textContent": "gone", "isTrashed": true, "createdTimestampUsec": 1 }"#).unwrap();
+        fs::write(keep.join("other.json"), r#"{ "something": "else" }"#).unwrap();
+
+        let (notes, report) = import_for_test(&dir, |ctx| import_google_keep(&dir, ctx));
+        let _ = fs::remove_dir_all(&dir);
+
+        assert_eq!(notes.len(), 1);
+        let note = &notes[0];
+        assert_eq!(note.title, "Milk");
+        assert!(note.content.starts_with("- [x] Milk\n- [ ] Bread"));
+        assert!(note.content.contains("<img src=\"data:image/jpeg;base64,"));
+        assert_eq!(note.tags, vec!["home"]);
+        assert_eq!(note.links, vec!["https://example.com/recipe"]);
+        assert!(note.pinned && !note.archived);
+        assert_eq!(note.created_at, DateTime::from_timestamp_micros(1700000000000000).unwrap());
+        assert_eq!(note.updated_at, DateTime::from_timestamp_micros(1700000100000000).unwrap());
+
+        let reasons: Vec<(&str, &str)> = report.skipped.iter().map(|s| (s.source.as_str(), s.reason.as_str())).collect();
+        assert_eq!(reasons, vec![("other.json", "Not a Keep note"), ("trashed.json", "Note is in the trash")]);
+        assert_eq!(report.images, 1);
+    }
+}
@@ -0,0 +1,394 @@
+pub mod document;
+pub mod enex;
+pub mod folder;
+pub mod joplin;
+pub mod keep;
+pub mod notion;
+pub mod pdf;
+pub mod standard_notes;
+
+use app_lib::{ImportProgress, ImportReport, ImportSkipped, ImportedNote, Note};
+use chrono::{DateTime, Utc};
+use regex::Regex;
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use url::Url;
+
+use crate::export::{escape_html, get_images_dir, image_data_url};
+use crate::markdown::is_valid_note_id;
+
+/// Link target the note route understands; used when rewriting internal links.
+pub fn note_href(id: &str) -> String {
+    format!("/note/{}", id)
+}
+
+/// Encodes the characters that would break a Markdown link destination.
+pub fn markdown_url(path: &Path) -> String {
+    path.to_string_lossy()
+        .replace('%', "%25")
+        .replace(' ', "%20")
+        .replace('(', "%28")
+        .replace(')', "%29")
+}
+
+/// `file://` URL of an absolute path, usable as a Markdown link target.
+pub fn file_url(path: &Path) -> String {
+    match Url::from_file_path(path) {
+        // Parentheses are legal in URLs but end a Markdown link
+        Ok(url) => url.to_string().replace('(', "%28").replace(')', "%29"),
+        Err(_) => format!("file://{}", markdown_url(path)),
+    }
+}
+
+// Non-image files attached to imported notes
+fn get_attachments_dir() -> Result<PathBuf, String> {
+    Ok(crate::get_notes_dir()?.join("attachments"))
+}
+
+pub fn is_image_file(path: &Path) -> bool {
+    matches!(
+        path.extension().and_then(|e| e.to_str()).map(|e| e.to_lowercase()).as_deref(),
+        Some("png" | "jpg" | "jpeg" | "gif" | "webp" | "svg" | "bmp")
+    )
+}
+
+fn markdown_image_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r#"!\[([^\]]*)\]\(\s*<?([^)\s>]+)>?(?:\s+"[^"]*")?\s*\)"#).unwrap())
+}
+
+fn setext_heading_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"(?m)^([^\n|]*\S[^\n|]*)\n(=+|-+)[ \t]*$").unwrap())
+}
+
+fn blank_lines_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"\n[ \t]*(?:\n[ \t]*){2,}").unwrap())
+}
+
+/// Converts HTML to the Markdown flavour notes are written in: ATX headings and
+/// at most one blank line between blocks.
+pub fn html_to_markdown(html: &str) -> String {
+    let markdown = html2md::parse_html(html);
+    let markdown = setext_heading_regex().replace_all(&markdown, |caps: &regex::Captures| {
+        let level = if caps[2].starts_with('=') { "#" } else { "##" };
+        format!("{} {}", level, caps[1].trim())
+    });
+    blank_lines_regex().replace_all(&markdown, "\n\n").trim().to_string()
+}
+
+pub fn new_note(id: String, title: String, content: String, created_at: DateTime<Utc>, updated_at: DateTime<Utc>) -> Note {
+    Note {
+        id,
+        title,
+        content,
+        links: Vec::new(),
+        created_at,
+        updated_at,
+        tags: Vec::new(),
+        capture_type: Some("text".to_string()),
+        source_app: None,
+        window_title: None,
+        pinned: false,
+        favorite: false,
+        archived: false,
+        sort_key: 0,
+        notebook: None,
+    }
+}
+
+/// Fresh scratch folder for unpacking archives; remove it with `remove_temp_dir`.
+pub fn temp_import_dir(prefix: &str) -> Result<PathBuf, String> {
+    let dir = crate::get_app_data_dir()?
+        .join("import_tmp")
+        .join(format!("{}_{}", prefix, Utc::now().timestamp_millis()));
+    fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create temp folder: {}", e))?;
+    Ok(dir)
+}
+
+pub fn remove_temp_dir(dir: &Path) {
+    let _ = fs::remove_dir_all(dir);
+    if let Some(parent) = dir.parent() {
+        let _ = fs::remove_dir(parent); // only succeeds once empty
+    }
+}
+
+type SaveNote<'a> = Box<dyn FnMut(&Note) -> Result<(), String> + 'a>;
+
+/// State shared by the importers: id allocation, the image store, saving (or, in
+/// dry-run mode, only recording) notes, progress callbacks and the final report.
+pub struct ImportContext<'a> {
+    dry_run: bool,
+    report: ImportReport,
+    taken_ids: HashSet<String>,
+    stored_images: HashMap<String, PathBuf>,
+    stored_attachments: HashMap<String, PathBuf>,
+    save: SaveNote<'a>,
+    progress: Box<dyn FnMut(ImportProgress) + 'a>,
+}
+
+impl<'a> ImportContext<'a> {
+    pub fn new(
+        source: &Path,
+        dry_run: bool,
+        existing_ids: HashSet<String>,
+        save: impl FnMut(&Note) -> Result<(), String> + 'a,
+        progress: impl FnMut(ImportProgress) + 'a,
+    ) -> Self {
+        Self {
+            dry_run,
+            report: ImportReport {
+                source: source.to_string_lossy().to_string(),
+                dry_run,
+                ..Default::default()
+            },
+            taken_ids: existing_ids,
+            stored_images: HashMap::new(),
+            stored_attachments: HashMap::new(),
+            save: Box::new(save),
+            progress: Box::new(progress),
+        }
+    }
+
+    /// Reuses `preferred` when it is a valid id nobody has, otherwise allocates a
+    /// fresh `note_<millis>_<n>` id. The suffix keeps imported ids apart from the
+    /// `note_<millis>` ids the app hands out while the import runs.
+    pub fn allocate_id(&mut self, preferred: Option<&str>) -> String {
+        if let Some(id) = preferred.filter(|id| is_valid_note_id(id)) {
+            if self.taken_ids.insert(id.to_string()) {
+                return id.to_string();
+            }
+        }
+        let millis = Utc::now().timestamp_millis();
+        let mut n = 1;
+        loop {
+            let id = format!("note_{}_{}", millis, n);
+            if self.taken_ids.insert(id.clone()) {
+                return id;
+            }
+            n += 1;
+        }
+    }
+
+    pub fn skip(&mut self, source: impl Into<String>, reason: impl Into<String>) {
+        self.report.skipped.push(ImportSkipped { source: source.into(), reason: reason.into() });
+    }
+
+    pub fn progress(&mut self, done: usize, total: usize, source: &str) {
+        (self.progress)(ImportProgress { done, total, source: source.to_string() });
+    }
+
+    pub fn save_note(&mut self, note: &Note, source: &str) {
+        if !self.dry_run {
+            let mut note = note.clone();
+            note.content = self.embed_images(&note.content, source);
+            if let Err(e) = (self.save)(&note) {
+                self.skip(source, e);
+                return;
+            }
+        }
+        self.report.notes.push(ImportedNote {
+            id: note.id.clone(),
+            title: note.title.clone(),
+            source: source.to_string(),
+        });
+    }
+
+    /// Rewrites `![alt](path)` images stored by this import into the editor's form:
+    /// the webview only loads data URLs for local files, so the bytes are inlined
+    /// and the stored file is kept in `data-path` for exports and OCR.
+    fn embed_images(&mut self, content: &str, source: &str) -> String {
+        let stored: HashMap<String, &PathBuf> = self
+            .stored_images
+            .values()
+            .map(|path| (markdown_url(path), path))
+            .collect();
+        let mut failed = Vec::new();
+        let content = markdown_image_regex()
+            .replace_all(content, |caps: &regex::Captures| {
+                let path = match stored.get(&caps[2]) {
+                    Some(path) => path,
+                    None => return caps[0].to_string(),
+                };
+                match image_data_url(path) {
+                    Ok(url) => format!(
+                        "<img src=\"{}\" alt=\"{}\" data-path=\"{}\">",
+                        url,
+                        escape_html(&caps[1]),
+                        escape_html(&path.to_string_lossy())
+                    ),
+                    Err(e) => {
+                        failed.push(e);
+                        caps[0].to_string()
+                    }
+                }
+            })
+            .to_string();
+        for e in failed {
+            self.skip(source, e);
+        }
+        content
+    }
+
+    /// Writes image bytes into the images directory, reusing an existing file with
+    /// the same content. Returns the stored path (the would-be path in dry-run mode).
+    pub fn store_image(&mut self, bytes: &[u8], name: &str) -> Result<PathBuf, String> {
+        self.store_file(true, bytes, name)
+    }
+
+    /// Stores an attached file: images go to the images directory, anything else
+    /// to the attachments directory.
+    pub fn store_attachment(&mut self, bytes: &[u8], name: &str) -> Result<PathBuf, String> {
+        self.store_file(is_image_file(Path::new(name)), bytes, name)
+    }
+
+    pub fn copy_image(&mut self, source: &Path) -> Result<PathBuf, String> {
+        let bytes = fs::read(source)
+            .map_err(|e| format!("Failed to read image: {}", e))?;
+        let name = source.file_name().and_then(|n| n.to_str()).unwrap_or("image.png");
+        self.store_image(&bytes, name)
+    }
+
+    pub fn copy_attachment(&mut self, source: &Path) -> Result<PathBuf, String> {
+        let bytes = fs::read(source)
+            .map_err(|e| format!("Failed to read attachment: {}", e))?;
+        let name = source.file_name().and_then(|n| n.to_str()).unwrap_or("attachment");
+        self.store_attachment(&bytes, name)
+    }
+
+    fn store_file(&mut self, image: bool, bytes: &[u8], name: &str) -> Result<PathBuf, String> {
+        let hash = format!("{:x}", Sha256::digest(bytes));
+        let stored = if image { &self.stored_images } else { &self.stored_attachments };
+        if let Some(path) = stored.get(&hash) {
+            return Ok(path.clone());
+        }
+
+        let name = sanitize_filename::sanitize(name);
+        let fallback = if image { "image" } else { "attachment" };
+        let (stem, ext) = match name.rsplit_once('.') {
+            Some((s, e)) if !s.is_empty() => (s.to_string(), format!(".{}", e.to_lowercase())),
+            _ if image => (if name.is_empty() { fallback.to_string() } else { name.clone() }, ".png".to_string()),
+            _ => (if name.is_empty() { fallback.to_string() } else { name.clone() }, String::new()),
+        };
+        let dir = if image { get_images_dir()? } else { get_attachments_dir()? };
+        let mut path = dir.join(format!("{}{}", stem, ext));
+        let mut n = 2;
+        loop {
+            match fs::read(&path) {
+                Ok(existing) if existing == bytes => break,
+                Ok(_) => {
+                    path = dir.join(format!("{} ({}){}", stem, n, ext));
+                    n += 1;
+                }
+                Err(_) => {
+                    if !self.dry_run {
+                        fs::create_dir_all(&dir)
+                            .map_err(|e| format!("Failed to create folder: {}", e))?;
+                        fs::write(&path, bytes)
+                            .map_err(|e| format!("Failed to write {}: {}", fallback, e))?;
+                    }
+                    break;
+                }
+            }
+        }
+
+        if image {
+            self.report.images += 1;
+            self.stored_images.insert(hash, path.clone());
+        } else {
+            self.report.attachments += 1;
+            self.stored_attachments.insert(hash, path.clone());
+        }
+        Ok(path)
+    }
+
+    pub fn finish(self) -> ImportReport {
+        self.report
+    }
+}
+
+/// Runs an importer for real and collects the notes it saved.
+#[cfg(test)]
+pub fn import_for_test(source: &Path, importer: impl FnOnce(&mut ImportContext) -> Result<(), String>) -> (Vec<Note>, ImportReport) {
+    let mut saved = Vec::new();
+    let mut ctx = ImportContext::new(source, false, HashSet::new(), |note| {
+        saved.push(note.clone());
+        Ok(())
+    }, |_| {});
+    importer(&mut ctx).unwrap();
+    let report = ctx.finish();
+    (saved, report)
+}
+
+/// Empty scratch folder for an importer test's fixture files.
+#[cfg(test)]
+pub fn fixture_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("notes_import_{}_{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Writes a zip archive with the given entries, for archive-based fixtures.
+#[cfg(test)]
+pub fn write_zip(path: &Path, entries: &[(&str, &[u8])]) {
+    use std::io::Write;
+    let mut zip = zip::ZipWriter::new(fs::File::create(path).unwrap());
+    for (name, bytes) in entries {
+        zip.start_file(*name, zip::write::SimpleFileOptions::default()).unwrap();
+        zip.write_all(bytes).unwrap();
+    }
+    zip.finish().unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn imported_images_are_saved_in_the_editor_form() {
+        let mut saved: Vec<Note> = Vec::new();
+        let mut ctx = ImportContext::new(Path::new("vault"), false, HashSet::new(), |note| {
+            saved.push(note.clone());
+            Ok(())
+        }, |_| {});
+        let stored = ctx.store_image(b"\x89PNG\r\n\x1a\nimport test", "import test.png").unwrap();
+        let id = ctx.allocate_id(None);
+        let content = format!("Before\n\n![a \"pic\"]({})\n\n![web](https://example.com/x.png)", markdown_url(&stored));
+        ctx.save_note(&new_note(id.clone(), "Pics".to_string(), content, Utc::now(), Utc::now()), "pics.md");
+        let report = ctx.finish();
+        let _ = fs::remove_file(&stored);
+
+        assert!(report.skipped.is_empty());
+        assert!(id.starts_with("note_") && id.matches('_').count() == 2);
+        let content = &saved[0].content;
+        // The webview's CSP only loads local images from data URLs
+        assert!(content.contains("<img src=\"data:image/png;base64,"));
+        assert!(content.contains(&format!("data-path=\"{}\"", escape_html(&stored.to_string_lossy()))));
+        assert!(content.contains("alt=\"a &quot;pic&quot;\""));
+        assert!(!content.contains(&format!("]({}", markdown_url(&stored))));
+        assert!(content.contains("![web](https://example.com/x.png)"));
+    }
+
+    #[test]
+    fn other_attachments_are_stored_apart_from_images() {
+        let mut ctx = ImportContext::new(Path::new("vault"), true, HashSet::new(), |_| Ok(()), |_| {});
+        let pdf = ctx.store_attachment(b"%PDF-1.4 import test", "Report (final).pdf").unwrap();
+        let png = ctx.store_attachment(b"\x89PNG\r\n\x1a\nattachment test", "chart.png").unwrap();
+        let report = ctx.finish();
+
+        assert_eq!(pdf.parent(), Some(get_attachments_dir().unwrap().as_path()));
+        assert_eq!(png.parent(), Some(get_images_dir().unwrap().as_path()));
+        assert_eq!((report.images, report.attachments), (1, 1));
+
+        let url = file_url(&pdf);
+        assert!(url.starts_with("file:///"));
+        assert!(url.ends_with("/Report%20%28final%29.pdf"));
+        assert!(!url.contains('\\'));
+    }
+}
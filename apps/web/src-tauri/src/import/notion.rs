@@ -0,0 +1,430 @@
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use regex::{Captures, Regex};
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File};
+use std::io::{self, Cursor, Read, Seek};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use zip::ZipArchive;
+
+use super::folder::{collect_files, map_outside_code, md_link_regex, normalize, rel_path};
+use super::{file_url, is_image_file, markdown_url, new_note, note_href, remove_temp_dir, temp_import_dir, ImportContext};
+use crate::export::percent_decode;
+use crate::markdown::{parse_date, parse_markdown};
+
+// "Page title 0123456789abcdef0123456789abcdef", databases may end in "_all"
+fn notion_name_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"^(.*?)\s*\b[0-9a-f]{32}(?:_all)?$").unwrap())
+}
+
+/// Removes the id Notion appends to every exported page, folder and database name.
+pub fn strip_notion_hash(name: &str) -> String {
+    match notion_name_regex().captures(name) {
+        Some(caps) if !caps[1].trim().is_empty() => caps[1].trim().to_string(),
+        _ => name.trim().to_string(),
+    }
+}
+
+fn file_stem(rel: &str) -> &str {
+    let name = rel.rsplit('/').next().unwrap_or(rel);
+    name.rsplit_once('.').map(|(s, _)| s).unwrap_or(name)
+}
+
+fn parent_dir(rel: &str) -> &str {
+    rel.rsplit_once('/').map(|(d, _)| d).unwrap_or("")
+}
+
+fn notebook_for(dir: &str) -> Option<String> {
+    let parts: Vec<String> = dir
+        .split('/')
+        .filter(|p| !p.is_empty())
+        .map(strip_notion_hash)
+        .collect();
+    if parts.is_empty() { None } else { Some(parts.join("/")) }
+}
+
+// Notion writes dates like "January 5, 2023 10:11 AM"
+fn parse_notion_date(text: &str) -> Option<DateTime<Utc>> {
+    let text = text.trim();
+    for fmt in ["%B %d, %Y %I:%M %p", "%B %e, %Y %I:%M %p", "%Y/%m/%d %H:%M"] {
+        if let Ok(d) = NaiveDateTime::parse_from_str(text, fmt) {
+            return Some(d.and_utc());
+        }
+    }
+    for fmt in ["%B %d, %Y", "%B %e, %Y", "%Y/%m/%d"] {
+        if let Some(d) = NaiveDate::parse_from_str(text, fmt).ok().and_then(|d| d.and_hms_opt(0, 0, 0)) {
+            return Some(d.and_utc());
+        }
+    }
+    parse_date(text)
+}
+
+fn extract_archive<R: Read + Seek>(mut archive: ZipArchive<R>, dest: &Path, nested: bool) -> Result<(), String> {
+    for i in 0..archive.len() {
+        let mut entry = archive.by_index(i)
+            .map_err(|e| format!("Failed to read zip entry: {}", e))?;
+        // enclosed_name rejects absolute paths and ".." components
+        let rel = match entry.enclosed_name() { Some(p) => p, None => continue };
+        let out = dest.join(&rel);
+        if entry.is_dir() {
+            fs::create_dir_all(&out)
+                .map_err(|e| format!("Failed to create folder: {}", e))?;
+            continue;
+        }
+        if let Some(parent) = out.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create folder: {}", e))?;
+        }
+        // Large workspaces are exported as a zip of "Part-N" zips
+        let is_zip = rel.extension().and_then(|e| e.to_str()).map(|e| e.eq_ignore_ascii_case("zip")).unwrap_or(false);
+        if is_zip && !nested {
+            let mut bytes = Vec::new();
+            entry.read_to_end(&mut bytes)
+                .map_err(|e| format!("Failed to read zip entry: {}", e))?;
+            let inner = ZipArchive::new(Cursor::new(bytes))
+                .map_err(|e| format!("Failed to open nested zip: {}", e))?;
+            extract_archive(inner, dest, true)?;
+            continue;
+        }
+        let mut file = File::create(&out)
+            .map_err(|e| format!("Failed to write file: {}", e))?;
+        io::copy(&mut entry, &mut file)
+            .map_err(|e| format!("Failed to write file: {}", e))?;
+    }
+    Ok(())
+}
+
+#[derive(Default, Clone)]
+struct RowProperties {
+    tags: Vec<String>,
+    links: Vec<String>,
+    created_at: Option<DateTime<Utc>>,
+    updated_at: Option<DateTime<Utc>>,
+}
+
+impl RowProperties {
+    fn from_columns(columns: &[(String, String)]) -> Self {
+        let mut props = RowProperties::default();
+        for (key, value) in columns {
+            let value = value.trim();
+            if value.is_empty() { continue; }
+            match key.trim().to_lowercase().as_str() {
+                "tags" | "tag" | "labels" | "label" | "category" | "categories" | "keywords" => {
+                    for tag in value.split(',').map(str::trim).filter(|t| !t.is_empty()) {
+                        if !props.tags.iter().any(|t| t == tag) { props.tags.push(tag.to_string()); }
+                    }
+                }
+                "created" | "created time" | "date created" | "created at" => {
+                    props.created_at = parse_notion_date(value);
+                }
+                "last edited time" | "last edited" | "updated" | "last modified" | "updated at" => {
+                    props.updated_at = parse_notion_date(value);
+                }
+                _ => {
+                    if value.starts_with("http://") || value.starts_with("https://") {
+                        props.links.push(value.to_string());
+                    }
+                }
+            }
+        }
+        props
+    }
+}
+
+struct Page {
+    path: PathBuf,
+    rel: String,
+    id: String,
+    props: RowProperties,
+    is_row: bool,
+}
+
+// Database rows that have no page of their own in the export
+struct RowNote {
+    id: String,
+    title: String,
+    notebook: Option<String>,
+    columns: Vec<(String, String)>,
+    props: RowProperties,
+}
+
+struct Database {
+    rel: String,
+    id: String,
+    title: String,
+    notebook: Option<String>,
+    rows: Vec<(String, String)>, // (row title, note id)
+}
+
+fn read_csv(path: &Path) -> Result<(Vec<String>, Vec<Vec<String>>), String> {
+    let bytes = fs::read(path)
+        .map_err(|e| format!("Failed to read file: {}", e))?;
+    let bytes = bytes.strip_prefix(b"\xef\xbb\xbf".as_slice()).unwrap_or(&bytes);
+    let mut reader = csv::ReaderBuilder::new().flexible(true).from_reader(bytes);
+    let headers: Vec<String> = reader.headers()
+        .map_err(|e| format!("Failed to parse CSV: {}", e))?
+        .iter()
+        .map(|h| h.to_string())
+        .collect();
+    let mut rows = Vec::new();
+    for record in reader.records() {
+        let record = record.map_err(|e| format!("Failed to parse CSV: {}", e))?;
+        rows.push(record.iter().map(|v| v.to_string()).collect());
+    }
+    Ok((headers, rows))
+}
+
+/// Imports a Notion "Markdown & CSV" export. Pages keep their hierarchy as
+/// notebooks, database rows become notes and links between pages point at the
+/// imported notes.
+pub fn import_notion_export(zip_path: &Path, ctx: &mut ImportContext) -> Result<(), String> {
+    let file = File::open(zip_path)
+        .map_err(|e| format!("Failed to open file: {}", e))?;
+    let archive = ZipArchive::new(file)
+        .map_err(|e| format!("Failed to open zip: {}", e))?;
+
+    let temp = temp_import_dir("notion")?;
+    let result = extract_archive(archive, &temp, false).and_then(|_| import_notion_dir(&temp, ctx));
+    remove_temp_dir(&temp);
+    result
+}
+
+fn import_notion_dir(root: &Path, ctx: &mut ImportContext) -> Result<(), String> {
+    let mut files = Vec::new();
+    collect_files(root, root, &mut files, ctx)?;
+    files.sort();
+
+    let mut pages: Vec<Page> = Vec::new();
+    let mut csv_files: Vec<(String, PathBuf)> = Vec::new();
+    let mut attachments: HashMap<String, PathBuf> = HashMap::new();
+    // Lowercased relative path -> note id, for link rewriting
+    let mut targets: HashMap<String, String> = HashMap::new();
+
+    for path in files {
+        let rel = rel_path(root, &path);
+        let ext = rel.rsplit_once('.').map(|(_, e)| e.to_lowercase()).unwrap_or_default();
+        match ext.as_str() {
+            "md" => {
+                let id = ctx.allocate_id(None);
+                targets.insert(rel.to_lowercase(), id.clone());
+                pages.push(Page { path, rel, id, props: RowProperties::default(), is_row: false });
+            }
+            "csv" => csv_files.push((rel, path)),
+            _ => { attachments.insert(rel.to_lowercase(), path); }
+        }
+    }
+
+    // Newer exports write both "DB <hash>.csv" and "DB <hash>_all.csv"; keep the full one
+    let all_variants: HashSet<String> = csv_files
+        .iter()
+        .filter_map(|(rel, _)| rel.strip_suffix("_all.csv").map(|r| format!("{}.csv", r)))
+        .collect();
+
+    let mut databases: Vec<Database> = Vec::new();
+    let mut row_notes: Vec<RowNote> = Vec::new();
+    for (rel, path) in csv_files {
+        if all_variants.contains(&rel) {
+            continue;
+        }
+        let (headers, rows) = match read_csv(&path) {
+            Ok(parsed) => parsed,
+            Err(e) => {
+                ctx.skip(rel, e);
+                continue;
+            }
+        };
+        let stem = file_stem(&rel);
+        let title = strip_notion_hash(stem);
+        let folder = format!("{}/{}", parent_dir(&rel), stem.trim_end_matches("_all"))
+            .trim_start_matches('/')
+            .to_lowercase();
+        let notebook = notebook_for(parent_dir(&rel));
+        let row_notebook = Some(match &notebook {
+            Some(n) => format!("{}/{}", n, title),
+            None => title.clone(),
+        });
+
+        let db_id = ctx.allocate_id(None);
+        targets.insert(rel.to_lowercase(), db_id.clone());
+        if let Some(base) = rel.strip_suffix("_all.csv") {
+            targets.insert(format!("{}.csv", base).to_lowercase(), db_id.clone());
+        }
+
+        let mut db = Database { rel: rel.clone(), id: db_id, title, notebook, rows: Vec::new() };
+        for values in rows {
+            let columns: Vec<(String, String)> = headers.iter().cloned().zip(values).collect();
+            let row_title = columns.first().map(|(_, v)| v.trim().to_string()).unwrap_or_default();
+            let props = RowProperties::from_columns(&columns);
+
+            // Rows with content are also exported as pages inside the database folder
+            let page = pages.iter_mut().find(|p| {
+                parent_dir(&p.rel).to_lowercase() == folder
+                    && !p.is_row
+                    && strip_notion_hash(file_stem(&p.rel)).eq_ignore_ascii_case(&row_title)
+            });
+            match page {
+                Some(page) => {
+                    page.props = props;
+                    page.is_row = true;
+                    db.rows.push((row_title, page.id.clone()));
+                }
+                None => {
+                    let id = ctx.allocate_id(None);
+                    let title = if row_title.is_empty() { "Untitled".to_string() } else { row_title };
+                    db.rows.push((title.clone(), id.clone()));
+                    row_notes.push(RowNote { id, title, notebook: row_notebook.clone(), columns, props });
+                }
+            }
+        }
+        databases.push(db);
+    }
+
+    let total = pages.len() + row_notes.len() + databases.len();
+    let mut done = 0;
+    let mut used: HashSet<String> = HashSet::new();
+    let now = Utc::now();
+
+    for page in &pages {
+        done += 1;
+        let text = match fs::read_to_string(&page.path) {
+            Ok(t) => t,
+            Err(e) => {
+                ctx.skip(page.rel.clone(), format!("Failed to read file: {}", e));
+                continue;
+            }
+        };
+        let parsed = parse_markdown(&text);
+        let dir = parent_dir(&page.rel);
+        let content = map_outside_code(&parsed.content, |segment| {
+            md_link_regex()
+                .replace_all(segment, |caps: &Captures| {
+                    let raw = caps[3].trim_start_matches('<').trim_end_matches('>');
+                    if raw.contains("://") || raw.starts_with('#') || raw.starts_with("mailto:") {
+                        return caps[0].to_string();
+                    }
+                    let target = percent_decode(raw.split('#').next().unwrap_or(raw));
+                    let key = normalize(dir, &target);
+                    if let Some(id) = targets.get(&key) {
+                        return format!("[{}]({})", &caps[2], note_href(id));
+                    }
+                    if let Some(path) = attachments.get(&key) {
+                        used.insert(key);
+                        let title = caps.get(4).map(|m| m.as_str()).unwrap_or("");
+                        return match ctx.copy_attachment(path) {
+                            Ok(stored) if is_image_file(&stored) => format!("{}[{}]({}{})", &caps[1], &caps[2], markdown_url(&stored), title),
+                            Ok(stored) => format!("[{}]({})", &caps[2], file_url(&stored)),
+                            Err(e) => {
+                                ctx.skip(page.rel.clone(), e);
+                                caps[0].to_string()
+                            }
+                        };
+                    }
+                    caps[0].to_string()
+                })
+                .to_string()
+        });
+
+        let title = parsed.title.clone().unwrap_or_else(|| strip_notion_hash(file_stem(&page.rel)));
+        let created_at = page.props.created_at.or(parsed.created_at).unwrap_or(now);
+        let updated_at = page.props.updated_at.or(parsed.updated_at).unwrap_or(created_at);
+        let mut note = new_note(page.id.clone(), title, content, created_at, updated_at);
+        note.notebook = notebook_for(dir);
+        note.tags = page.props.tags.clone();
+        note.links = page.props.links.clone();
+        ctx.save_note(&note, &page.rel);
+        ctx.progress(done, total, &page.rel);
+    }
+
+    for row in row_notes {
+        done += 1;
+        let content = row
+            .columns
+            .iter()
+            .skip(1)
+            .filter(|(_, v)| !v.trim().is_empty())
+            .map(|(k, v)| format!("{}: {}", k, v.trim()))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let created_at = row.props.created_at.unwrap_or(now);
+        let updated_at = row.props.updated_at.unwrap_or(created_at);
+        let mut note = new_note(row.id, row.title.clone(), content, created_at, updated_at);
+        note.notebook = row.notebook;
+        note.tags = row.props.tags;
+        note.links = row.props.links;
+        let source = row.title;
+        ctx.save_note(&note, &source);
+        ctx.progress(done, total, &source);
+    }
+
+    // Each database also gets an index note listing its rows
+    for db in databases {
+        done += 1;
+        let content = db
+            .rows
+            .iter()
+            .map(|(title, id)| format!("- [{}]({})", title, note_href(id)))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let mut note = new_note(db.id, db.title, content, now, now);
+        note.notebook = db.notebook;
+        ctx.save_note(&note, &db.rel);
+        ctx.progress(done, total, &db.rel);
+    }
+
+    let mut unused: Vec<(&String, &PathBuf)> = attachments.iter().filter(|(k, _)| !used.contains(*k)).collect();
+    unused.sort();
+    for (_, path) in unused {
+        ctx.skip(rel_path(root, path), "Attachment not referenced by any page");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::import::{fixture_dir, import_for_test, write_zip};
+
+    #[test]
+    fn notion_names_lose_their_hash() {
+        assert_eq!(strip_notion_hash("Projects 0123456789abcdef0123456789abcdef"), "Projects");
+        assert_eq!(strip_notion_hash("Tasks 0123456789abcdef0123456789abcdef_all"), "Tasks");
+        assert_eq!(strip_notion_hash("Plain name"), "Plain name");
+    }
+
+    #[test]
+    fn notion_export_keeps_hierarchy_and_rewrites_links() {
+        let dir = fixture_dir("notion");
+        let path = dir.join("export.zip");
+        write_zip(&path, &[
+            (
+                "Projects 0123456789abcdef0123456789abcdef.md",
+                b"# Projects\n\nSee [Tasks](Projects%200123456789abcdef0123456789abcdef/Tasks%20fedcba9876543210fedcba9876543210.md) and ![diagram](Projects%200123456789abcdef0123456789abcdef/diagram.png)\n\nDocs: [site](https://example.com)",
+            ),
+            (
+                "Projects 0123456789abcdef0123456789abcdef/Tasks fedcba9876543210fedcba9876543210.md",
+                b"# Tasks\n\nBack to [Projects](../Projects%200123456789abcdef0123456789abcdef.md)",
+            ),
+            ("Projects 0123456789abcdef0123456789abcdef/diagram.png", b"\x89PNG\r\n\x1a\nnotion test image"),
+            ("Projects 0123456789abcdef0123456789abcdef/unused.txt", b"never linked"),
+        ]);
+
+        let (notes, report) = import_for_test(&path, |ctx| import_notion_export(&path, ctx));
+        let _ = fs::remove_dir_all(&dir);
+
+        assert_eq!(notes.len(), 2);
+        let projects = notes.iter().find(|n| n.title == "Projects").unwrap();
+        let tasks = notes.iter().find(|n| n.title == "Tasks").unwrap();
+        assert_eq!(projects.notebook, None);
+        assert_eq!(tasks.notebook.as_deref(), Some("Projects"));
+        assert!(projects.content.contains(&format!("See [Tasks](/note/{})", tasks.id)));
+        assert!(projects.content.contains("<img src=\"data:image/png;base64,"));
+        assert!(projects.content.contains("[site](https://example.com)"));
+        assert_eq!(tasks.content, format!("Back to [Projects](/note/{})", projects.id));
+
+        assert_eq!(report.images, 1);
+        assert_eq!(report.skipped.len(), 1);
+        assert!(report.skipped[0].source.ends_with("unused.txt"));
+        assert_eq!(report.skipped[0].reason, "Attachment not referenced by any page");
+    }
+}
@@ -0,0 +1,438 @@
+use chrono::{DateTime, FixedOffset, NaiveDate, TimeZone, Utc};
+use lopdf::content::Content;
+use lopdf::{Dictionary, Document, Object};
+use regex::Regex;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::OnceLock;
+
+use super::folder::file_dates;
+use super::{file_url, new_note, ImportContext};
+
+/// Glyph code -> text, from a font's ToUnicode CMap.
+struct ToUnicode {
+    code_bytes: usize,
+    map: HashMap<u32, String>,
+}
+
+enum FontDecoder {
+    Cmap(ToUnicode),
+    Simple(String),
+    // Composite font without a ToUnicode map; its glyph ids can't be turned into text
+    Unknown,
+}
+
+fn bfchar_section_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"(?s)beginbfchar(.*?)endbfchar").unwrap())
+}
+
+fn bfrange_section_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"(?s)beginbfrange(.*?)endbfrange").unwrap())
+}
+
+fn codespace_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"(?s)begincodespacerange\s*<([0-9A-Fa-f]+)>").unwrap())
+}
+
+fn bfchar_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"<([0-9A-Fa-f]+)>\s*<([0-9A-Fa-f]*)>").unwrap())
+}
+
+fn bfrange_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(r"<([0-9A-Fa-f]+)>\s*<([0-9A-Fa-f]+)>\s*(?:<([0-9A-Fa-f]*)>|\[([^\]]*)\])").unwrap()
+    })
+}
+
+fn hex_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"<([0-9A-Fa-f]*)>").unwrap())
+}
+
+fn blank_lines_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"\n{3,}").unwrap())
+}
+
+fn hex_bytes(hex: &str) -> Vec<u8> {
+    hex.as_bytes()
+        .chunks(2)
+        .filter_map(|pair| std::str::from_utf8(pair).ok().and_then(|p| u8::from_str_radix(p, 16).ok()))
+        .collect()
+}
+
+fn utf16_hex(hex: &str) -> String {
+    let bytes = hex_bytes(hex);
+    let units: Vec<u16> = bytes.chunks(2).map(|c| u16::from_be_bytes([c[0], *c.get(1).unwrap_or(&0)])).collect();
+    String::from_utf16_lossy(&units)
+}
+
+fn code_value(hex: &str) -> u32 {
+    u32::from_str_radix(hex, 16).unwrap_or(0)
+}
+
+fn parse_cmap(text: &str) -> ToUnicode {
+    let code_bytes = codespace_regex()
+        .captures(text)
+        .map(|c| c[1].len().div_ceil(2))
+        .unwrap_or(2)
+        .clamp(1, 4);
+    let mut map = HashMap::new();
+    for section in bfchar_section_regex().captures_iter(text) {
+        for caps in bfchar_regex().captures_iter(&section[1]) {
+            map.insert(code_value(&caps[1]), utf16_hex(&caps[2]));
+        }
+    }
+    for section in bfrange_section_regex().captures_iter(text) {
+        for caps in bfrange_regex().captures_iter(&section[1]) {
+            let (lo, hi) = (code_value(&caps[1]), code_value(&caps[2]));
+            // Guard against bogus ranges covering the whole code space
+            if hi < lo || hi - lo > 0xFFFF {
+                continue;
+            }
+            if let Some(start) = caps.get(3) {
+                let mut units: Vec<u16> = hex_bytes(start.as_str())
+                    .chunks(2)
+                    .map(|c| u16::from_be_bytes([c[0], *c.get(1).unwrap_or(&0)]))
+                    .collect();
+                for code in lo..=hi {
+                    map.insert(code, String::from_utf16_lossy(&units));
+                    if let Some(last) = units.last_mut() {
+                        *last = last.wrapping_add(1);
+                    }
+                }
+            } else if let Some(list) = caps.get(4) {
+                for (code, dst) in (lo..=hi).zip(hex_regex().captures_iter(list.as_str())) {
+                    map.insert(code, utf16_hex(&dst[1]));
+                }
+            }
+        }
+    }
+    ToUnicode { code_bytes, map }
+}
+
+fn font_decoder(doc: &Document, font: &Dictionary) -> FontDecoder {
+    let cmap = font
+        .get(b"ToUnicode")
+        .and_then(|o| doc.dereference(o))
+        .and_then(|(_, o)| o.as_stream())
+        .and_then(|s| s.decompressed_content().or_else(|_| Ok(s.content.clone())));
+    if let Ok(bytes) = cmap {
+        return FontDecoder::Cmap(parse_cmap(&String::from_utf8_lossy(&bytes)));
+    }
+    let encoding = font.get_font_encoding();
+    if encoding.starts_with("Identity") {
+        FontDecoder::Unknown
+    } else {
+        FontDecoder::Simple(encoding.to_string())
+    }
+}
+
+fn decode_string(decoder: Option<&FontDecoder>, bytes: &[u8]) -> String {
+    match decoder {
+        Some(FontDecoder::Cmap(cmap)) => bytes
+            .chunks(cmap.code_bytes)
+            .filter_map(|code| {
+                let value = code.iter().fold(0u32, |acc, b| (acc << 8) | *b as u32);
+                cmap.map.get(&value).map(|s| s.as_str())
+            })
+            .collect(),
+        Some(FontDecoder::Simple(encoding)) => Document::decode_text(Some(encoding), bytes),
+        Some(FontDecoder::Unknown) => String::new(),
+        None => Document::decode_text(None, bytes),
+    }
+}
+
+fn new_line(text: &mut String) {
+    if !text.is_empty() && !text.ends_with('\n') {
+        text.push('\n');
+    }
+}
+
+fn operand(operands: &[Object], index: usize) -> f32 {
+    operands.get(index).and_then(|o| o.as_float().ok()).unwrap_or(0.0)
+}
+
+/// Text of one page in reading order of the content stream; text positioning
+/// operators that move to another line become line breaks.
+fn page_text(doc: &Document, page_id: lopdf::ObjectId) -> Result<String, String> {
+    let fonts: HashMap<Vec<u8>, FontDecoder> = doc
+        .get_page_fonts(page_id)
+        .into_iter()
+        .map(|(name, font)| (name, font_decoder(doc, font)))
+        .collect();
+    let data = doc.get_page_content(page_id)
+        .map_err(|e| format!("Failed to read page content: {}", e))?;
+    let content = Content::decode(&data)
+        .map_err(|e| format!("Failed to parse page content: {}", e))?;
+
+    let mut text = String::new();
+    let mut font: Option<&FontDecoder> = None;
+    let mut last_y: Option<f32> = None;
+    for op in &content.operations {
+        match op.operator.as_str() {
+            "Tf" => {
+                font = op.operands.first().and_then(|o| o.as_name().ok()).and_then(|n| fonts.get(n));
+            }
+            "Td" | "TD" => {
+                if operand(&op.operands, 1).abs() > 0.1 {
+                    new_line(&mut text);
+                } else if operand(&op.operands, 0) > 0.1 && !text.ends_with([' ', '\n']) && !text.is_empty() {
+                    text.push(' ');
+                }
+            }
+            "Tm" => {
+                let y = operand(&op.operands, 5);
+                if last_y.map(|l| (l - y).abs() > 0.1).unwrap_or(false) {
+                    new_line(&mut text);
+                }
+                last_y = Some(y);
+            }
+            "T*" | "ET" => new_line(&mut text),
+            "Tj" | "'" | "\"" => {
+                if op.operator != "Tj" {
+                    new_line(&mut text);
+                }
+                if let Some(Object::String(bytes, _)) = op.operands.last() {
+                    text.push_str(&decode_string(font, bytes));
+                }
+            }
+            "TJ" => {
+                for item in op.operands.first().and_then(|o| o.as_array().ok()).into_iter().flatten() {
+                    match item {
+                        Object::String(bytes, _) => text.push_str(&decode_string(font, bytes)),
+                        // Large negative kerning is how most generators write a space
+                        Object::Integer(_) | Object::Real(_)
+                            if item.as_float().unwrap_or(0.0) < -200.0 && !text.ends_with(' ') =>
+                        {
+                            text.push(' ');
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let lines: Vec<&str> = text.lines().map(str::trim_end).collect();
+    Ok(blank_lines_regex().replace_all(lines.join("\n").trim(), "\n\n").to_string())
+}
+
+// PDF text strings are UTF-16BE with a BOM or PDFDocEncoding (close enough to Latin-1)
+fn decode_pdf_string(bytes: &[u8]) -> String {
+    if bytes.starts_with(&[0xFE, 0xFF]) {
+        let units: Vec<u16> = bytes[2..].chunks(2).map(|c| u16::from_be_bytes([c[0], *c.get(1).unwrap_or(&0)])).collect();
+        String::from_utf16_lossy(&units)
+    } else if bytes.starts_with(&[0xEF, 0xBB, 0xBF]) {
+        String::from_utf8_lossy(&bytes[3..]).to_string()
+    } else {
+        bytes.iter().map(|b| *b as char).collect()
+    }
+}
+
+fn info_string(doc: &Document, key: &[u8]) -> Option<String> {
+    let info = doc.trailer.get(b"Info").and_then(|o| doc.dereference(o)).ok()?.1.as_dict().ok()?;
+    match info.get(key).and_then(|o| doc.dereference(o)).ok()?.1 {
+        Object::String(bytes, _) => Some(decode_pdf_string(bytes).trim().to_string()).filter(|s| !s.is_empty()),
+        _ => None,
+    }
+}
+
+// PDF dates look like D:20230131124500+01'00'; everything after the year is optional
+fn parse_pdf_date(text: &str) -> Option<DateTime<Utc>> {
+    let text = text.trim().trim_start_matches("D:");
+    let digits: String = text.chars().take_while(|c| c.is_ascii_digit()).collect();
+    let field = |start: usize, len: usize, default: u32| -> u32 {
+        digits.get(start..start + len).and_then(|d| d.parse().ok()).unwrap_or(default)
+    };
+    let year = digits.get(0..4)?.parse().ok()?;
+    let naive = NaiveDate::from_ymd_opt(year, field(4, 2, 1), field(6, 2, 1))?
+        .and_hms_opt(field(8, 2, 0), field(10, 2, 0), field(12, 2, 0))?;
+
+    let zone = &text[digits.len()..];
+    let offset_secs = match zone.chars().next() {
+        Some(sign @ ('+' | '-')) => {
+            let parts: Vec<i32> = zone[1..]
+                .split('\'')
+                .filter_map(|p| p.trim().parse().ok())
+                .collect();
+            let secs = parts.first().unwrap_or(&0) * 3600 + parts.get(1).unwrap_or(&0) * 60;
+            if sign == '-' { -secs } else { secs }
+        }
+        _ => 0,
+    };
+    FixedOffset::east_opt(offset_secs)?
+        .from_local_datetime(&naive)
+        .single()
+        .map(|d| d.with_timezone(&Utc))
+}
+
+fn pdf_title(doc: &Document) -> Option<String> {
+    let title = info_string(doc, b"Title")?;
+    // Office and print drivers often write "Microsoft Word - spec.docx"
+    let title = title.strip_prefix("Microsoft Word - ").unwrap_or(&title).trim().to_string();
+    if title.is_empty() || title.eq_ignore_ascii_case("untitled") {
+        return None;
+    }
+    Some(title)
+}
+
+/// Imports the text layer of a PDF as one note with a `## Page N` heading per page.
+/// The note links back to the original file.
+pub fn import_pdf(path: &Path, ctx: &mut ImportContext) -> Result<(), String> {
+    let source = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    let mut doc = Document::load(path)
+        .map_err(|e| format!("Failed to open PDF: {}", e))?;
+    if doc.is_encrypted() {
+        // Many PDFs are "encrypted" with an empty user password just to set permissions
+        doc.decrypt("")
+            .map_err(|_| "PDF is password protected".to_string())?;
+    }
+
+    let pages = doc.get_pages();
+    let total = pages.len();
+    let mut sections: Vec<String> = Vec::new();
+    let mut has_text = false;
+    for (i, (number, page_id)) in pages.into_iter().enumerate() {
+        let text = match page_text(&doc, page_id) {
+            Ok(t) => t,
+            Err(e) => {
+                ctx.skip(format!("{} page {}", source, number), e);
+                String::new()
+            }
+        };
+        has_text |= !text.is_empty();
+        sections.push(if text.is_empty() {
+            format!("## Page {}", number)
+        } else {
+            format!("## Page {}\n\n{}", number, text)
+        });
+        ctx.progress(i + 1, total, &format!("{} page {}", source, number));
+    }
+    if !has_text {
+        return Err("No text found in this PDF; it may be a scanned document".to_string());
+    }
+
+    let (file_created, file_modified) = file_dates(path);
+    let now = Utc::now();
+    let created_at = info_string(&doc, b"CreationDate")
+        .and_then(|d| parse_pdf_date(&d))
+        .or(file_created)
+        .unwrap_or(now);
+    let updated_at = info_string(&doc, b"ModDate")
+        .and_then(|d| parse_pdf_date(&d))
+        .or(file_modified)
+        .unwrap_or(created_at);
+    let title = pdf_title(&doc).unwrap_or_else(|| {
+        path.file_stem().and_then(|s| s.to_str()).unwrap_or("Imported PDF").to_string()
+    });
+
+    let absolute = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    let id = ctx.allocate_id(None);
+    let mut note = new_note(id, title, sections.join("\n\n"), created_at, updated_at);
+    note.links.push(file_url(&absolute));
+    ctx.save_note(&note, &source);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::import::{fixture_dir, import_for_test};
+    use crate::markdown::parse_date;
+    use lopdf::content::Operation;
+    use std::fs;
+    use lopdf::{dictionary, Stream, StringFormat};
+
+    fn text_page(doc: &mut Document, pages_id: lopdf::ObjectId, font: Dictionary, operations: Vec<Operation>) -> lopdf::ObjectId {
+        let font_id = doc.add_object(font);
+        let content = Content { operations }.encode().unwrap();
+        let content_id = doc.add_object(Stream::new(dictionary! {}, content));
+        doc.add_object(dictionary! {
+            "Type" => "Page",
+            "Parent" => pages_id,
+            "Contents" => content_id,
+            "Resources" => dictionary! { "Font" => dictionary! { "F1" => font_id } },
+        })
+    }
+
+    fn show(bytes: &[u8]) -> Operation {
+        Operation::new("Tj", vec![Object::String(bytes.to_vec(), StringFormat::Hexadecimal)])
+    }
+
+    // Page 1 uses a standard font; page 2 a composite font that only a ToUnicode
+    // CMap can decode; page 3 has no text at all
+    fn write_fixture(path: &Path) {
+        let mut doc = Document::with_version("1.5");
+        let pages_id = doc.new_object_id();
+        let helvetica = dictionary! {
+            "Type" => "Font", "Subtype" => "Type1", "BaseFont" => "Helvetica", "Encoding" => "WinAnsiEncoding",
+        };
+        let first = text_page(&mut doc, pages_id, helvetica, vec![
+            Operation::new("BT", vec![]),
+            Operation::new("Tf", vec!["F1".into(), 12.into()]),
+            Operation::new("Td", vec![72.into(), 720.into()]),
+            show(b"Meeting notes"),
+            Operation::new("Td", vec![0.into(), (-14).into()]),
+            Operation::new("TJ", vec![Object::Array(vec![
+                Object::string_literal("Action"),
+                (-250).into(),
+                Object::string_literal("items"),
+            ])]),
+            Operation::new("ET", vec![]),
+        ]);
+        let cmap = "/CIDInit /ProcSet findresource begin\nbegincmap\n1 begincodespacerange\n<0000> <FFFF>\nendcodespacerange\n\
+                    1 beginbfchar\n<0001> <00DC>\nendbfchar\n1 beginbfrange\n<0002> <0003> <0062>\nendbfrange\nendcmap\nend";
+        let cmap_id = doc.add_object(Stream::new(dictionary! {}, cmap.as_bytes().to_vec()));
+        let composite = dictionary! {
+            "Type" => "Font", "Subtype" => "Type0", "BaseFont" => "Custom", "Encoding" => "Identity-H", "ToUnicode" => cmap_id,
+        };
+        let second = text_page(&mut doc, pages_id, composite, vec![
+            Operation::new("BT", vec![]),
+            Operation::new("Tf", vec!["F1".into(), 12.into()]),
+            show(&[0, 1, 0, 2, 0, 3]),
+            Operation::new("ET", vec![]),
+        ]);
+        let third = text_page(&mut doc, pages_id, dictionary! {}, vec![]);
+        let pages = dictionary! {
+            "Type" => "Pages",
+            "Kids" => vec![first.into(), second.into(), third.into()],
+            "Count" => 3,
+            "MediaBox" => vec![0.into(), 0.into(), 612.into(), 792.into()],
+        };
+        doc.objects.insert(pages_id, Object::Dictionary(pages));
+        let catalog_id = doc.add_object(dictionary! { "Type" => "Catalog", "Pages" => pages_id });
+        let info_id = doc.add_object(dictionary! {
+            "Title" => Object::string_literal("Microsoft Word - minutes.docx"),
+            "CreationDate" => Object::string_literal("D:20230131124500+01'00'"),
+        });
+        doc.trailer.set("Root", catalog_id);
+        doc.trailer.set("Info", info_id);
+        doc.save(path).unwrap();
+    }
+
+    #[test]
+    fn pdf_pages_become_sections_linking_the_original() {
+        let dir = fixture_dir("pdf");
+        let path = dir.join("minutes.pdf");
+        write_fixture(&path);
+
+        let (notes, report) = import_for_test(&path, |ctx| import_pdf(&path, ctx));
+        let absolute = path.canonicalize().unwrap();
+        let _ = fs::remove_dir_all(&dir);
+
+        assert_eq!(notes.len(), 1);
+        let note = &notes[0];
+        assert_eq!(note.title, "minutes.docx");
+        assert_eq!(note.content, "## Page 1\n\nMeeting notes\nAction items\n\n## Page 2\n\nÜbc\n\n## Page 3");
+        assert_eq!(note.links, vec![file_url(&absolute)]);
+        assert_eq!(note.created_at, parse_date("2023-01-31T11:45:00Z").unwrap());
+        assert!(report.skipped.is_empty());
+    }
+}
@@ -0,0 +1,260 @@
+use chrono::Utc;
+use regex::{Captures, Regex};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::sync::OnceLock;
+
+use super::folder::map_outside_code;
+use super::{new_note, note_href, ImportContext};
+use crate::markdown::parse_date;
+
+fn uuid_link_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(r"\]\(\s*([0-9a-fA-F]{8}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{12})\s*\)").unwrap()
+    })
+}
+
+fn str_field<'a>(value: &'a Value, key: &str) -> &'a str {
+    value.get(key).and_then(|v| v.as_str()).unwrap_or("")
+}
+
+fn references(content: &Value) -> Vec<(String, String)> {
+    content
+        .get("references")
+        .and_then(|r| r.as_array())
+        .map(|refs| {
+            refs.iter()
+                .map(|r| (str_field(r, "uuid").to_string(), str_field(r, "content_type").to_string()))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+// Super notes store a Lexical editor state; keep the text of each block
+fn lexical_text(node: &Value, out: &mut String) {
+    if let Some(text) = node.get("text").and_then(|t| t.as_str()) {
+        out.push_str(text);
+    }
+    if let Some(children) = node.get("children").and_then(|c| c.as_array()) {
+        for child in children {
+            lexical_text(child, out);
+        }
+        if node.get("type").and_then(|t| t.as_str()) != Some("root") {
+            out.push('\n');
+        }
+    }
+}
+
+fn note_text(content: &Value) -> String {
+    let text = str_field(content, "text");
+    if str_field(content, "noteType") == "super" {
+        if let Ok(state) = serde_json::from_str::<Value>(text) {
+            let mut out = String::new();
+            lexical_text(state.get("root").unwrap_or(&state), &mut out);
+            return out.trim().to_string();
+        }
+    }
+    text.to_string()
+}
+
+fn app_flag(content: &Value, key: &str) -> bool {
+    content
+        .pointer(&format!("/appData/org.standardnotes.sn/{}", key))
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false)
+}
+
+/// Imports a decrypted Standard Notes backup (`.txt`/`.json` with an `items` array).
+/// Tags keep their nesting as `parent/child`; note-to-note references and links to
+/// note uuids point at the imported notes.
+pub fn import_standard_notes(path: &Path, ctx: &mut ImportContext) -> Result<(), String> {
+    let text = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read file: {}", e))?;
+    let backup: Value = serde_json::from_str(&text)
+        .map_err(|e| format!("Failed to parse backup: {}", e))?;
+    let items = backup
+        .get("items")
+        .and_then(|i| i.as_array())
+        .ok_or("Not a Standard Notes backup")?;
+
+    let mut notes: Vec<&Value> = Vec::new();
+    let mut tag_titles: HashMap<String, String> = HashMap::new();
+    let mut tag_parents: HashMap<String, String> = HashMap::new();
+    let mut tag_notes: Vec<(String, String)> = Vec::new();
+
+    for item in items {
+        let uuid = str_field(item, "uuid").to_string();
+        if item.get("deleted").and_then(|d| d.as_bool()).unwrap_or(false) {
+            continue;
+        }
+        let content = match item.get("content") {
+            Some(c) if c.is_object() => c,
+            Some(_) => {
+                ctx.skip(uuid, "Encrypted item; export a decrypted backup");
+                continue;
+            }
+            None => continue,
+        };
+        match str_field(item, "content_type") {
+            "Note" => notes.push(item),
+            "Tag" => {
+                tag_titles.insert(uuid.clone(), str_field(content, "title").to_string());
+                for (ref_uuid, ref_type) in references(content) {
+                    match ref_type.as_str() {
+                        "Note" => tag_notes.push((uuid.clone(), ref_uuid)),
+                        "Tag" => { tag_parents.insert(uuid.clone(), ref_uuid); }
+                        _ => {}
+                    }
+                }
+            }
+            _ => {} // components, themes, preferences
+        }
+    }
+
+    let tag_path = |uuid: &str| -> String {
+        let mut parts = Vec::new();
+        let mut current = uuid.to_string();
+        for _ in 0..32 {
+            match tag_titles.get(&current) {
+                Some(title) => parts.push(title.clone()),
+                None => break,
+            }
+            match tag_parents.get(&current) {
+                Some(parent) => current = parent.clone(),
+                None => break,
+            }
+        }
+        parts.reverse();
+        parts.join("/")
+    };
+    let mut tags_by_note: HashMap<String, Vec<String>> = HashMap::new();
+    for (tag_uuid, note_uuid) in &tag_notes {
+        let tag = tag_path(tag_uuid);
+        if !tag.is_empty() {
+            tags_by_note.entry(note_uuid.clone()).or_default().push(tag);
+        }
+    }
+
+    let mut new_ids: HashMap<String, String> = HashMap::new();
+    let mut titles: HashMap<String, String> = HashMap::new();
+    for item in &notes {
+        let uuid = str_field(item, "uuid").to_string();
+        new_ids.insert(uuid.clone(), ctx.allocate_id(None));
+        titles.insert(uuid, str_field(&item["content"], "title").to_string());
+    }
+
+    let total = notes.len();
+    let now = Utc::now();
+    for (i, item) in notes.iter().enumerate() {
+        let uuid = str_field(item, "uuid");
+        let content = &item["content"];
+        let source = format!("Note {}", uuid);
+        if content.get("trashed").and_then(|t| t.as_bool()).unwrap_or(false) {
+            ctx.skip(source.clone(), "Note is in the trash");
+            ctx.progress(i + 1, total, &source);
+            continue;
+        }
+
+        let mut body = map_outside_code(&note_text(content), |segment| {
+            uuid_link_regex()
+                .replace_all(segment, |caps: &Captures| match new_ids.get(&caps[1].to_lowercase()) {
+                    Some(id) => format!("]({})", note_href(id)),
+                    None => caps[0].to_string(),
+                })
+                .to_string()
+        });
+        // Linked notes are references rather than inline links in Standard Notes
+        let linked: Vec<String> = references(content)
+            .into_iter()
+            .filter(|(_, t)| t == "Note")
+            .filter_map(|(u, _)| new_ids.get(&u).map(|id| format!("- [{}]({})", titles.get(&u).map(|t| t.as_str()).unwrap_or("Linked note"), note_href(id))))
+            .collect();
+        if !linked.is_empty() {
+            body = format!("{}\n\n## Linked notes\n\n{}", body.trim_end(), linked.join("\n"));
+        }
+
+        let title = match str_field(content, "title").trim() {
+            "" => "Untitled".to_string(),
+            t => t.to_string(),
+        };
+        let created_at = parse_date(str_field(item, "created_at")).unwrap_or(now);
+        let updated_at = parse_date(str_field(item, "updated_at")).unwrap_or(created_at);
+        let mut note = new_note(new_ids[uuid].clone(), title, body, created_at, updated_at);
+        note.tags = tags_by_note.remove(uuid).unwrap_or_default();
+        note.pinned = app_flag(content, "pinned");
+        note.archived = app_flag(content, "archived");
+        ctx.save_note(&note, &source);
+        ctx.progress(i + 1, total, &source);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::import::{fixture_dir, import_for_test};
+    use serde_json::json;
+
+    const PLAN: &str = "11111111-1111-4111-8111-111111111111";
+    const OTHER: &str = "22222222-2222-4222-8222-222222222222";
+
+    #[test]
+    fn standard_notes_backup_rewrites_uuid_links_and_nests_tags() {
+        let dir = fixture_dir("standard_notes");
+        fs::create_dir_all(&dir).unwrap();
+        let backup = json!({ "items": [
+            { "uuid": PLAN, "content_type": "Note", "created_at": "2024-03-01T10:00:00.000Z", "content": {
+                "title": "Plan",
+                "text": format!("See [Other]({})\n```\n[code]({})\n```", OTHER.to_uppercase(), OTHER),
+                "references": [{ "uuid": OTHER, "content_type": "Note" }],
+                "appData": { "org.standardnotes.sn": { "pinned": true } }
+            }},
+            { "uuid": OTHER, "content_type": "Note", "content": {
+                "title": "Other",
+                "noteType": "super",
+                "text": json!({ "root": { "type": "root", "children": [
+                    { "type": "paragraph", "children": [{ "text": "First" }] },
+                    { "type": "paragraph", "children": [{ "text": "Second" }] }
+                ]}}).to_string()
+            }},
+            { "uuid": "33333333-3333-4333-8333-333333333333", "content_type": "Note", "content": { "title": "Old", "text": "", "trashed": true } },
+            { "uuid": "44444444-4444-4444-8444-444444444444", "content_type": "Note", "content": "004:encrypted" },
+            { "uuid": "55555555-5555-4555-8555-555555555555", "content_type": "Tag", "content": {
+                "title": "projects", "references": []
+            }},
+            { "uuid": "66666666-6666-4666-8666-666666666666", "content_type": "Tag", "content": {
+                "title": "q1",
+                "references": [
+                    { "uuid": PLAN, "content_type": "Note" },
+                    { "uuid": "55555555-5555-4555-8555-555555555555", "content_type": "Tag" }
+                ]
+            }}
+        ]});
+        let path = dir.join("backup.txt");
+        fs::write(&path, backup.to_string()).unwrap();
+
+        let (notes, report) = import_for_test(&path, |ctx| import_standard_notes(&path, ctx));
+        let _ = fs::remove_dir_all(&dir);
+
+        assert_eq!(notes.len(), 2);
+        let plan = notes.iter().find(|n| n.title == "Plan").unwrap();
+        let other = notes.iter().find(|n| n.title == "Other").unwrap();
+        assert_eq!(
+            plan.content,
+            format!(
+                "See [Other](/note/{0})\n```\n[code]({1})\n```\n\n## Linked notes\n\n- [Other](/note/{0})",
+                other.id, OTHER
+            )
+        );
+        assert_eq!(other.content, "First\nSecond");
+        assert_eq!(plan.tags, vec!["projects/q1"]);
+        assert!(plan.pinned);
+        assert_eq!(plan.created_at, parse_date("2024-03-01T10:00:00Z").unwrap());
+
+        let reasons: Vec<&str> = report.skipped.iter().map(|s| s.reason.as_str()).collect();
+        assert_eq!(reasons, vec!["Encrypted item; export a decrypted backup", "Note is in the trash"]);
+    }
+}
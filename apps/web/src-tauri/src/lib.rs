@@ -1,6 +1,10 @@
 use serde::{Serialize, Deserialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::mpsc::Sender;
 use std::sync::{Arc, Mutex};
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
+use regex::Regex;
 use tauri_plugin_dialog;
 
 #[derive(Clone, Serialize)]
@@ -27,10 +31,54 @@ pub struct ClipboardHistoryEntry {
     pub tags: Vec<String>,
     #[serde(default)]
     pub content_hash: Option<String>,
+    #[serde(default, skip_serializing_if = "ClipboardFormats::is_empty")]
+    pub formats: ClipboardFormats,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image: Option<ClipboardImageRef>,
+    /// Why the content was flagged as sensitive, e.g. "aws_access_key, jwt"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sensitive_reason: Option<String>,
+    /// Set on memory-only entries: never written to disk, dropped at this time
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<DateTime<Utc>>,
+    /// Text, formats and stored image files in bytes, measured at capture
+    #[serde(default)]
+    pub size_bytes: u64,
 }
 
 fn default_capture_type() -> String { "text".to_string() }
 
+/// Richer representations captured alongside the plain text; restoring an entry
+/// writes all of them back.
+#[derive(Clone, Serialize, Deserialize, Debug, Default, PartialEq)]
+pub struct ClipboardFormats {
+    /// Sanitized HTML fragment
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub html: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rtf: Option<String>,
+    /// `text/uri-list` entries as `file://` URIs
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub files: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub svg: Option<String>,
+}
+
+/// Where an image capture and its thumbnail are stored, both PNG.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct ClipboardImageRef {
+    pub path: String,
+    pub thumbnail: String,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl ClipboardFormats {
+    pub fn is_empty(&self) -> bool {
+        self.html.is_none() && self.rtf.is_none() && self.files.is_empty() && self.svg.is_none()
+    }
+}
+
 #[derive(Clone, Serialize, Deserialize, Debug)]
 pub struct Note {
     pub id: String,
@@ -47,6 +95,16 @@ pub struct Note {
     pub source_app: Option<String>,
     #[serde(default)]
     pub window_title: Option<String>,
+    #[serde(default)]
+    pub pinned: bool,
+    #[serde(default)]
+    pub favorite: bool,
+    #[serde(default)]
+    pub archived: bool,
+    #[serde(default)]
+    pub sort_key: i64,             // manual order, ascending
+    #[serde(default)]
+    pub notebook: Option<String>,  // "/"-separated path, e.g. "Work/Projects"
 }
 
 #[derive(Clone, Serialize, Deserialize, Debug)]
@@ -55,14 +113,388 @@ pub struct NoteMetadata {
     pub title: String,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    #[serde(default)]
+    pub pinned: bool,
+    #[serde(default)]
+    pub favorite: bool,
+    #[serde(default)]
+    pub archived: bool,
+    #[serde(default)]
+    pub sort_key: i64,
+    #[serde(default)]
+    pub notebook: Option<String>,
+    // Derived on save, so the sidebar can render previews without load_note
+    #[serde(default)]
+    pub excerpt: String,
+    #[serde(default)]
+    pub word_count: usize,
+    #[serde(default)]
+    pub char_count: usize,
+    #[serde(default)]
+    pub reading_time_minutes: u32,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub capture_type: Option<String>,
+    #[serde(default)]
+    pub link_count: usize,
+    #[serde(default)]
+    pub image_count: usize,
+    // OCR text of the embedded images, for search
+    #[serde(default)]
+    pub image_text: String,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum NoteSortMode {
+    #[default]
+    Updated,
+    Created,
+    Title,
+    Manual,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum HistorySortMode {
+    #[default]
+    Newest,
+    Oldest,
+}
+
+/// Age and size limits for the clipboard history, applied on top of the entry
+/// limit. Pinned entries are never removed.
+#[derive(Clone, Serialize, Deserialize, Debug, Default, PartialEq)]
+pub struct RetentionPolicy {
+    /// Unpinned entries older than this are dropped
+    #[serde(default)]
+    pub max_age_hours: Option<u64>,
+    /// Lifetime per `capture_type` ("text", "link", "code", "image", "files"),
+    /// taking precedence over `max_age_hours`
+    #[serde(default)]
+    pub ttl_hours: HashMap<String, u64>,
+    /// Total size of unpinned entries, stored images included; oldest go first
+    #[serde(default)]
+    pub max_bytes: Option<u64>,
+}
+
+/// What happens to a capture that contains a secret.
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum SecretAction {
+    /// Not recorded at all
+    Skip,
+    /// Recorded with the secret parts masked out
+    Mask,
+    /// Recorded as is, but kept off disk and expired after a while
+    #[default]
+    MemoryOnly,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct TaskItem {
+    pub id: String,
+    pub note_id: String,
+    pub note_title: String,
+    pub line: usize,               // 1-based line in note content
+    pub text: String,              // task text with annotations stripped
+    pub done: bool,
+    pub due: Option<NaiveDate>,    // from @due(YYYY-MM-DD)
+    pub priority: Option<String>,  // "high" | "medium" | "low"
+    pub tags: Vec<String>,         // remaining #hashtags
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, Default)]
+pub struct TaskFilter {
+    #[serde(default)]
+    pub done: Option<bool>,
+    #[serde(default)]
+    pub note_id: Option<String>,
+    #[serde(default)]
+    pub priority: Option<String>,
+    #[serde(default)]
+    pub tag: Option<String>,
+    #[serde(default)]
+    pub due_before: Option<NaiveDate>,
+    #[serde(default)]
+    pub query: Option<String>,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ImageExportMode {
+    #[default]
+    Embed,                         // inline as data URLs
+    Copy,                          // copy into a folder next to the exported file
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, Default)]
+pub struct HtmlExportOptions {
+    #[serde(default)]
+    pub path: Option<String>,      // defaults to Notes_V2/exports/<title>.html
+    #[serde(default)]
+    pub theme: Option<String>,     // code highlighting theme, see list_export_themes
+    #[serde(default)]
+    pub images: ImageExportMode,
+    #[serde(default)]
+    pub open_after: bool,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum PdfPageSize {
+    #[default]
+    A4,
+    A5,
+    Letter,
+    Legal,
 }
 
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct PdfExportOptions {
+    #[serde(default)]
+    pub page_size: PdfPageSize,
+    #[serde(default = "default_pdf_margin_mm")]
+    pub margin_mm: f32,
+    #[serde(default = "default_true")]
+    pub include_metadata: bool,    // created/updated dates and tags under the title
+    #[serde(default = "default_true")]
+    pub include_images: bool,      // embed images from the images directory
+    #[serde(default)]
+    pub open_after: bool,
+}
+
+fn default_pdf_margin_mm() -> f32 { 20.0 }
+
+fn default_true() -> bool { true }
+
+impl Default for PdfExportOptions {
+    fn default() -> Self {
+        PdfExportOptions {
+            page_size: PdfPageSize::default(),
+            margin_mm: default_pdf_margin_mm(),
+            include_metadata: true,
+            include_images: true,
+            open_after: false,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    #[default]
+    Markdown,
+    Html,
+    Pdf,
+    Json,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportGrouping {
+    #[default]
+    None,
+    Tag,                           // first tag of each note
+    Notebook,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, Default)]
+pub struct BulkExportOptions {
+    #[serde(default)]
+    pub group_by: ExportGrouping,
+    #[serde(default)]
+    pub theme: Option<String>,     // html only
+    #[serde(default)]
+    pub pdf: Option<PdfExportOptions>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct ExportProgress {
+    pub done: usize,
+    pub total: usize,
+    pub note_id: String,
+    pub title: String,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct ExportFailure {
+    pub note_id: String,
+    pub title: Option<String>,
+    pub error: String,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct ExportSummary {
+    pub output_dir: String,
+    pub exported: usize,
+    pub failed: Vec<ExportFailure>,
+    /// PDF only: characters no available font could draw, left out of the files
+    #[serde(default)]
+    pub missing_chars: Vec<char>,
+}
+
+/// Whether the local OCR engine (Tesseract) can be run.
+#[derive(Clone, Serialize, Debug)]
+pub struct OcrStatus {
+    pub available: bool,
+    /// Program that was run; `TESSERACT_PATH` overrides it
+    pub binary: String,
+    /// First line of `tesseract --version`
+    pub version: Option<String>,
+    pub error: Option<String>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct PdfExportResult {
+    pub path: String,
+    /// Characters no available font could draw, left out of the PDF
+    pub missing_chars: Vec<char>,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum FolderMapping {
+    #[default]
+    Notebook,
+    Tag,
+    None,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, Default)]
+pub struct FolderImportOptions {
+    #[serde(default)]
+    pub folders: FolderMapping,
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct ImportProgress {
+    pub done: usize,
+    pub total: usize,
+    pub source: String,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct ImportedNote {
+    pub id: String,
+    pub title: String,
+    pub source: String,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct ImportSkipped {
+    pub source: String,
+    pub reason: String,
+}
+
+/// Shared result of every importer. In dry-run mode `notes` lists what would be created.
+#[derive(Clone, Serialize, Deserialize, Debug, Default)]
+pub struct ImportReport {
+    pub source: String,
+    pub dry_run: bool,
+    pub notes: Vec<ImportedNote>,
+    /// Images stored into the images directory
+    #[serde(default)]
+    pub images: usize,
+    /// Other attached files, stored into the attachments directory
+    pub attachments: usize,
+    pub skipped: Vec<ImportSkipped>,
+}
+
+/// Part of a capture a rule condition looks at.
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RuleField {
+    Text,
+    Url,
+    App,
+    Window,
+    Type,
+}
+
+/// When a rule applies: a regex on one field, or an AND/OR group of conditions.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum RuleCondition {
+    Match {
+        field: RuleField,
+        pattern: String,
+        /// Applies when the pattern does not match
+        #[serde(default)]
+        negate: bool,
+    },
+    All(Vec<RuleCondition>),
+    Any(Vec<RuleCondition>),
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum TextTransform {
+    Trim,
+    Lowercase,
+    Uppercase,
+    /// Every run of whitespace becomes a single space
+    CollapseWhitespace,
+    Replace {
+        pattern: String,
+        #[serde(default)]
+        replacement: String,
+    },
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RuleAction {
+    Tag { tag: String },
+    /// Drops the capture; no later rule runs
+    Ignore,
+    /// Appends the capture to the previous one when that matched this rule too
+    Merge {
+        /// How long after the previous capture; 30 seconds when unset
+        #[serde(default)]
+        window_seconds: Option<u64>,
+        /// Put between merged captures; a newline when unset
+        #[serde(default)]
+        separator: Option<String>,
+    },
+    SetType { capture_type: String },
+    Transform { transform: TextTransform },
+    Pin,
+    /// Appends the captured text to a note
+    RouteToNote { note_id: String },
+}
+
+/// A clipboard rule. Rules run by descending priority, in list order within the
+/// same priority; later rules see the text and type earlier ones changed.
 #[derive(Clone, Serialize, Deserialize, Debug)]
 pub struct Rule {
-    pub pattern: String,           // regex
-    pub field: String,             // "text" | "url" | "app" | "type"
-    pub action: String,            // "tag" | "ignore" | "merge"
-    pub tag: Option<String>,       // for action == tag
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub priority: i32,
+    pub when: RuleCondition,
+    pub actions: Vec<RuleAction>,
+    /// No lower-priority rule runs once this one matched
+    #[serde(default)]
+    pub stop_processing: bool,
+}
+
+/// A rule accepted by `set_rules`, with its patterns compiled once.
+#[derive(Clone, Debug)]
+pub struct CompiledRule {
+    pub rule: Rule,
+    /// Condition and replace-transform patterns, keyed by their source
+    pub patterns: HashMap<String, Regex>,
+}
+
+/// Why a rule passed to `set_rules` was rejected; `index` is its position in the list.
+#[derive(Clone, Serialize, Debug)]
+pub struct RuleError {
+    pub index: usize,
+    pub message: String,
 }
 
 #[derive(Clone, Serialize, Deserialize, Debug)]
@@ -74,11 +506,19 @@ pub struct SidebarState {
     pub is_right_collapsed: Option<bool>,
 }
 
+/// Work for the background OCR thread.
+pub enum OcrJob {
+    /// An image saved into the images directory
+    Image(PathBuf),
+    /// An image recorded from the clipboard, as PNG; `hash` identifies the raw image
+    Capture { png: Vec<u8>, hash: String },
+}
+
 #[derive(Clone, Serialize)]
 pub struct AppState {
     pub is_focused: Arc<Mutex<bool>>,
     pub last_internal_copy: Arc<Mutex<String>>,
-    pub notes: Arc<Mutex<Vec<Note>>>,
+    pub note_metadata: Arc<Mutex<HashMap<String, NoteMetadata>>>,
     pub sidebar_state: Arc<Mutex<Option<SidebarState>>>,
     pub clipboard_monitoring_enabled: Arc<Mutex<bool>>,
     pub clipboard_history: Arc<Mutex<Vec<ClipboardHistoryEntry>>>,
@@ -87,7 +527,17 @@ pub struct AppState {
     // Config & rules
     pub min_clipboard_text_length: Arc<Mutex<usize>>,
     pub dedup_window_minutes: Arc<Mutex<u64>>,
+    // Only used when no clipboard change notifications are available
+    pub clipboard_poll_interval_ms: Arc<Mutex<u64>>,
     pub rules: Arc<Mutex<Vec<Rule>>>,
+    // The same rules as the capture pipeline runs them
+    #[serde(skip)]
+    pub compiled_rules: Arc<Mutex<Arc<Vec<CompiledRule>>>>,
+    pub secret_action: Arc<Mutex<SecretAction>>,
+    pub secret_ttl_minutes: Arc<Mutex<u64>>,
+    pub retention: Arc<Mutex<RetentionPolicy>>,
+    #[serde(skip)]
+    pub ocr_queue: Arc<Mutex<Sender<OcrJob>>>,
 }
 
 pub fn run() {
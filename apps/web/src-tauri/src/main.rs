@@ -1,25 +1,34 @@
 // Prevents additional console window on Windows in release, DO NOT REMOVE!!
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
-use chrono::{Utc, Duration as ChronoDuration};
+mod clipboard;
+mod export;
+mod import;
+mod markdown;
+mod metadata;
+mod note_store;
+mod ocr;
+mod rules;
+mod tasks;
+
+use chrono::Utc;
+use std::collections::{HashMap, HashSet};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 use std::thread;
-use std::time::{Duration, Instant};
 use tauri::{command, Emitter};
 use tauri_plugin_clipboard_manager::{init as clipboard_manager_plugin, ClipboardExt};
 
 use tauri_plugin_opener::OpenerExt;
 use window_vibrancy::apply_acrylic;
 use tauri::Manager;
-use std::sync::{Arc, Mutex};
-use app_lib::{AppState, Note, NoteMetadata, SidebarState, ClipboardContent, ClipboardHistoryEntry, Rule};
-use regex::Regex;
+use std::sync::{mpsc, Arc, Mutex};
+use app_lib::{AppState, BulkExportOptions, ExportFailure, ExportFormat, ExportProgress, ExportSummary, FolderImportOptions, HistorySortMode, ImportReport, HtmlExportOptions, Note, PdfExportOptions, PdfExportResult, NoteMetadata, NoteSortMode, OcrJob, OcrStatus, SidebarState, ClipboardContent, ClipboardHistoryEntry, RetentionPolicy, Rule, RuleError, SecretAction, TaskFilter, TaskItem};
 use url::Url;
+use clipboard::{ClipboardMonitor, MonitorStatus};
 use serde::Serialize;
 use sha2::{Digest, Sha256};
-use png::{Encoder, ColorType, BitDepth};
 
 #[cfg(target_os = "windows")]
 use windows::Win32::UI::WindowsAndMessaging::{GetForegroundWindow, GetWindowTextW, GetWindowTextLengthW};
@@ -145,31 +154,6 @@ fn is_probable_screenshot(img_width: u32, img_height: u32) -> bool {
     }
 }
 
-fn apply_rules(text: &str, source_url: Option<&str>, source_app: Option<&str>, capture_type: &str, rules: &Vec<Rule>) -> (Vec<String>, bool, bool) {
-    let mut tags: Vec<String> = Vec::new();
-    let mut ignore = false;
-    let mut merge = false;
-    for r in rules {
-        let re = match Regex::new(&r.pattern) { Ok(x) => x, Err(_) => continue };
-        let target = match r.field.as_str() {
-            "url" => source_url.unwrap_or(""),
-            "app" => source_app.unwrap_or(""),
-            "type" => capture_type,
-            _ => text,
-        };
-        if re.is_match(target) {
-            match r.action.as_str() {
-                "tag" => {
-                    if let Some(t) = &r.tag { tags.push(t.clone()); }
-                },
-                "ignore" => { ignore = true; },
-                "merge" => { merge = true; },
-                _ => {}
-            }
-        }
-    }
-    (tags, ignore, merge)
-}
 #[derive(Serialize, Clone)]
 struct ClipboardImagePayload {
     data_url: String,
@@ -177,9 +161,15 @@ struct ClipboardImagePayload {
     height: u32,
 }
 #[command]
-fn get_clipboard_history(app_state: tauri::State<'_, AppState>) -> Result<Vec<ClipboardHistoryEntry>, String> {
+fn get_clipboard_history(limit: Option<usize>, cursor: Option<String>, sort: Option<HistorySortMode>, app_state: tauri::State<'_, AppState>) -> Result<Vec<ClipboardHistoryEntry>, String> {
     if let Ok(history) = app_state.clipboard_history.lock() {
-        Ok(history.clone())
+        // History is kept pinned-first, newest-first; only the requested page is cloned
+        let mut ordered: Vec<&ClipboardHistoryEntry> = history.iter().collect();
+        if sort.unwrap_or_default() == HistorySortMode::Oldest {
+            ordered.sort_by(|a, b| b.pinned.cmp(&a.pinned).then(a.timestamp.cmp(&b.timestamp)));
+        }
+        let page = page_after(ordered, |e| &e.id, limit, cursor.as_deref())?;
+        Ok(page.into_iter().cloned().collect())
     } else {
         Err("Failed to lock clipboard history".to_string())
     }
@@ -192,10 +182,12 @@ fn set_clipboard_history_limit(limit: usize, app_state: tauri::State<'_, AppStat
         *l = limit;
         // Persist with new limit enforced
         if let Ok(mut history) = app_state.clipboard_history.lock() {
-            enforce_history_order_and_limit(&mut history, limit);
+            let orphaned = enforce_history_order_and_limit(&mut history, limit);
             if let Err(e) = save_clipboard_history_to_disk(&history.clone()) {
                 eprintln!("Failed to save clipboard history: {}", e);
             }
+            drop(history);
+            clipboard::remove_images(orphaned);
         }
         Ok(())
     } else {
@@ -215,15 +207,13 @@ fn get_clipboard_history_limit(app_state: tauri::State<'_, AppState>) -> Result<
 #[command]
 fn clear_clipboard_history(keep_pinned: bool, app_state: tauri::State<'_, AppState>) -> Result<(), String> {
     if let Ok(mut history) = app_state.clipboard_history.lock() {
-        if keep_pinned {
-            history.retain(|e| e.pinned);
-        } else {
-            history.clear();
-        }
+        let orphaned = clipboard::retain_history(&mut history, |e| keep_pinned && e.pinned);
         // persist
         if let Err(e) = save_clipboard_history_to_disk(&history.clone()) {
             eprintln!("Failed to save clipboard history: {}", e);
         }
+        drop(history);
+        clipboard::remove_images(orphaned);
         Ok(())
     } else {
         Err("Failed to clear history".to_string())
@@ -258,11 +248,13 @@ fn pin_clipboard_entry(id: String, pinned: bool, app_state: tauri::State<'_, App
 fn delete_clipboard_entry(id: String, app_state: tauri::State<'_, AppState>) -> Result<(), String> {
     if let Ok(mut history) = app_state.clipboard_history.lock() {
         let before = history.len();
-        history.retain(|e| e.id != id);
+        let orphaned = clipboard::retain_history(&mut history, |e| e.id != id);
         if history.len() < before {
             if let Err(e) = save_clipboard_history_to_disk(&history.clone()) {
                 eprintln!("Failed to save clipboard history: {}", e);
             }
+            drop(history);
+            clipboard::remove_images(orphaned);
             Ok(())
         } else {
             Err("Entry not found".to_string())
@@ -273,10 +265,24 @@ fn delete_clipboard_entry(id: String, app_state: tauri::State<'_, AppState>) ->
 }
 
 #[command]
-fn restore_clipboard_entry(text: String, app_handle: tauri::AppHandle, app_state: tauri::State<'_, AppState>) -> Result<(), String> {
-    // Write to system clipboard
-    let clipboard_manager = app_handle.clipboard();
-    clipboard_manager.write_text(text.clone()).map_err(|e| format!("Failed to write clipboard: {}", e))?;
+fn restore_clipboard_entry(text: String, id: Option<String>, app_handle: tauri::AppHandle, app_state: tauri::State<'_, AppState>) -> Result<(), String> {
+    // Rich representations go back together with the text so pasting keeps formatting
+    let (formats, image) = match (&id, app_state.clipboard_history.lock()) {
+        (Some(id), Ok(history)) => history.iter()
+            .find(|e| &e.id == id)
+            .map(|e| (e.formats.clone(), e.image.clone()))
+            .unwrap_or_default(),
+        _ => Default::default(),
+    };
+    if let Some(image) = &image {
+        clipboard::write_image(&app_handle, image)?;
+    } else if formats.is_empty() {
+        // Write to system clipboard
+        let clipboard_manager = app_handle.clipboard();
+        clipboard_manager.write_text(text.clone()).map_err(|e| format!("Failed to write clipboard: {}", e))?;
+    } else {
+        clipboard::write_formats(&text, &formats)?;
+    }
 
     // Mark internal copy to avoid loops
     if let Ok(mut last_copy) = app_state.last_internal_copy.lock() {
@@ -291,50 +297,53 @@ fn restore_clipboard_entry(text: String, app_handle: tauri::AppHandle, app_state
 
     Ok(())
 }
+#[command]
+fn get_clipboard_image(id: String, thumbnail: Option<bool>, app_state: tauri::State<'_, AppState>) -> Result<String, String> {
+    let image = match app_state.clipboard_history.lock() {
+        Ok(history) => history.iter().find(|e| e.id == id).and_then(|e| e.image.clone()),
+        Err(_) => return Err("Failed to lock history".to_string()),
+    };
+    let image = image.ok_or("Entry has no image")?;
+    let path = if thumbnail.unwrap_or(true) { &image.thumbnail } else { &image.path };
+    let bytes = fs::read(clipboard::resolve_image(path))
+        .map_err(|e| format!("Failed to read clipboard image: {}", e))?;
+    Ok(format!("data:image/png;base64,{}", general_purpose::STANDARD.encode(bytes)))
+}
+
 use rfd::FileDialog;
 use base64::{engine::general_purpose, Engine as _};
 use tauri::{WebviewUrl, WebviewWindowBuilder};
 
+#[cfg(not(test))]
+fn get_documents_dir() -> Result<PathBuf, String> {
+    dirs::document_dir().ok_or_else(|| "Failed to get documents directory".to_string())
+}
+
+// Tests never touch the user's notes
+#[cfg(test)]
+fn get_documents_dir() -> Result<PathBuf, String> {
+    Ok(std::env::temp_dir().join(format!("notes_v2_test_{}", std::process::id())))
+}
+
 fn get_notes_dir() -> Result<PathBuf, String> {
-    let documents_dir = dirs::document_dir()
-        .ok_or("Failed to get documents directory")?;
-    let notes_dir = documents_dir.join("Notes_V2");
+    let notes_dir = get_documents_dir()?.join("Notes_V2");
     fs::create_dir_all(&notes_dir)
         .map_err(|e| format!("Failed to create notes directory: {}", e))?;
     Ok(notes_dir)
 }
 
 fn get_app_data_dir() -> Result<PathBuf, String> {
-    let documents_dir = dirs::document_dir()
-        .ok_or("Failed to get documents directory")?;
-    let app_data_dir = documents_dir.join("Notes_V2").join("app_data");
+    let app_data_dir = get_documents_dir()?.join("Notes_V2").join("app_data");
     fs::create_dir_all(&app_data_dir)
         .map_err(|e| format!("Failed to create app data directory: {}", e))?;
     Ok(app_data_dir)
 }
 
-fn load_notes_from_disk() -> Result<Vec<Note>, String> {
-    let notes_dir = get_notes_dir()?;
-    let mut notes = Vec::new();
-
-    if let Ok(entries) = fs::read_dir(notes_dir) {
-        for entry in entries {
-            if let Ok(entry) = entry {
-                let path = entry.path();
-                if path.is_file() && path.extension().and_then(|s| s.to_str()) == Some("json") {
-                    if let Ok(content) = fs::read_to_string(&path) {
-                        if let Ok(note) = serde_json::from_str::<Note>(&content) {
-                            notes.push(note);
-                        }
-                    }
-                }
-            }
-        }
-    }
-
-    // Sort by updated_at descending (most recent first)
-    notes.sort_by(|a, b| b.updated_at.cmp(&a.updated_at));
-    Ok(notes)
+fn get_exports_dir() -> Result<PathBuf, String> {
+    let exports_dir = get_notes_dir()?.join("exports");
+    fs::create_dir_all(&exports_dir)
+        .map_err(|e| format!("Failed to create exports directory: {}", e))?;
+    Ok(exports_dir)
 }
 
 fn save_note_to_disk(note: &Note) -> Result<(), String> {
@@ -350,6 +359,55 @@ fn save_note_to_disk(note: &Note) -> Result<(), String> {
     Ok(())
 }
 
+fn store_note(app_state: &AppState, note: &Note) -> Result<(), String> {
+    let mut index = app_state.note_metadata.lock()
+        .map_err(|_| "Failed to lock note metadata".to_string())?;
+    note_store::write_note(&mut index, note)
+}
+
+/// Writes an imported note, refusing ids another save took meanwhile; `run_import`
+/// saves the index once the whole batch is written.
+fn store_imported_note(app_state: &AppState, note: &Note) -> Result<(), String> {
+    let mut index = app_state.note_metadata.lock()
+        .map_err(|_| "Failed to lock note metadata".to_string())?;
+    if index.contains_key(&note.id) {
+        return Err(format!("Note id already in use: {}", note.id));
+    }
+    note_store::insert_note(&mut index, note)
+}
+
+/// Appends captured text to a note as a new paragraph, in whichever format the note
+/// is written in.
+fn append_to_note(app_state: &AppState, id: &str, text: &str) -> Result<(), String> {
+    let mut index = app_state.note_metadata.lock()
+        .map_err(|_| "Failed to lock note metadata".to_string())?;
+    if !index.contains_key(id) {
+        return Err("Note not found".to_string());
+    }
+    let mut note = note_store::read_note(id)?;
+    // The editor saves HTML; imported and new notes may still be Markdown
+    if note.content.trim_start().starts_with('<') {
+        note.content.push_str(&format!("<p>{}</p>", export::escape_html(text).replace('\n', "<br>")));
+    } else {
+        if !note.content.trim().is_empty() {
+            note.content.push_str("\n\n");
+        }
+        note.content.push_str(text);
+    }
+    note.updated_at = Utc::now();
+    note_store::write_note(&mut index, &note)
+}
+
+fn read_indexed_note(app_state: &AppState, id: &str) -> Result<Note, String> {
+    let known = app_state.note_metadata.lock()
+        .map_err(|_| "Failed to lock note metadata".to_string())?
+        .contains_key(id);
+    if !known {
+        return Err("Note not found".to_string());
+    }
+    note_store::read_note(id)
+}
+
 fn delete_note_from_disk(note_id: &str) -> Result<(), String> {
     let notes_dir = get_notes_dir()?;
     let file_path = notes_dir.join(format!("{}.json", note_id));
@@ -367,19 +425,20 @@ fn get_clipboard_history_file() -> Result<PathBuf, String> {
     Ok(app_data_dir.join("clipboard_history.json"))
 }
 
-fn enforce_history_order_and_limit(history: &mut Vec<ClipboardHistoryEntry>, limit: usize) {
+/// Returns the image files of removed entries, to delete after releasing the lock.
+fn enforce_history_order_and_limit(history: &mut Vec<ClipboardHistoryEntry>, limit: usize) -> Vec<String> {
     // Remove duplicates by content hash if present, else by text
     let mut seen = std::collections::HashSet::new();
-    history.retain(|e| {
+    let mut orphaned = clipboard::retain_history(history, |e| {
         let key = e.content_hash.clone().unwrap_or_else(|| e.text.clone());
         seen.insert(key)
     });
     // Keep pinned entries and up to `limit` non-pinned entries
     let mut non_pinned = 0usize;
-    history.retain(|e| {
+    orphaned.extend(clipboard::retain_history(history, |e| {
         if e.pinned { return true; }
         if non_pinned < limit { non_pinned += 1; true } else { false }
-    });
+    }));
     // Sort: pinned first, then timestamp desc
     history.sort_by(|a, b| {
         match b.pinned.cmp(&a.pinned) {
@@ -387,6 +446,7 @@ fn enforce_history_order_and_limit(history: &mut Vec<ClipboardHistoryEntry>, lim
             other => other,
         }
     });
+    orphaned
 }
 
 fn load_clipboard_history_from_disk(limit: usize) -> Vec<ClipboardHistoryEntry> {
@@ -394,7 +454,19 @@ fn load_clipboard_history_from_disk(limit: usize) -> Vec<ClipboardHistoryEntry>
         if path.exists() {
             if let Ok(content) = fs::read_to_string(&path) {
                 if let Ok(mut v) = serde_json::from_str::<Vec<ClipboardHistoryEntry>>(&content) {
-                    enforce_history_order_and_limit(&mut v, limit);
+                    // Histories saved before sizes were stored
+                    for entry in v.iter_mut().filter(|e| e.size_bytes == 0) {
+                        entry.size_bytes = clipboard::measure_entry(entry);
+                    }
+                    // Older ids were only the capture millisecond and could repeat
+                    let mut seen = HashSet::new();
+                    for entry in v.iter_mut() {
+                        if !seen.insert(entry.id.clone()) {
+                            entry.id = clipboard::new_clip_id(entry.timestamp);
+                            seen.insert(entry.id.clone());
+                        }
+                    }
+                    clipboard::remove_images(enforce_history_order_and_limit(&mut v, limit));
                     return v;
                 }
             }
@@ -405,7 +477,9 @@ fn load_clipboard_history_from_disk(limit: usize) -> Vec<ClipboardHistoryEntry>
 
 fn save_clipboard_history_to_disk(history: &Vec<ClipboardHistoryEntry>) -> Result<(), String> {
     let path = get_clipboard_history_file()?;
-    let json = serde_json::to_string_pretty(history)
+    // Memory-only entries hold secrets; they never touch the disk
+    let persisted: Vec<&ClipboardHistoryEntry> = history.iter().filter(|e| e.expires_at.is_none()).collect();
+    let json = serde_json::to_string_pretty(&persisted)
         .map_err(|e| format!("Failed to serialize clipboard history: {}", e))?;
     fs::write(&path, json)
         .map_err(|e| format!("Failed to write clipboard history: {}", e))
@@ -423,89 +497,196 @@ fn new_window(app_handle: tauri::AppHandle) -> Result<(), String> {
     Ok(())
 }
 
+// Builds a note from Markdown text, keeping the id, dates, tags and links recorded
+// by a previous export. The recorded id is only reused if no note has it yet.
+fn note_from_markdown(text: &str, fallback_title: &str, app_state: &AppState) -> Result<Note, String> {
+    let parsed = markdown::parse_markdown(text);
+    let now = Utc::now();
+
+    let id_taken = |id: &str| {
+        app_state.note_metadata.lock().map(|index| index.contains_key(id)).unwrap_or(true)
+    };
+    let id = match parsed.id {
+        Some(id) if markdown::is_valid_note_id(&id) && !id_taken(&id) => id,
+        _ => format!("note_{}", now.timestamp_millis()),
+    };
+    let created_at = parsed.created_at.unwrap_or(now);
+
+    Ok(Note {
+        id,
+        title: parsed.title.unwrap_or_else(|| fallback_title.to_string()),
+        content: parsed.content,
+        links: parsed.links,
+        created_at,
+        updated_at: parsed.updated_at.unwrap_or(created_at),
+        tags: parsed.tags,
+        capture_type: parsed.capture_type.or_else(|| Some("text".to_string())),
+        source_app: parsed.source_app,
+        window_title: None,
+        pinned: parsed.pinned,
+        favorite: parsed.favorite,
+        archived: parsed.archived,
+        sort_key: 0,
+        notebook: parsed.notebook,
+    })
+}
+
+// Imports one file as a note: HTML and DOCX are converted to Markdown, anything
+// else is read as Markdown/plain text. Returns the new note's id.
+fn import_file_as_note(file_path: &Path, app_handle: &tauri::AppHandle, app_state: &AppState) -> Result<String, String> {
+    let id = if import::document::is_document(file_path) {
+        let report = run_import(file_path, false, app_handle, app_state, |ctx| {
+            import::document::import_document(file_path, ctx)
+        })?;
+        match report.notes.first() {
+            Some(note) => note.id.clone(),
+            None => return Err(report.skipped.first().map(|s| s.reason.clone()).unwrap_or_else(|| "Nothing to import".to_string())),
+        }
+    } else {
+        let content = fs::read_to_string(file_path)
+            .map_err(|e| format!("Failed to read file: {}", e))?;
+
+        let filename = file_path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or("Imported Note");
+
+        let note = note_from_markdown(&content, filename, app_state)?;
+
+        // Save to disk and index
+        store_note(app_state, &note)?;
+        let _ = app_handle.emit("note-saved", ());
+        note.id
+    };
+
+    // Track recents in the UI
+    let _ = app_handle.emit("recent-file-opened", file_path.to_string_lossy().to_string());
+    Ok(id)
+}
+
 #[command]
 fn import_note_from_file(app_handle: tauri::AppHandle, app_state: tauri::State<'_, AppState>) -> Result<String, String> {
-    // Ask user for a text/markdown file and import it as a new note
+    // Ask user for a document and import it as a new note
     let file_path = FileDialog::new()
         .set_title("Open File")
+        .add_filter("Documents", &["txt", "md", "html", "htm", "docx"])
         .add_filter("Text / Markdown", &["txt", "md"])
+        .add_filter("HTML", &["html", "htm"])
+        .add_filter("Word", &["docx"])
         .add_filter("All files", &["*"])
         .pick_file()
         .ok_or("User cancelled the dialog")?;
 
-    let content = fs::read_to_string(&file_path)
-        .map_err(|e| format!("Failed to read file: {}", e))?;
-
-    let filename = file_path
-        .file_stem()
-        .and_then(|s| s.to_str())
-        .unwrap_or("Imported Note");
+    import_file_as_note(&file_path, &app_handle, &app_state)
+}
 
-    let now = Utc::now();
-    let id = format!("note_{}", now.timestamp_millis());
-    let note = Note {
-        id: id.clone(),
-        title: filename.to_string(),
-        content,
-        links: Vec::new(),
-        created_at: now,
-        updated_at: now,
-        tags: Vec::new(),
-        capture_type: Some("text".to_string()),
-        source_app: None,
-        window_title: None,
-    };
+#[command]
+fn import_note_from_path(path: String, app_handle: tauri::AppHandle, app_state: tauri::State<'_, AppState>) -> Result<String, String> {
+    import_file_as_note(&PathBuf::from(&path), &app_handle, &app_state)
+}
 
-    // Save to memory and disk
-    if let Ok(mut notes) = app_state.notes.lock() {
-        notes.push(note.clone());
+// Runs an importer against the note store, streaming "import-progress" events.
+// Notes are indexed as they are saved; the UI is told to refresh once at the end.
+fn run_import(
+    source: &Path,
+    dry_run: bool,
+    app_handle: &tauri::AppHandle,
+    app_state: &AppState,
+    importer: impl FnOnce(&mut import::ImportContext) -> Result<(), String>,
+) -> Result<ImportReport, String> {
+    let existing_ids: HashSet<String> = app_state.note_metadata.lock()
+        .map_err(|_| "Failed to lock note metadata".to_string())?
+        .keys()
+        .cloned()
+        .collect();
+    let mut ctx = import::ImportContext::new(
+        source,
+        dry_run,
+        existing_ids,
+        |note| store_imported_note(app_state, note),
+        |progress| { let _ = app_handle.emit("import-progress", progress); },
+    );
+    let result = importer(&mut ctx);
+    let report = ctx.finish();
+    if !dry_run && !report.notes.is_empty() {
+        let index = app_state.note_metadata.lock()
+            .map_err(|_| "Failed to lock note metadata".to_string())?;
+        note_store::save_index(&index)?;
     }
-    save_note_to_disk(&note)?;
+    result?;
 
-    // Emit events so UI can refresh and track recents
-    let _ = app_handle.emit("note-saved", ());
-    let _ = app_handle.emit("recent-file-opened", file_path.to_string_lossy().to_string());
+    if !dry_run && !report.notes.is_empty() {
+        let _ = app_handle.emit("note-saved", ());
+    }
+    println!(
+        "📥 Imported {} note(s) from {} ({} skipped{})",
+        report.notes.len(),
+        report.source,
+        report.skipped.len(),
+        if dry_run { ", dry run" } else { "" }
+    );
+    Ok(report)
+}
 
-    Ok(id)
+#[tauri::command]
+async fn import_folder(path: String, options: Option<FolderImportOptions>, app_handle: tauri::AppHandle, app_state: tauri::State<'_, AppState>) -> Result<ImportReport, String> {
+    let options = options.unwrap_or_default();
+    let root = PathBuf::from(&path);
+    run_import(&root, options.dry_run, &app_handle, &app_state, |ctx| {
+        import::folder::import_folder(&root, &options, ctx)
+    })
 }
 
-#[command]
-fn import_note_from_path(path: String, app_handle: tauri::AppHandle, app_state: tauri::State<'_, AppState>) -> Result<String, String> {
+#[tauri::command]
+async fn import_enex(path: String, app_handle: tauri::AppHandle, app_state: tauri::State<'_, AppState>) -> Result<ImportReport, String> {
     let file_path = PathBuf::from(&path);
-    let content = fs::read_to_string(&file_path)
-        .map_err(|e| format!("Failed to read file: {}", e))?;
+    run_import(&file_path, false, &app_handle, &app_state, |ctx| {
+        import::enex::import_enex(&file_path, ctx)
+    })
+}
 
-    let filename = file_path
-        .file_stem()
-        .and_then(|s| s.to_str())
-        .unwrap_or("Imported Note");
+#[tauri::command]
+async fn import_notion_export(zip_path: String, app_handle: tauri::AppHandle, app_state: tauri::State<'_, AppState>) -> Result<ImportReport, String> {
+    let file_path = PathBuf::from(&zip_path);
+    run_import(&file_path, false, &app_handle, &app_state, |ctx| {
+        import::notion::import_notion_export(&file_path, ctx)
+    })
+}
 
-    let now = Utc::now();
-    let id = format!("note_{}", now.timestamp_millis());
-    let note = Note {
-        id: id.clone(),
-        title: filename.to_string(),
-        content,
-        links: Vec::new(),
-        created_at: now,
-        updated_at: now,
-        tags: Vec::new(),
-        capture_type: Some("text".to_string()),
-        source_app: None,
-        window_title: None,
-    };
+#[tauri::command]
+async fn import_google_keep(takeout_dir: String, app_handle: tauri::AppHandle, app_state: tauri::State<'_, AppState>) -> Result<ImportReport, String> {
+    let dir = PathBuf::from(&takeout_dir);
+    run_import(&dir, false, &app_handle, &app_state, |ctx| {
+        import::keep::import_google_keep(&dir, ctx)
+    })
+}
 
-    if let Ok(mut notes) = app_state.notes.lock() {
-        notes.push(note.clone());
-    }
-    save_note_to_disk(&note)?;
-    let _ = app_handle.emit("note-saved", ());
-    let _ = app_handle.emit("recent-file-opened", file_path.to_string_lossy().to_string());
-    Ok(id)
+#[tauri::command]
+async fn import_joplin(path: String, app_handle: tauri::AppHandle, app_state: tauri::State<'_, AppState>) -> Result<ImportReport, String> {
+    let source = PathBuf::from(&path);
+    run_import(&source, false, &app_handle, &app_state, |ctx| {
+        import::joplin::import_joplin(&source, ctx)
+    })
+}
+
+#[tauri::command]
+async fn import_standard_notes(path: String, app_handle: tauri::AppHandle, app_state: tauri::State<'_, AppState>) -> Result<ImportReport, String> {
+    let source = PathBuf::from(&path);
+    run_import(&source, false, &app_handle, &app_state, |ctx| {
+        import::standard_notes::import_standard_notes(&source, ctx)
+    })
+}
+
+#[tauri::command]
+async fn import_pdf(path: String, app_handle: tauri::AppHandle, app_state: tauri::State<'_, AppState>) -> Result<ImportReport, String> {
+    let file_path = PathBuf::from(&path);
+    run_import(&file_path, false, &app_handle, &app_state, |ctx| {
+        import::pdf::import_pdf(&file_path, ctx)
+    })
 }
 
 #[command]
-fn save_image_base64(data: String, suggested_name: Option<String>) -> Result<String, String> {
+fn save_image_base64(data: String, suggested_name: Option<String>, app_state: tauri::State<'_, AppState>) -> Result<String, String> {
     // Expect data URL: data:image/png;base64,XXXX
     let (mime, b64) = if let Some(comma_idx) = data.find(",") {
         let header = &data[..comma_idx];
@@ -549,9 +730,30 @@ fn save_image_base64(data: String, suggested_name: Option<String>) -> Result<Str
     fs::write(&filepath, bytes)
         .map_err(|e| format!("Failed to write image: {}", e))?;
 
+    // Recognize text in the background so the image becomes searchable
+    if let Ok(queue) = app_state.ocr_queue.lock() {
+        let _ = queue.send(OcrJob::Image(filepath.clone()));
+    }
+
     Ok(filepath.to_string_lossy().to_string())
 }
 
+#[tauri::command]
+async fn ocr_image(path: String, force: Option<bool>, app_handle: tauri::AppHandle, app_state: tauri::State<'_, AppState>) -> Result<String, String> {
+    let image = export::resolve_image_src(&path)
+        .ok_or("Image is not in the images directory")?;
+    let text = ocr::ocr_image(&image, force.unwrap_or(false))?;
+    if !ocr::refresh_notes_for_image(&app_state, &image)?.is_empty() {
+        let _ = app_handle.emit("note-saved", ());
+    }
+    Ok(text)
+}
+
+#[tauri::command]
+async fn get_ocr_status() -> OcrStatus {
+    ocr::status()
+}
+
 #[command]
 fn save_sidebar_state(state: SidebarState, app_state: tauri::State<'_, AppState>) -> Result<(), String> {
     println!("💾 Saving sidebar state with {} notes", state.notes.len());
@@ -629,15 +831,15 @@ fn save_note(title: String, content: String, links: Vec<String>, app_state: taur
         capture_type,
         source_app,
         window_title,
+        pinned: false,
+        favorite: false,
+        archived: false,
+        sort_key: 0,
+        notebook: None,
     };
 
-    // Save to memory
-    if let Ok(mut notes) = app_state.notes.lock() {
-        notes.push(note.clone());
-    }
-
-    // Save to disk
-    save_note_to_disk(&note)?;
+    // Save to disk and index
+    store_note(&app_state, &note)?;
 
     println!("✅ Note saved with ID: {}", id);
     Ok(id)
@@ -645,80 +847,190 @@ fn save_note(title: String, content: String, links: Vec<String>, app_state: taur
 
 #[command]
 fn update_note(id: String, title: String, content: String, links: Vec<String>, app_state: tauri::State<'_, AppState>) -> Result<(), String> {
-    if let Ok(mut notes) = app_state.notes.lock() {
-        if let Some(note) = notes.iter_mut().find(|n| n.id == id) {
-            note.title = title;
-            note.content = content;
-            note.links = links;
-            note.updated_at = Utc::now();
-            // update context
-            note.capture_type = Some(if !note.links.is_empty() { "link".to_string() } else { detect_capture_type(&note.content) });
-            let (win_title, app_name) = get_active_window_info();
-            note.window_title = win_title;
-            note.source_app = app_name;
-            // regenerate tags from links
-            let mut tags: Vec<String> = Vec::new();
-            for l in &note.links {
-                if let Some(d) = extract_domain(l) {
-                    tags.push(match d.as_str() { "github.com" => "github".to_string(), "docs.rs" => "rust-docs".to_string(), _ => d.split('.').next().unwrap_or(&d).to_string() });
-                }
-            }
-            note.tags = tags;
+    update_stored_note(&app_state, &id, title, content, links)?;
+    println!("✅ Note updated: {}", id);
+    Ok(())
+}
 
-            // Save updated note to disk
-            save_note_to_disk(note)?;
+// Tag a note gets for linking to `link`'s domain
+fn link_tag(link: &str) -> Option<String> {
+    extract_domain(link).map(|d| match d.as_str() {
+        "github.com" => "github".to_string(),
+        "docs.rs" => "rust-docs".to_string(),
+        _ => d.split('.').next().unwrap_or(&d).to_string(),
+    })
+}
 
-            println!("✅ Note updated: {}", id);
-            Ok(())
-        } else {
-            Err("Note not found".to_string())
+fn update_stored_note(app_state: &AppState, id: &str, title: String, content: String, links: Vec<String>) -> Result<(), String> {
+    let mut index = app_state.note_metadata.lock()
+        .map_err(|_| "Failed to lock note metadata".to_string())?;
+    if !index.contains_key(id) {
+        return Err("Note not found".to_string());
+    }
+    let mut note = note_store::read_note(id)?;
+    // Tags derived from the previous links go; imported and user-set tags stay
+    let old_link_tags: Vec<String> = note.links.iter().filter_map(|l| link_tag(l)).collect();
+    let new_link_tags: Vec<String> = links.iter().filter_map(|l| link_tag(l)).collect();
+    note.tags.retain(|t| new_link_tags.contains(t) || !old_link_tags.contains(t));
+    note.title = title;
+    note.content = content;
+    note.links = links;
+    note.updated_at = Utc::now();
+    // update context
+    note.capture_type = Some(if !note.links.is_empty() { "link".to_string() } else { detect_capture_type(&note.content) });
+    let (win_title, app_name) = get_active_window_info();
+    note.window_title = win_title;
+    note.source_app = app_name;
+    for tag in new_link_tags {
+        if !note.tags.contains(&tag) {
+            note.tags.push(tag);
         }
-    } else {
-        Err("Failed to lock notes state".to_string())
     }
+
+    // Save updated note to disk
+    note_store::write_note(&mut index, &note)
 }
 
 #[command]
 fn load_note(id: String, app_state: tauri::State<'_, AppState>) -> Result<Note, String> {
-    if let Ok(notes) = app_state.notes.lock() {
-        if let Some(note) = notes.iter().find(|n| n.id == id) {
-            Ok(note.clone())
-        } else {
-            Err("Note not found".to_string())
-        }
-    } else {
-        Err("Failed to lock notes state".to_string())
-    }
+    // Bodies are not kept in memory; read the latest version from disk
+    read_indexed_note(&app_state, &id)
 }
 
-#[command]
-fn list_notes(app_state: tauri::State<'_, AppState>) -> Result<Vec<NoteMetadata>, String> {
-    if let Ok(notes) = app_state.notes.lock() {
-        let metadata: Vec<NoteMetadata> = notes
+// Pinned notes always come first; within each group the requested mode applies.
+// Ties fall back to updated_at descending.
+fn sort_notes(notes: &mut [&NoteMetadata], mode: NoteSortMode) {
+    notes.sort_by(|a, b| {
+        let by_mode = match mode {
+            NoteSortMode::Updated => b.updated_at.cmp(&a.updated_at),
+            NoteSortMode::Created => b.created_at.cmp(&a.created_at),
+            NoteSortMode::Title => a.title.to_lowercase().cmp(&b.title.to_lowercase()),
+            NoteSortMode::Manual => a.sort_key.cmp(&b.sort_key),
+        };
+        b.pinned.cmp(&a.pinned)
+            .then(by_mode)
+            .then_with(|| b.updated_at.cmp(&a.updated_at))
+            .then_with(|| a.id.cmp(&b.id))
+    });
+}
+
+// Cursor pagination over an already sorted list. The cursor is the id of the
+// last item of the previous page; `None` starts from the beginning.
+fn page_after<'a, T>(items: Vec<&'a T>, id_of: impl Fn(&T) -> &str, limit: Option<usize>, cursor: Option<&str>) -> Result<Vec<&'a T>, String> {
+    let start = match cursor {
+        Some(c) => items
             .iter()
-            .map(|note| NoteMetadata {
-                id: note.id.clone(),
-                title: note.title.clone(),
-                created_at: note.created_at,
-                updated_at: note.updated_at,
-            })
+            .position(|item| id_of(item) == c)
+            .map(|pos| pos + 1)
+            .ok_or("Invalid cursor")?,
+        None => 0,
+    };
+    Ok(items.into_iter().skip(start).take(limit.unwrap_or(usize::MAX)).collect())
+}
+
+#[command]
+fn list_notes(sort: Option<NoteSortMode>, limit: Option<usize>, cursor: Option<String>, app_state: tauri::State<'_, AppState>) -> Result<Vec<NoteMetadata>, String> {
+    if let Ok(index) = app_state.note_metadata.lock() {
+        let mut sorted: Vec<&NoteMetadata> = index.values().collect();
+        sort_notes(&mut sorted, sort.unwrap_or_default());
+        let metadata: Vec<NoteMetadata> = page_after(sorted, |m| &m.id, limit, cursor.as_deref())?
+            .into_iter()
+            .cloned()
             .collect();
 
         println!("📝 Listed {} notes", metadata.len());
         Ok(metadata)
     } else {
-        Err("Failed to lock notes state".to_string())
+        Err("Failed to lock note metadata".to_string())
     }
 }
 
 #[command]
-fn delete_note(id: String, app_state: tauri::State<'_, AppState>) -> Result<(), String> {
-    if let Ok(mut notes) = app_state.notes.lock() {
-        if let Some(index) = notes.iter().position(|n| n.id == id) {
-            notes.remove(index);
+fn pin_note(id: String, pinned: bool, app_state: tauri::State<'_, AppState>) -> Result<(), String> {
+    if let Ok(mut index) = app_state.note_metadata.lock() {
+        if !index.contains_key(&id) {
+            return Err("Note not found".to_string());
+        }
+        let mut note = note_store::read_note(&id)?;
+        note.pinned = pinned;
+        note_store::write_note(&mut index, &note)
+    } else {
+        Err("Failed to lock note metadata".to_string())
+    }
+}
 
+#[command]
+fn set_note_favorite(id: String, favorite: bool, app_state: tauri::State<'_, AppState>) -> Result<(), String> {
+    if let Ok(mut index) = app_state.note_metadata.lock() {
+        if !index.contains_key(&id) {
+            return Err("Note not found".to_string());
+        }
+        let mut note = note_store::read_note(&id)?;
+        note.favorite = favorite;
+        note_store::write_note(&mut index, &note)
+    } else {
+        Err("Failed to lock note metadata".to_string())
+    }
+}
+
+#[command]
+fn set_note_archived(id: String, archived: bool, app_state: tauri::State<'_, AppState>) -> Result<(), String> {
+    if let Ok(mut index) = app_state.note_metadata.lock() {
+        if !index.contains_key(&id) {
+            return Err("Note not found".to_string());
+        }
+        let mut note = note_store::read_note(&id)?;
+        note.archived = archived;
+        note_store::write_note(&mut index, &note)
+    } else {
+        Err("Failed to lock note metadata".to_string())
+    }
+}
+
+// Drag semantics: move `id` so it sits right before `before_id` (or at the end
+// when `before_id` is None), then renumber sort keys for the manual order.
+#[command]
+fn reorder_note(id: String, before_id: Option<String>, app_state: tauri::State<'_, AppState>) -> Result<(), String> {
+    if let Ok(mut index) = app_state.note_metadata.lock() {
+        let mut order: Vec<String> = {
+            let mut sorted: Vec<&NoteMetadata> = index.values().collect();
+            sort_notes(&mut sorted, NoteSortMode::Manual);
+            sorted.into_iter().map(|m| m.id.clone()).collect()
+        };
+        let from = order.iter().position(|n| *n == id).ok_or("Note not found")?;
+        let moved = order.remove(from);
+        let to = match &before_id {
+            Some(b) => order.iter().position(|n| n == b).ok_or("Target note not found")?,
+            None => order.len(),
+        };
+        order.insert(to, moved);
+
+        for (idx, note_id) in order.iter().enumerate() {
+            let key = (idx + 1) as i64;
+            let changed = index.get(note_id).map(|m| m.sort_key != key).unwrap_or(false);
+            if changed {
+                let mut note = note_store::read_note(note_id)?;
+                note.sort_key = key;
+                save_note_to_disk(&note)?;
+                if let Some(meta) = index.get_mut(note_id) {
+                    meta.sort_key = key;
+                }
+            }
+        }
+        note_store::save_index(&index)?;
+        println!("✅ Note reordered: {}", id);
+        Ok(())
+    } else {
+        Err("Failed to lock note metadata".to_string())
+    }
+}
+
+#[command]
+fn delete_note(id: String, app_state: tauri::State<'_, AppState>) -> Result<(), String> {
+    if let Ok(mut index) = app_state.note_metadata.lock() {
+        if index.contains_key(&id) {
             // Delete from disk
-            delete_note_from_disk(&id)?;
+            note_store::remove_note(&mut index, &id)?;
 
             println!("✅ Note deleted: {}", id);
             Ok(())
@@ -726,7 +1038,56 @@ fn delete_note(id: String, app_state: tauri::State<'_, AppState>) -> Result<(),
             Err("Note not found".to_string())
         }
     } else {
-        Err("Failed to lock notes state".to_string())
+        Err("Failed to lock note metadata".to_string())
+    }
+}
+
+#[command]
+fn list_tasks(filter: Option<TaskFilter>, app_state: tauri::State<'_, AppState>) -> Result<Vec<TaskItem>, String> {
+    let filter = filter.unwrap_or_default();
+    // Snapshot ids (most recently updated first) so notes are read without holding the lock
+    let ids: Vec<String> = if let Ok(index) = app_state.note_metadata.lock() {
+        let mut sorted: Vec<&NoteMetadata> = index.values().collect();
+        sort_notes(&mut sorted, NoteSortMode::Updated);
+        sorted
+            .into_iter()
+            .filter(|m| filter.note_id.as_ref().map_or(true, |id| *id == m.id))
+            .map(|m| m.id.clone())
+            .collect()
+    } else {
+        return Err("Failed to lock note metadata".to_string());
+    };
+
+    let notes = ids.iter().filter_map(|id| note_store::read_note(id).ok());
+    let tasks = tasks::collect_tasks(notes, &filter);
+    println!("☑️ Listed {} tasks", tasks.len());
+    Ok(tasks)
+}
+
+#[command]
+fn toggle_task(note_id: String, task_id: String, app_handle: tauri::AppHandle, app_state: tauri::State<'_, AppState>) -> Result<TaskItem, String> {
+    if let Ok(mut index) = app_state.note_metadata.lock() {
+        if !index.contains_key(&note_id) {
+            return Err("Note not found".to_string());
+        }
+        let mut note = note_store::read_note(&note_id)?;
+
+        let content = tasks::toggle_task_in_content(&note, &task_id)
+            .ok_or("Task not found")?;
+        note.content = content;
+        note.updated_at = Utc::now();
+        note_store::write_note(&mut index, &note)?;
+
+        let task = tasks::extract_tasks(&note)
+            .into_iter()
+            .find(|t| t.id == task_id)
+            .ok_or("Task not found")?;
+        let _ = app_handle.emit("note-saved", ());
+
+        println!("✅ Task toggled: {} in {}", task_id, note_id);
+        Ok(task)
+    } else {
+        Err("Failed to lock note metadata".to_string())
     }
 }
 
@@ -793,110 +1154,154 @@ fn show_notification(title: String, body: String) -> Result<(), String> {
 
 #[tauri::command]
 async fn download_note_as_md(note_id: String, app_state: tauri::State<'_, AppState>, app_handle: tauri::AppHandle) -> Result<(), String> {
-    // Get the note from AppState
-    if let Ok(notes) = app_state.notes.lock() {
-        let note = notes
-            .iter()
-            .find(|n| n.id == note_id)
-            .ok_or("Note not found")?;
+    // Get the note from the store
+    let note = read_indexed_note(&app_state, &note_id)?;
 
-        // Get the documents directory
-        let documents_dir = dirs::document_dir()
-            .ok_or("Failed to get documents directory")?;
-        let downloads_dir = documents_dir.join("Notes_V2").join("exports");
-        
-        // Create exports directory if it doesn't exist
-        fs::create_dir_all(&downloads_dir)
-            .map_err(|e| format!("Failed to create exports directory: {}", e))?;
+    // Exports directory inside the notes folder
+    let downloads_dir = get_exports_dir()?;
 
-        let filename = format!("{}.md", sanitize_filename::sanitize(&note.title));
-        let file_path = downloads_dir.join(&filename);
+    let filename = format!("{}.md", sanitize_filename::sanitize(&note.title));
+    let file_path = downloads_dir.join(&filename);
 
-        // Convert note to markdown
-        let markdown = convert_note_to_markdown(note);
+    // Convert note to markdown
+    let markdown = markdown::note_to_markdown(&note);
 
-        // Write the markdown file
-        fs::write(&file_path, markdown)
-            .map_err(|e| format!("Failed to write file: {}", e))?;
+    // Write the markdown file
+    fs::write(&file_path, markdown)
+        .map_err(|e| format!("Failed to write file: {}", e))?;
 
-        // Use opener plugin to open the file
-        let app_handle = app_handle.clone();
-        let _ = app_handle.opener().open_path(file_path.to_string_lossy().to_string(), None::<&str>)
-            .map_err(|e| format!("Failed to open file: {}", e))?;
+    // Use opener plugin to open the file
+    let app_handle = app_handle.clone();
+    let _ = app_handle.opener().open_path(file_path.to_string_lossy().to_string(), None::<&str>)
+        .map_err(|e| format!("Failed to open file: {}", e))?;
 
-        println!("✅ Note exported to: {:?}", file_path);
-        Ok(())
-    } else {
-        Err("Failed to lock notes state".to_string())
-    }
+    println!("✅ Note exported to: {:?}", file_path);
+    Ok(())
 }
 
 #[tauri::command]
 async fn export_note_with_dialog(note_id: String, app_state: tauri::State<'_, AppState>) -> Result<(), String> {
-    // Get the note from AppState
-    if let Ok(notes) = app_state.notes.lock() {
-        let note = notes
-            .iter()
-            .find(|n| n.id == note_id)
-            .ok_or("Note not found")?;
+    // Get the note from the store
+    let note = read_indexed_note(&app_state, &note_id)?;
 
-        // Convert note to markdown
-        let markdown = convert_note_to_markdown(note);
-        
-        // Create default filename
-        let default_filename = format!("{}.md", sanitize_filename::sanitize(&note.title));
-
-        // Show native save dialog
-        let file_path = FileDialog::new()
-            .set_title("Export Note")
-            .set_file_name(&default_filename)
-            .add_filter("Markdown files", &["md"])
-            .add_filter("All files", &["*"])
-            .save_file()
-            .ok_or("User cancelled the dialog")?;
-
-        // Write the markdown file to the selected location
-        fs::write(&file_path, markdown)
-            .map_err(|e| format!("Failed to write file: {}", e))?;
-
-        println!("✅ Note exported to: {:?}", file_path);
-        Ok(())
-    } else {
-        Err("Failed to lock notes state".to_string())
+    // Convert note to markdown
+    let markdown = markdown::note_to_markdown(&note);
+    
+    // Create default filename
+    let default_filename = format!("{}.md", sanitize_filename::sanitize(&note.title));
+
+    // Show native save dialog
+    let file_path = FileDialog::new()
+        .set_title("Export Note")
+        .set_file_name(&default_filename)
+        .add_filter("Markdown files", &["md"])
+        .add_filter("All files", &["*"])
+        .save_file()
+        .ok_or("User cancelled the dialog")?;
+
+    // Write the markdown file to the selected location
+    fs::write(&file_path, markdown)
+        .map_err(|e| format!("Failed to write file: {}", e))?;
+
+    println!("✅ Note exported to: {:?}", file_path);
+    Ok(())
+}
+
+#[tauri::command]
+async fn export_note_html(note_id: String, options: Option<HtmlExportOptions>, app_state: tauri::State<'_, AppState>, app_handle: tauri::AppHandle) -> Result<String, String> {
+    let note = read_indexed_note(&app_state, &note_id)?;
+    let options = options.unwrap_or_default();
+
+    let file_path = match &options.path {
+        Some(p) => PathBuf::from(p),
+        None => get_exports_dir()?.join(format!("{}.html", sanitize_filename::sanitize(&note.title))),
+    };
+    let html = export::html::render_note_document(&note, &options, &file_path)?;
+    fs::write(&file_path, html)
+        .map_err(|e| format!("Failed to write file: {}", e))?;
+
+    if options.open_after {
+        app_handle.opener().open_path(file_path.to_string_lossy().to_string(), None::<&str>)
+            .map_err(|e| format!("Failed to open file: {}", e))?;
     }
+
+    println!("✅ Note exported to: {:?}", file_path);
+    Ok(file_path.to_string_lossy().to_string())
 }
 
-fn convert_note_to_markdown(note: &Note) -> String {
-    let mut lines = Vec::new();
+#[tauri::command]
+async fn export_note_pdf(note_id: String, path: String, options: Option<PdfExportOptions>, app_state: tauri::State<'_, AppState>, app_handle: tauri::AppHandle) -> Result<PdfExportResult, String> {
+    export_notes_pdf(vec![note_id], path, options, app_state, app_handle).await
+}
+
+#[tauri::command]
+async fn export_notes_pdf(note_ids: Vec<String>, path: String, options: Option<PdfExportOptions>, app_state: tauri::State<'_, AppState>, app_handle: tauri::AppHandle) -> Result<PdfExportResult, String> {
+    if note_ids.is_empty() {
+        return Err("No notes selected".to_string());
+    }
+    let notes = note_ids
+        .iter()
+        .map(|id| read_indexed_note(&app_state, id))
+        .collect::<Result<Vec<Note>, String>>()?;
+    let options = options.unwrap_or_default();
 
-    // Add title as H1
-    lines.push(format!("# {}", note.title));
-    lines.push("".to_string()); // Empty line
+    let file_path = PathBuf::from(&path);
+    let missing_chars = export::pdf::write_notes_pdf(&notes, &file_path, &options)?;
+    if !missing_chars.is_empty() {
+        eprintln!("PDF export left out {} character(s) no font could draw", missing_chars.len());
+    }
 
-    // Add content
-    if !note.content.trim().is_empty() {
-        lines.push(note.content.clone());
-        lines.push("".to_string()); // Empty line
+    if options.open_after {
+        app_handle.opener().open_path(file_path.to_string_lossy().to_string(), None::<&str>)
+            .map_err(|e| format!("Failed to open file: {}", e))?;
     }
 
-    // Add links section if there are any
-    if !note.links.is_empty() {
-        lines.push("## Links".to_string());
-        lines.push("".to_string());
-        for link in &note.links {
-            lines.push(format!("- {}", link));
+    println!("✅ {} note(s) exported to: {:?}", notes.len(), file_path);
+    Ok(PdfExportResult { path, missing_chars })
+}
+
+#[tauri::command]
+async fn export_notes(note_ids: Option<Vec<String>>, dir: String, format: ExportFormat, options: Option<BulkExportOptions>, app_state: tauri::State<'_, AppState>, app_handle: tauri::AppHandle) -> Result<ExportSummary, String> {
+    // No ids means every note, in the sidebar's default order
+    let ids: Vec<String> = match note_ids {
+        Some(ids) => ids,
+        None => {
+            let index = app_state.note_metadata.lock()
+                .map_err(|_| "Failed to lock note metadata".to_string())?;
+            let mut sorted: Vec<&NoteMetadata> = index.values().collect();
+            sort_notes(&mut sorted, NoteSortMode::default());
+            sorted.into_iter().map(|m| m.id.clone()).collect()
         }
-        lines.push("".to_string()); // Empty line
+    };
+
+    let mut exporter = export::bulk::BulkExporter::new(PathBuf::from(&dir), format, options.unwrap_or_default())?;
+    let mut summary = ExportSummary { output_dir: dir, exported: 0, failed: Vec::new(), missing_chars: Vec::new() };
+    let total = ids.len();
+
+    for (i, id) in ids.into_iter().enumerate() {
+        let note = match read_indexed_note(&app_state, &id) {
+            Ok(note) => note,
+            Err(e) => {
+                summary.failed.push(ExportFailure { note_id: id.clone(), title: None, error: e });
+                let _ = app_handle.emit("export-progress", ExportProgress { done: i + 1, total, note_id: id, title: String::new() });
+                continue;
+            }
+        };
+        match exporter.export_note(&note) {
+            Ok(_) => summary.exported += 1,
+            Err(e) => summary.failed.push(ExportFailure { note_id: note.id.clone(), title: Some(note.title.clone()), error: e }),
+        }
+        let _ = app_handle.emit("export-progress", ExportProgress { done: i + 1, total, note_id: note.id, title: note.title });
     }
 
-    // Add metadata as comments
-    lines.push("---".to_string());
-    lines.push(format!("Created: {}", note.created_at.format("%Y-%m-%d %H:%M:%S")));
-    lines.push(format!("Updated: {}", note.updated_at.format("%Y-%m-%d %H:%M:%S")));
-    lines.push(format!("Note ID: {}", note.id));
-    lines.push("---".to_string());
+    summary.missing_chars = exporter.missing_chars();
+    println!("✅ Exported {} of {} note(s) to: {}", summary.exported, total, summary.output_dir);
+    Ok(summary)
+}
 
-    lines.join("\n")
+#[tauri::command]
+fn list_export_themes() -> Vec<String> {
+    export::html::available_themes()
 }
 
 #[tauri::command]
@@ -929,6 +1334,21 @@ async fn set_clipboard_monitoring_enabled(enabled: bool, app_state: tauri::State
     }
 }
 
+#[tauri::command]
+fn start_clipboard_monitor(app_handle: tauri::AppHandle, monitor: tauri::State<'_, ClipboardMonitor>) -> Result<(), String> {
+    monitor.start(app_handle)
+}
+
+#[tauri::command]
+fn stop_clipboard_monitor(monitor: tauri::State<'_, ClipboardMonitor>) -> Result<(), String> {
+    monitor.stop()
+}
+
+#[tauri::command]
+fn get_clipboard_monitor_status(monitor: tauri::State<'_, ClipboardMonitor>) -> Result<MonitorStatus, String> {
+    monitor.status()
+}
+
 #[tauri::command]
 async fn get_persistence_enabled(app_state: tauri::State<'_, AppState>) -> Result<bool, String> {
     if let Ok(enabled) = app_state.persistence_enabled.lock() {
@@ -974,8 +1394,61 @@ fn get_dedup_window_minutes(app_state: tauri::State<'_, AppState>) -> Result<u64
 }
 
 #[tauri::command]
-fn set_rules(rules: Vec<Rule>, app_state: tauri::State<'_, AppState>) -> Result<(), String> {
-    if let Ok(mut r) = app_state.rules.lock() { *r = rules; Ok(()) } else { Err("Failed to set rules".into()) }
+fn set_clipboard_poll_interval(interval_ms: u64, app_state: tauri::State<'_, AppState>) -> Result<(), String> {
+    let interval_ms = interval_ms.clamp(clipboard::MIN_POLL_INTERVAL_MS, clipboard::MAX_POLL_INTERVAL_MS);
+    if let Ok(mut v) = app_state.clipboard_poll_interval_ms.lock() { *v = interval_ms; Ok(()) } else { Err("Failed to set poll interval".into()) }
+}
+
+#[tauri::command]
+fn get_clipboard_poll_interval(app_state: tauri::State<'_, AppState>) -> Result<u64, String> {
+    if let Ok(v) = app_state.clipboard_poll_interval_ms.lock() { Ok(*v) } else { Err("Failed to get poll interval".into()) }
+}
+
+#[tauri::command]
+fn set_secret_action(action: SecretAction, app_state: tauri::State<'_, AppState>) -> Result<(), String> {
+    if let Ok(mut v) = app_state.secret_action.lock() { *v = action; Ok(()) } else { Err("Failed to set secret action".into()) }
+}
+
+#[tauri::command]
+fn get_secret_action(app_state: tauri::State<'_, AppState>) -> Result<SecretAction, String> {
+    if let Ok(v) = app_state.secret_action.lock() { Ok(*v) } else { Err("Failed to get secret action".into()) }
+}
+
+#[tauri::command]
+fn set_secret_ttl_minutes(minutes: u64, app_state: tauri::State<'_, AppState>) -> Result<(), String> {
+    if minutes == 0 { return Err("Expiry must be at least one minute".to_string()); }
+    if let Ok(mut v) = app_state.secret_ttl_minutes.lock() { *v = minutes; Ok(()) } else { Err("Failed to set secret expiry".into()) }
+}
+
+#[tauri::command]
+fn get_secret_ttl_minutes(app_state: tauri::State<'_, AppState>) -> Result<u64, String> {
+    if let Ok(v) = app_state.secret_ttl_minutes.lock() { Ok(*v) } else { Err("Failed to get secret expiry".into()) }
+}
+
+#[tauri::command]
+fn set_retention_policy(policy: RetentionPolicy, app_handle: tauri::AppHandle, app_state: tauri::State<'_, AppState>) -> Result<(), String> {
+    if let Ok(mut v) = app_state.retention.lock() { *v = policy; } else { return Err("Failed to set retention policy".into()); }
+    // Apply right away rather than on the next sweep
+    clipboard::sweep_history(&app_handle);
+    Ok(())
+}
+
+#[tauri::command]
+fn get_retention_policy(app_state: tauri::State<'_, AppState>) -> Result<RetentionPolicy, String> {
+    if let Ok(v) = app_state.retention.lock() { Ok(v.clone()) } else { Err("Failed to get retention policy".into()) }
+}
+
+#[tauri::command]
+fn set_rules(rules: Vec<serde_json::Value>, app_state: tauri::State<'_, AppState>) -> Result<(), Vec<RuleError>> {
+    let note_exists = |id: &str| app_state.note_metadata.lock().is_ok_and(|index| index.contains_key(id));
+    let compiled = rules::parse(rules, &note_exists)?;
+    if let (Ok(mut r), Ok(mut c)) = (app_state.rules.lock(), app_state.compiled_rules.lock()) {
+        *r = compiled.iter().map(|c| c.rule.clone()).collect();
+        *c = Arc::new(compiled);
+        Ok(())
+    } else {
+        Err(vec![RuleError { index: 0, message: "Failed to set rules".into() }])
+    }
 }
 
 #[tauri::command]
@@ -984,24 +1457,25 @@ fn get_rules(app_state: tauri::State<'_, AppState>) -> Result<Vec<Rule>, String>
 }
 
 fn main() {
-    // Load notes from disk on startup
-    let initial_notes = match load_notes_from_disk() {
-        Ok(notes) => {
-            println!("📚 Loaded {} notes from disk", notes.len());
-            notes
+    // Load the note metadata index on startup; bodies are read on demand
+    let initial_metadata = match note_store::load_index() {
+        Ok(index) => {
+            println!("📚 Indexed {} notes from disk", index.len());
+            index
         }
         Err(e) => {
             println!("⚠️ Failed to load notes from disk: {}", e);
-            Vec::new()
+            HashMap::new()
         }
     };
 
     let initial_history = load_clipboard_history_from_disk(50);
+    let (ocr_sender, ocr_jobs) = mpsc::channel::<OcrJob>();
 
     let app_state = AppState {
         is_focused: Arc::new(Mutex::new(false)),
         last_internal_copy: Arc::new(Mutex::new(String::new())),
-        notes: Arc::new(Mutex::new(initial_notes)),
+        note_metadata: Arc::new(Mutex::new(initial_metadata)),
         sidebar_state: Arc::new(Mutex::new(None)),
         clipboard_monitoring_enabled: Arc::new(Mutex::new(true)),
         clipboard_history: Arc::new(Mutex::new(initial_history)),
@@ -1009,7 +1483,13 @@ fn main() {
         persistence_enabled: Arc::new(Mutex::new(true)),
         min_clipboard_text_length: Arc::new(Mutex::new(8)),
         dedup_window_minutes: Arc::new(Mutex::new(3)),
+        clipboard_poll_interval_ms: Arc::new(Mutex::new(clipboard::DEFAULT_POLL_INTERVAL_MS)),
         rules: Arc::new(Mutex::new(Vec::new())),
+        compiled_rules: Arc::new(Mutex::new(Arc::new(Vec::new()))),
+        secret_action: Arc::new(Mutex::new(SecretAction::default())),
+        secret_ttl_minutes: Arc::new(Mutex::new(5)),
+        retention: Arc::new(Mutex::new(RetentionPolicy::default())),
+        ocr_queue: Arc::new(Mutex::new(ocr_sender)),
     };
 
     tauri::Builder::default()
@@ -1021,6 +1501,7 @@ fn main() {
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_opener::init())
         .manage(app_state)
+        .manage(ClipboardMonitor::default())
         .invoke_handler(tauri::generate_handler![
             open_url,
             save_note,
@@ -1028,9 +1509,22 @@ fn main() {
             load_note,
             list_notes,
             delete_note,
+            pin_note,
+            set_note_favorite,
+            set_note_archived,
+            reorder_note,
+            list_tasks,
+            toggle_task,
             new_window,
             import_note_from_file,
             import_note_from_path,
+            import_folder,
+            import_enex,
+            import_notion_export,
+            import_google_keep,
+            import_joplin,
+            import_standard_notes,
+            import_pdf,
             minimize_window,
             maximize_window,
             close_window,
@@ -1040,10 +1534,20 @@ fn main() {
             show_notification,
             download_note_as_md,
             export_note_with_dialog,
+            export_note_html,
+            export_note_pdf,
+            export_notes_pdf,
+            export_notes,
+            list_export_themes,
             mark_internal_copy,
             get_clipboard_monitoring_enabled,
             set_clipboard_monitoring_enabled,
+            start_clipboard_monitor,
+            stop_clipboard_monitor,
+            get_clipboard_monitor_status,
             save_image_base64,
+            ocr_image,
+            get_ocr_status,
             get_clipboard_history,
             set_clipboard_history_limit,
             get_clipboard_history_limit,
@@ -1051,12 +1555,21 @@ fn main() {
             pin_clipboard_entry,
             delete_clipboard_entry,
             restore_clipboard_entry,
+            get_clipboard_image,
             get_persistence_enabled,
             set_persistence_enabled,
             set_min_clipboard_text_length,
             get_min_clipboard_text_length,
             set_dedup_window_minutes,
             get_dedup_window_minutes,
+            set_clipboard_poll_interval,
+            get_clipboard_poll_interval,
+            set_secret_action,
+            get_secret_action,
+            set_secret_ttl_minutes,
+            get_secret_ttl_minutes,
+            set_retention_policy,
+            get_retention_policy,
             set_rules,
             get_rules
         ])
@@ -1084,270 +1597,17 @@ fn main() {
                 // macOS vibrancy support would need to be implemented differently
             }
 
-            // Start clipboard monitoring in a separate thread
-            thread::spawn(move || {
-                let mut last_content = String::new();
-                let mut last_image_hash = String::new();
-                let mut last_snip_seen_at: Instant = Instant::now() - Duration::from_secs(60);
-                println!("Clipboard monitoring thread started");
-
-                loop {
-                    // Track if a snipping/screenshot window is active recently
-                    if let (Some(title), _) = get_active_window_info() {
-                        let t = title.to_lowercase();
-                        if is_snipping_window_title(&t) {
-                            last_snip_seen_at = Instant::now();
-                        }
-                    }
-                    // Respect the auto-copy/monitoring flag
-                    if let Some(state) = app_handle.try_state::<AppState>() {
-                        let enabled = *state.clipboard_monitoring_enabled.lock().unwrap();
-                        if !enabled {
-                            thread::sleep(Duration::from_millis(500));
-                            continue;
-                        }
-                    }
+            // OCR runs on its own thread so captures never wait on the engine
+            let ocr_handle = app_handle.clone();
+            thread::spawn(move || ocr::run_worker(ocr_handle, ocr_jobs));
 
-                    // Try to read clipboard using the plugin first
-                    let clipboard_manager = app_handle.clipboard();
-                    match clipboard_manager.read_text() {
-                        Ok(text) => {
-                            if !text.is_empty() && text != last_content {
-                                last_content = text.clone();
-                                println!("Clipboard content detected: {}", text);
-
-                                // Update clipboard history
-                                if let Some(state) = app_handle.try_state::<AppState>() {
-                                    let limit = *state.clipboard_history_limit.lock().unwrap();
-                                    let min_len = *state.min_clipboard_text_length.lock().unwrap();
-                                    let dedup_mins = *state.dedup_window_minutes.lock().unwrap();
-                                    let rules = state.rules.lock().unwrap().clone();
-
-                                    let trimmed = text.trim().to_string();
-                                    let cap_type = detect_capture_type(&trimmed);
-                                    if trimmed.len() < min_len && cap_type != "code" && cap_type != "link" {
-                                        // skip short non-code/non-link
-                                    } else {
-                                        let (win_title, app_name) = get_active_window_info();
-                                        let source_url = if is_url(&trimmed) { Some(trimmed.clone()) } else { None };
-                                        let (mut extra_tags, ignore, _merge) = apply_rules(&trimmed, source_url.as_deref(), app_name.as_deref(), &cap_type, &rules);
-                                        if !ignore {
-                                            let mut history = state.clipboard_history.lock().unwrap();
-                                            let now_ts = Utc::now();
-                                            let hash = compute_text_hash(&trimmed);
-                                            history.retain(|e| {
-                                                if let Some(h) = &e.content_hash {
-                                                    if *h == hash {
-                                                        return now_ts - e.timestamp > ChronoDuration::minutes(dedup_mins as i64);
-                                                    }
-                                                }
-                                                true
-                                            });
-                                            if let Some(url) = &source_url { extra_tags.extend(auto_tags_for_text_and_url(url)); }
-                                            history.insert(0, ClipboardHistoryEntry {
-                                                id: format!("clip_{}", now_ts.timestamp_millis()),
-                                                text: trimmed.clone(),
-                                                pinned: false,
-                                                timestamp: now_ts,
-                                                source_app: app_name,
-                                                window_title: win_title,
-                                                source_url,
-                                                capture_type: cap_type,
-                                                tags: extra_tags,
-                                                content_hash: Some(hash),
-                                            });
-                                            enforce_history_order_and_limit(&mut history, limit);
-                                        }
-                                    }
-                                }
-                                // Persist to disk if enabled
-                                if let Some(state) = app_handle.try_state::<AppState>() {
-                                    let enabled = *state.persistence_enabled.lock().unwrap();
-                                    if enabled {
-                                        if let Ok(history) = state.clipboard_history.lock() {
-                                            if let Err(e) = save_clipboard_history_to_disk(&history.clone()) {
-                                                eprintln!("Failed to save clipboard history: {}", e);
-                                            }
-                                        }
-                                    }
-                                }
-
-                                // Emit event to frontend with new clipboard content
-                                if let Err(e) = app_handle.emit(
-                                    "clipboard-changed",
-                                    ClipboardContent {
-                                        text: text.clone(),
-                                        from_app: false,
-                                    },
-                                ) {
-                                    eprintln!("Failed to emit clipboard event: {}", e);
-                                } else {
-                                    println!("Clipboard event emitted successfully");
-                                }
-                                println!("Clipboard content changed: {}", text);
-                            }
-                            // Also try to read image from clipboard when present
-                            // Using arboard as it provides raw image bytes
-                            if let Ok(img) = arboard::Clipboard::new().and_then(|mut cb| cb.get_image()) {
-                                let bytes = img.bytes.into_owned();
-                                // Compute hash to avoid duplicates
-                                let mut hasher = Sha256::new();
-                                hasher.update(&bytes);
-                                let hash = format!("{:x}", hasher.finalize());
-                                if hash != last_image_hash {
-                                    last_image_hash = hash;
-                                    // Encode to PNG
-                                    let mut png_data: Vec<u8> = Vec::new();
-                                    {
-                                        let mut encoder = Encoder::new(&mut png_data, img.width as u32, img.height as u32);
-                                        encoder.set_color(ColorType::Rgba);
-                                        encoder.set_depth(BitDepth::Eight);
-                                        match encoder.write_header() {
-                                            Ok(mut header) => {
-                                                if let Err(e) = header.write_image_data(&bytes) {
-                                                    eprintln!("Failed to write PNG data: {}", e);
-                                                }
-                                            }
-                                            Err(e) => {
-                                                eprintln!("Failed to write PNG header: {}", e);
-                                            }
-                                        }
-                                    }
-                                    if !png_data.is_empty() {
-                                        let probable_size = is_probable_screenshot(img.width as u32, img.height as u32);
-                                        let recent_snip = last_snip_seen_at.elapsed() <= Duration::from_secs(6);
-                                        let probable = probable_size || recent_snip;
-                                        let (win_title, _) = get_active_window_info();
-                                        println!("📸 Clipboard image {}x{}, window={:?}, probable_screenshot={}, recent_snip={}", img.width, img.height, win_title, probable, recent_snip);
-                                        if probable {
-                                            let b64 = base64::engine::general_purpose::STANDARD.encode(&png_data);
-                                            let data_url = format!("data:image/png;base64,{}", b64);
-                                            let payload = ClipboardImagePayload { data_url, width: img.width as u32, height: img.height as u32 };
-                                            let _ = app_handle.emit("clipboard-image", payload);
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                        Err(e) => {
-                            eprintln!("Failed to read clipboard: {}", e);
-                            // Try alternative clipboard reading method
-                            if let Ok(text) = arboard::Clipboard::new().and_then(|mut cb| cb.get_text()) {
-                                if !text.is_empty() && text != last_content {
-                                    last_content = text.clone();
-                                    println!("Clipboard content detected (alternative method): {}", text);
-                                    // Update clipboard history
-                                    if let Some(state) = app_handle.try_state::<AppState>() {
-                                        let limit = *state.clipboard_history_limit.lock().unwrap();
-                                        let min_len = *state.min_clipboard_text_length.lock().unwrap();
-                                        let dedup_mins = *state.dedup_window_minutes.lock().unwrap();
-                                        let rules = state.rules.lock().unwrap().clone();
-
-                                        let trimmed = text.trim().to_string();
-                                        let cap_type = detect_capture_type(&trimmed);
-                                        if trimmed.len() >= min_len || cap_type == "code" || cap_type == "link" {
-                                            let (win_title, app_name) = get_active_window_info();
-                                            let source_url = if is_url(&trimmed) { Some(trimmed.clone()) } else { None };
-                                            let (mut extra_tags, ignore, _merge) = apply_rules(&trimmed, source_url.as_deref(), app_name.as_deref(), &cap_type, &rules);
-                                            if !ignore {
-                                                let mut history = state.clipboard_history.lock().unwrap();
-                                                let now_ts = Utc::now();
-                                                let hash = compute_text_hash(&trimmed);
-                                                history.retain(|e| {
-                                                    if let Some(h) = &e.content_hash {
-                                                        if *h == hash {
-                                                            return now_ts - e.timestamp > ChronoDuration::minutes(dedup_mins as i64);
-                                                        }
-                                                    }
-                                                    true
-                                                });
-                                                if let Some(url) = &source_url { extra_tags.extend(auto_tags_for_text_and_url(url)); }
-                                                history.insert(0, ClipboardHistoryEntry {
-                                                    id: format!("clip_{}", now_ts.timestamp_millis()),
-                                                    text: trimmed.clone(),
-                                                    pinned: false,
-                                                    timestamp: now_ts,
-                                                    source_app: app_name,
-                                                    window_title: win_title,
-                                                    source_url,
-                                                    capture_type: cap_type,
-                                                    tags: extra_tags,
-                                                    content_hash: Some(hash),
-                                                });
-                                                enforce_history_order_and_limit(&mut history, limit);
-                                            }
-                                        }
-                                    }
-                                    // Persist to disk if enabled
-                                    if let Some(state) = app_handle.try_state::<AppState>() {
-                                        let enabled = *state.persistence_enabled.lock().unwrap();
-                                        if enabled {
-                                            if let Ok(history) = state.clipboard_history.lock() {
-                                                if let Err(e) = save_clipboard_history_to_disk(&history.clone()) {
-                                                    eprintln!("Failed to save clipboard history: {}", e);
-                                                }
-                                            }
-                                        }
-                                    }
-
-                                    if let Err(e) = app_handle.emit(
-                                        "clipboard-changed",
-                                        ClipboardContent {
-                                            text: text.clone(),
-                                            from_app: false,
-                                        },
-                                    ) {
-                                        eprintln!("Failed to emit clipboard event: {}", e);
-                                    } else {
-                                        println!("Clipboard event emitted successfully");
-                                    }
-                                }
-                            }
-                            // Attempt to read image via arboard in the fallback too
-                            if let Ok(img) = arboard::Clipboard::new().and_then(|mut cb| cb.get_image()) {
-                                let bytes = img.bytes.into_owned();
-                                let mut hasher = Sha256::new();
-                                hasher.update(&bytes);
-                                let hash = format!("{:x}", hasher.finalize());
-                                if hash != last_image_hash {
-                                    last_image_hash = hash;
-                                    let mut png_data: Vec<u8> = Vec::new();
-                                    {
-                                        let mut encoder = Encoder::new(&mut png_data, img.width as u32, img.height as u32);
-                                        encoder.set_color(ColorType::Rgba);
-                                        encoder.set_depth(BitDepth::Eight);
-                                        match encoder.write_header() {
-                                            Ok(mut header) => {
-                                                if let Err(e) = header.write_image_data(&bytes) {
-                                                    eprintln!("Failed to write PNG data: {}", e);
-                                                }
-                                            }
-                                            Err(e) => {
-                                                eprintln!("Failed to write PNG header: {}", e);
-                                            }
-                                        }
-                                    }
-                                    if !png_data.is_empty() {
-                                        let probable_size = is_probable_screenshot(img.width as u32, img.height as u32);
-                                        let recent_snip = last_snip_seen_at.elapsed() <= Duration::from_secs(6);
-                                        let probable = probable_size || recent_snip;
-                                        let (win_title, _) = get_active_window_info();
-                                        println!("📸 Clipboard image (fallback) {}x{}, window={:?}, probable_screenshot={}, recent_snip={}", img.width, img.height, win_title, probable, recent_snip);
-                                        if probable {
-                                            let b64 = base64::engine::general_purpose::STANDARD.encode(&png_data);
-                                            let data_url = format!("data:image/png;base64,{}", b64);
-                                            let payload = ClipboardImagePayload { data_url, width: img.width as u32, height: img.height as u32 };
-                                            let _ = app_handle.emit("clipboard-image", payload);
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                    }
-                    // Wait a bit before checking again
-                    thread::sleep(Duration::from_millis(500));
-                }
-            });
+            // Retention and memory-only expiry run whether or not the monitor does
+            clipboard::spawn_sweeper(app_handle.clone());
+
+            // Start clipboard monitoring in a separate thread
+            if let Err(e) = app.state::<ClipboardMonitor>().start(app_handle.clone()) {
+                eprintln!("{}", e);
+            }
 
             println!("Tauri app setup complete");
             Ok(())
@@ -1357,4 +1617,96 @@ fn main() {
             eprintln!("Error while running tauri application: {}", e);
             std::process::exit(1);
         });
-}
\ No newline at end of file
+}
+#[cfg(test)]
+fn test_app_state() -> AppState {
+    AppState {
+        is_focused: Arc::new(Mutex::new(false)),
+        last_internal_copy: Arc::new(Mutex::new(String::new())),
+        note_metadata: Arc::new(Mutex::new(HashMap::new())),
+        sidebar_state: Arc::new(Mutex::new(None)),
+        clipboard_monitoring_enabled: Arc::new(Mutex::new(true)),
+        clipboard_history: Arc::new(Mutex::new(Vec::new())),
+        clipboard_history_limit: Arc::new(Mutex::new(50)),
+        persistence_enabled: Arc::new(Mutex::new(false)),
+        min_clipboard_text_length: Arc::new(Mutex::new(8)),
+        dedup_window_minutes: Arc::new(Mutex::new(3)),
+        clipboard_poll_interval_ms: Arc::new(Mutex::new(clipboard::DEFAULT_POLL_INTERVAL_MS)),
+        rules: Arc::new(Mutex::new(Vec::new())),
+        compiled_rules: Arc::new(Mutex::new(Arc::new(Vec::new()))),
+        secret_action: Arc::new(Mutex::new(SecretAction::default())),
+        secret_ttl_minutes: Arc::new(Mutex::new(5)),
+        retention: Arc::new(Mutex::new(RetentionPolicy::default())),
+        ocr_queue: Arc::new(Mutex::new(mpsc::channel().0)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn update_keeps_front_matter_tags() {
+        let state = test_app_state();
+        let text = "---\ntitle: Reading list\ntags: [books, later]\n---\n\nSome notes";
+        let note = note_from_markdown(text, "Untitled", &state).unwrap();
+        store_note(&state, &note).unwrap();
+
+        let content = format!("{}\n\nhttps://github.com/rust-lang/rust", note.content);
+        let links = vec!["https://github.com/rust-lang/rust".to_string()];
+        update_stored_note(&state, &note.id, note.title.clone(), content, links).unwrap();
+
+        let exported = markdown::note_to_markdown(&note_store::read_note(&note.id).unwrap());
+        let parsed = markdown::parse_markdown(&exported);
+        assert_eq!(parsed.tags, vec!["books", "later", "github"]);
+        assert_eq!(parsed.title.as_deref(), Some("Reading list"));
+
+        let mut index = state.note_metadata.lock().unwrap();
+        note_store::remove_note(&mut index, &note.id).unwrap();
+    }
+
+    #[test]
+    fn removing_a_link_drops_only_its_domain_tag() {
+        let state = test_app_state();
+        let text = "---\nid: note_test_link_tags\ntitle: Sources\ntags: [research]\n---\n\nSee links";
+        let note = note_from_markdown(text, "Untitled", &state).unwrap();
+        store_note(&state, &note).unwrap();
+
+        let links = vec!["https://github.com/a/b".to_string(), "https://docs.rs/regex".to_string()];
+        update_stored_note(&state, &note.id, note.title.clone(), note.content.clone(), links).unwrap();
+        assert_eq!(note_store::read_note(&note.id).unwrap().tags, vec!["research", "github", "rust-docs"]);
+
+        let links = vec!["https://docs.rs/regex".to_string()];
+        update_stored_note(&state, &note.id, note.title.clone(), note.content.clone(), links).unwrap();
+        assert_eq!(note_store::read_note(&note.id).unwrap().tags, vec!["research", "rust-docs"]);
+
+        let mut index = state.note_metadata.lock().unwrap();
+        note_store::remove_note(&mut index, &note.id).unwrap();
+    }
+
+    #[test]
+    fn history_pages_cover_entries_captured_in_the_same_millisecond() {
+        let now = Utc::now();
+        let history: Vec<ClipboardHistoryEntry> = (0..5)
+            .map(|i| serde_json::from_value(serde_json::json!({
+                "id": clipboard::new_clip_id(now),
+                "text": format!("entry {}", i),
+                "pinned": false,
+                "timestamp": now,
+            })).unwrap())
+            .collect();
+
+        let mut seen = Vec::new();
+        let mut cursor: Option<String> = None;
+        loop {
+            let page = page_after(history.iter().collect(), |e| &e.id, Some(2), cursor.as_deref()).unwrap();
+            if page.is_empty() {
+                break;
+            }
+            seen.extend(page.iter().map(|e| e.text.clone()));
+            cursor = page.last().map(|e| e.id.clone());
+        }
+        let expected: Vec<String> = history.iter().map(|e| e.text.clone()).collect();
+        assert_eq!(seen, expected);
+    }
+}
@@ -0,0 +1,332 @@
+use app_lib::Note;
+use chrono::{DateTime, NaiveDate, NaiveDateTime, SecondsFormat, Utc};
+use regex::Regex;
+use serde::Serialize;
+use serde_yaml::{Mapping, Value};
+use std::sync::OnceLock;
+
+#[derive(Serialize)]
+struct FrontMatterOut<'a> {
+    id: &'a str,
+    title: &'a str,
+    created: String,
+    updated: String,
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    tags: &'a [String],
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    links: &'a [String],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    notebook: Option<&'a str>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pinned: bool,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    favorite: bool,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    archived: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    capture_type: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    source_app: Option<&'a str>,
+}
+
+/// Everything an imported Markdown file can tell us about the note it came from.
+/// Fields missing from the file are left empty for the caller to fill in.
+#[derive(Debug, Default, Clone)]
+pub struct ParsedMarkdown {
+    pub id: Option<String>,
+    pub title: Option<String>,
+    pub content: String,
+    pub tags: Vec<String>,
+    pub links: Vec<String>,
+    pub created_at: Option<DateTime<Utc>>,
+    pub updated_at: Option<DateTime<Utc>>,
+    pub notebook: Option<String>,
+    pub pinned: bool,
+    pub favorite: bool,
+    pub archived: bool,
+    pub capture_type: Option<String>,
+    pub source_app: Option<String>,
+}
+
+fn format_date(date: &DateTime<Utc>) -> String {
+    date.to_rfc3339_opts(SecondsFormat::AutoSi, true)
+}
+
+/// Serializes a note as Markdown with YAML front matter followed by the title as H1.
+pub fn note_to_markdown(note: &Note) -> String {
+    let front = FrontMatterOut {
+        id: &note.id,
+        title: &note.title,
+        created: format_date(&note.created_at),
+        updated: format_date(&note.updated_at),
+        tags: &note.tags,
+        links: &note.links,
+        notebook: note.notebook.as_deref(),
+        pinned: note.pinned,
+        favorite: note.favorite,
+        archived: note.archived,
+        capture_type: note.capture_type.as_deref(),
+        source_app: note.source_app.as_deref(),
+    };
+    let yaml = serde_yaml::to_string(&front).unwrap_or_default();
+
+    let mut out = format!("---\n{}---\n\n# {}\n", yaml, note.title);
+    if !note.content.trim().is_empty() {
+        out.push('\n');
+        out.push_str(note.content.trim_end());
+        out.push('\n');
+    }
+    out
+}
+
+// Accepts RFC 3339, "YYYY-MM-DD HH:MM[:SS]" (as written by older exports) and bare dates
+pub fn parse_date(text: &str) -> Option<DateTime<Utc>> {
+    let text = text.trim().trim_matches(|c| c == '"' || c == '\'');
+    if let Ok(d) = DateTime::parse_from_rfc3339(text) {
+        return Some(d.with_timezone(&Utc));
+    }
+    for fmt in ["%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M:%S", "%Y-%m-%d %H:%M", "%Y-%m-%dT%H:%M"] {
+        if let Ok(d) = NaiveDateTime::parse_from_str(text, fmt) {
+            return Some(d.and_utc());
+        }
+    }
+    NaiveDate::parse_from_str(text, "%Y-%m-%d")
+        .ok()
+        .and_then(|d| d.and_hms_opt(0, 0, 0))
+        .map(|d| d.and_utc())
+}
+
+fn value_str(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.trim().to_string()).filter(|s| !s.is_empty()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+// Lists may be written as YAML sequences or as a single "a, b" / "a b" string
+fn value_list(value: &Value, split_spaces: bool) -> Vec<String> {
+    match value {
+        Value::Sequence(items) => items.iter().filter_map(value_str).collect(),
+        Value::String(s) => s
+            .split(|c: char| c == ',' || (split_spaces && c.is_whitespace()))
+            .map(|t| t.trim().to_string())
+            .filter(|t| !t.is_empty())
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn value_bool(value: &Value) -> bool {
+    match value {
+        Value::Bool(b) => *b,
+        Value::String(s) => matches!(s.trim().to_lowercase().as_str(), "true" | "yes" | "1"),
+        _ => false,
+    }
+}
+
+fn apply_front_matter(parsed: &mut ParsedMarkdown, map: &Mapping) {
+    for (key, value) in map {
+        let key = match key.as_str() { Some(k) => k.to_lowercase(), None => continue };
+        match key.as_str() {
+            "id" | "note_id" | "note-id" => parsed.id = value_str(value),
+            "title" => parsed.title = value_str(value),
+            "tags" | "tag" | "keywords" => {
+                for tag in value_list(value, true) {
+                    let tag = tag.trim_start_matches('#').to_string();
+                    if !tag.is_empty() && !parsed.tags.contains(&tag) {
+                        parsed.tags.push(tag);
+                    }
+                }
+            }
+            "links" | "link" | "source" | "url" | "source_url" => {
+                for link in value_list(value, false) {
+                    if !parsed.links.contains(&link) {
+                        parsed.links.push(link);
+                    }
+                }
+            }
+            "created" | "created_at" | "date" | "creation_date" => {
+                if let Some(d) = value_str(value).and_then(|s| parse_date(&s)) {
+                    parsed.created_at = Some(d);
+                }
+            }
+            "updated" | "updated_at" | "modified" | "last_modified" => {
+                if let Some(d) = value_str(value).and_then(|s| parse_date(&s)) {
+                    parsed.updated_at = Some(d);
+                }
+            }
+            "notebook" | "folder" => parsed.notebook = value_str(value),
+            "pinned" => parsed.pinned = value_bool(value),
+            "favorite" | "favourite" | "starred" => parsed.favorite = value_bool(value),
+            "archived" => parsed.archived = value_bool(value),
+            "capture_type" => parsed.capture_type = value_str(value),
+            "source_app" => parsed.source_app = value_str(value),
+            _ => {}
+        }
+    }
+}
+
+// Splits a leading "---\n...\n---" block off the text
+fn split_front_matter(text: &str) -> Option<(&str, &str)> {
+    let rest = text.strip_prefix("---")?;
+    let rest = rest.strip_prefix("\r\n").or_else(|| rest.strip_prefix('\n'))?;
+    let mut offset = 0;
+    for line in rest.split_inclusive('\n') {
+        let trimmed = line.trim_end();
+        if trimmed == "---" || trimmed == "..." {
+            return Some((&rest[..offset], &rest[offset + line.len()..]));
+        }
+        offset += line.len();
+    }
+    None
+}
+
+// Footer written by exports before front matter existed:
+// ---\nCreated: ...\nUpdated: ...\nNote ID: ...\n---
+fn legacy_footer_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(r"(?s)\n?---[ \t]*\r?\nCreated:[ \t]*([^\r\n]*)\r?\nUpdated:[ \t]*([^\r\n]*)\r?\nNote ID:[ \t]*([^\r\n]*)\r?\n---[ \t]*\s*$").unwrap()
+    })
+}
+
+// "## Links" section written by the legacy exporter right before the footer
+fn legacy_links_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"(?s)\n?## Links[ \t]*\r?\n\r?\n((?:- [^\r\n]*\r?\n?)+)\s*$").unwrap())
+}
+
+fn heading_title_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"^\s*#[ \t]+([^\r\n]+?)[ \t]*#*[ \t]*(?:\r?\n|$)").unwrap())
+}
+
+/// Parses a Markdown file produced by this app (front matter or the legacy footer)
+/// or by other tools. The title comes from front matter, else from a leading H1.
+pub fn parse_markdown(text: &str) -> ParsedMarkdown {
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+    let mut parsed = ParsedMarkdown::default();
+
+    let mut body = text;
+    if let Some((yaml, rest)) = split_front_matter(text) {
+        if let Ok(Value::Mapping(map)) = serde_yaml::from_str::<Value>(yaml) {
+            apply_front_matter(&mut parsed, &map);
+            body = rest;
+        }
+    }
+    let mut body = body.to_string();
+
+    if let Some(caps) = legacy_footer_regex().captures(&body) {
+        if parsed.created_at.is_none() { parsed.created_at = parse_date(&caps[1]); }
+        if parsed.updated_at.is_none() { parsed.updated_at = parse_date(&caps[2]); }
+        if parsed.id.is_none() { parsed.id = Some(caps[3].trim().to_string()).filter(|s| !s.is_empty()); }
+        let start = caps.get(0).map(|m| m.start()).unwrap_or(body.len());
+        body.truncate(start);
+
+        if let Some(caps) = legacy_links_regex().captures(&body) {
+            for line in caps[1].lines() {
+                let link = line.trim_start_matches("- ").trim().to_string();
+                if !link.is_empty() && !parsed.links.contains(&link) {
+                    parsed.links.push(link);
+                }
+            }
+            let start = caps.get(0).map(|m| m.start()).unwrap_or(body.len());
+            body.truncate(start);
+        }
+    }
+
+    // Drop the H1 the exporter writes, or use it as the title when none was given
+    if let Some(caps) = heading_title_regex().captures(&body) {
+        let heading = caps[1].trim().to_string();
+        let matches_title = parsed.title.as_deref().map(|t| t == heading).unwrap_or(true);
+        if matches_title {
+            parsed.title = Some(heading);
+            let end = caps.get(0).map(|m| m.end()).unwrap_or(0);
+            body = body[end..].to_string();
+        }
+    }
+
+    parsed.content = body.trim_matches(|c| c == '\n' || c == '\r').trim_end().to_string();
+    parsed
+}
+
+pub fn is_valid_note_id(id: &str) -> bool {
+    !id.is_empty() && id.len() <= 128 && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn note_round_trips_through_markdown() {
+        let note: Note = serde_json::from_value(serde_json::json!({
+            "id": "note_1700000000000",
+            "title": "Plans: Q3 \"draft\"",
+            "content": "Some **text**\n\n- [ ] a task\n\n---\n\nAfter a rule",
+            "links": ["https://github.com/rust-lang/rust", "https://docs.rs/regex"],
+            "created_at": "2024-03-05T10:20:30.123456789Z",
+            "updated_at": "2024-03-06T08:00:00.5Z",
+            "tags": ["work", "q3 plans"],
+            "notebook": "Projects/2024",
+            "pinned": true,
+            "favorite": true,
+            "archived": true,
+            "capture_type": "link",
+            "source_app": "Firefox",
+        }))
+        .unwrap();
+
+        let parsed = parse_markdown(&note_to_markdown(&note));
+        assert_eq!(parsed.id.as_deref(), Some(note.id.as_str()));
+        assert_eq!(parsed.title.as_deref(), Some(note.title.as_str()));
+        assert_eq!(parsed.content, note.content);
+        assert_eq!(parsed.created_at, Some(note.created_at));
+        assert_eq!(parsed.updated_at, Some(note.updated_at));
+        assert_eq!(parsed.tags, note.tags);
+        assert_eq!(parsed.links, note.links);
+        assert_eq!(parsed.notebook, note.notebook);
+        assert!(parsed.pinned && parsed.favorite && parsed.archived);
+        assert_eq!(parsed.capture_type.as_deref(), Some("link"));
+        assert_eq!(parsed.source_app.as_deref(), Some("Firefox"));
+    }
+
+    #[test]
+    fn unset_flags_stay_unset() {
+        let note: Note = serde_json::from_value(serde_json::json!({
+            "id": "note_2",
+            "title": "Plain",
+            "content": "",
+            "links": [],
+            "created_at": "2024-01-01T00:00:00Z",
+            "updated_at": "2024-01-01T00:00:00Z",
+        }))
+        .unwrap();
+
+        let parsed = parse_markdown(&note_to_markdown(&note));
+        assert_eq!(parsed.title.as_deref(), Some("Plain"));
+        assert_eq!(parsed.content, "");
+        assert!(parsed.tags.is_empty() && parsed.links.is_empty());
+        assert!(parsed.notebook.is_none());
+        assert!(!parsed.pinned && !parsed.favorite && !parsed.archived);
+    }
+
+    #[test]
+    fn legacy_footer_and_links_section_are_read() {
+        let text = "# Old note\n\nBody text\n\n## Links\n\n- https://example.com/a\n- https://example.com/b\n\n---\nCreated: 2023-05-01 09:30:00\nUpdated: 2023-05-02 10:00\nNote ID: note_42\n---\n";
+        let parsed = parse_markdown(text);
+        assert_eq!(parsed.id.as_deref(), Some("note_42"));
+        assert_eq!(parsed.title.as_deref(), Some("Old note"));
+        assert_eq!(parsed.content, "Body text");
+        assert_eq!(parsed.links, vec!["https://example.com/a", "https://example.com/b"]);
+        assert_eq!(parsed.created_at, parse_date("2023-05-01T09:30:00Z"));
+        assert_eq!(parsed.updated_at, parse_date("2023-05-02T10:00:00Z"));
+
+        // A "## Links" heading without the footer is ordinary content
+        let parsed = parse_markdown("# Reading\n\n## Links\n\n- https://example.com/a\n");
+        assert!(parsed.links.is_empty());
+        assert!(parsed.content.contains("## Links"));
+    }
+}
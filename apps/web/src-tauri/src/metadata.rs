@@ -0,0 +1,119 @@
+use app_lib::{Note, NoteMetadata};
+use regex::Regex;
+use std::sync::OnceLock;
+
+use crate::export::resolve_image_src;
+use crate::ocr::read_text;
+
+const EXCERPT_CHARS: usize = 160;
+const WORDS_PER_MINUTE: usize = 200;
+
+fn html_tag_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"(?s)<[^>]+>").unwrap())
+}
+
+// Markdown images, Obsidian-style embeds and HTML <img> tags
+fn image_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"(?i)!\[[^\]]*\]\([^)]*\)|!\[\[[^\]]+\]\]|<img\b").unwrap())
+}
+
+// Sources of Markdown images and HTML <img> tags; data-path wins over a data URL src
+fn image_src_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r#"(?i)!\[[^\]]*\]\(\s*<?([^)\s>]+)|<img\b[^>]*?\sdata-path\s*=\s*["']([^"']+)["']|<img\b[^>]*?\bsrc\s*=\s*["']([^"']+)["']"#).unwrap())
+}
+
+// Inline Markdown links (not images) and HTML anchors
+fn inline_link_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"(?i)(?:^|[^!])\[[^\]]*\]\([^)]+\)|<a\s[^>]*href=").unwrap())
+}
+
+fn markdown_link_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"\[([^\]]*)\]\([^)]*\)").unwrap())
+}
+
+fn markdown_syntax_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"(?m)^\s*(?:#{1,6}\s+|>\s?|[-*+]\s+\[[ xX]\]\s+|[-*+]\s+|\d+[.)]\s+)|[*_`~]+").unwrap())
+}
+
+fn decode_basic_entities(text: &str) -> String {
+    text.replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&")
+}
+
+/// Reduces note content (Markdown or editor HTML) to plain text for previews and counts.
+pub fn plain_text(content: &str) -> String {
+    let no_tags = html_tag_regex().replace_all(content, " ");
+    let no_images = image_regex().replace_all(&no_tags, " ");
+    let no_links = markdown_link_regex().replace_all(&no_images, "$1");
+    let no_syntax = markdown_syntax_regex().replace_all(&no_links, "");
+    decode_basic_entities(&no_syntax)
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn excerpt(text: &str) -> String {
+    if text.chars().count() <= EXCERPT_CHARS {
+        return text.to_string();
+    }
+    let cut: String = text.chars().take(EXCERPT_CHARS).collect();
+    // Prefer breaking on a word boundary
+    let trimmed = match cut.rfind(' ') {
+        Some(idx) if idx > EXCERPT_CHARS / 2 => &cut[..idx],
+        _ => cut.as_str(),
+    };
+    format!("{}…", trimmed.trim_end())
+}
+
+/// Text recognized in the note's local images (see `ocr`), joined for search.
+fn image_text(content: &str) -> String {
+    let mut texts: Vec<String> = Vec::new();
+    for caps in image_src_regex().captures_iter(content) {
+        let src = caps.get(1).or(caps.get(2)).or(caps.get(3)).map(|m| m.as_str()).unwrap_or("");
+        let text = resolve_image_src(src)
+            .and_then(|path| read_text(&path))
+            .map(|t| t.split_whitespace().collect::<Vec<_>>().join(" "))
+            .unwrap_or_default();
+        if !text.is_empty() && !texts.contains(&text) {
+            texts.push(text);
+        }
+    }
+    texts.join("\n")
+}
+
+pub fn compute_note_metadata(note: &Note) -> NoteMetadata {
+    let text = plain_text(&note.content);
+    let word_count = text.split_whitespace().count();
+    let reading_time_minutes = if word_count == 0 { 0 } else { word_count.div_ceil(WORDS_PER_MINUTE) as u32 };
+
+    NoteMetadata {
+        id: note.id.clone(),
+        title: note.title.clone(),
+        created_at: note.created_at,
+        updated_at: note.updated_at,
+        pinned: note.pinned,
+        favorite: note.favorite,
+        archived: note.archived,
+        sort_key: note.sort_key,
+        notebook: note.notebook.clone(),
+        excerpt: excerpt(&text),
+        word_count,
+        char_count: text.chars().count(),
+        reading_time_minutes,
+        tags: note.tags.clone(),
+        capture_type: note.capture_type.clone(),
+        link_count: note.links.len() + inline_link_regex().find_iter(&note.content).count(),
+        image_count: image_regex().find_iter(&note.content).count(),
+        image_text: image_text(&note.content),
+    }
+}
@@ -0,0 +1,189 @@
+use app_lib::{Note, NoteMetadata};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::metadata::compute_note_metadata;
+use crate::{delete_note_from_disk, get_app_data_dir, get_notes_dir, save_note_to_disk};
+
+// Only metadata stays resident; note bodies are read from disk on demand.
+// The index file caches metadata so startup does not have to parse every note.
+
+// Bump whenever `NoteMetadata` gains or changes a field, so cached entries are
+// recomputed instead of keeping serde defaults until their note is edited
+const INDEX_VERSION: u32 = 2;
+
+#[derive(Deserialize)]
+struct CachedIndex {
+    version: u32,
+    notes: Vec<NoteMetadata>,
+}
+
+fn get_notes_index_file() -> Result<PathBuf, String> {
+    let app_data_dir = get_app_data_dir()?;
+    Ok(app_data_dir.join("notes_index.json"))
+}
+
+fn modified_at(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+fn read_note_file(path: &Path) -> Result<Note, String> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read note file: {}", e))?;
+    serde_json::from_str::<Note>(&content)
+        .map_err(|e| format!("Failed to parse note file: {}", e))
+}
+
+// Empty when the file is missing, unreadable or written by another index version
+fn read_cached_index(path: &Path) -> HashMap<String, NoteMetadata> {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|content| serde_json::from_str::<CachedIndex>(&content).ok())
+        .filter(|cached| cached.version == INDEX_VERSION)
+        .map(|cached| cached.notes.into_iter().map(|m| (m.id.clone(), m)).collect())
+        .unwrap_or_default()
+}
+
+/// Builds the metadata index, re-parsing only note files modified after the
+/// index was last written (or missing from it). An index from another version is
+/// rebuilt from scratch.
+pub fn load_index() -> Result<HashMap<String, NoteMetadata>, String> {
+    let notes_dir = get_notes_dir()?;
+    let index_path = get_notes_index_file()?;
+    let index_modified = modified_at(&index_path);
+    let mut cached = read_cached_index(&index_path);
+
+    let mut index: HashMap<String, NoteMetadata> = HashMap::new();
+    let mut reparsed = 0usize;
+    if let Ok(entries) = fs::read_dir(notes_dir) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if !path.is_file() || path.extension().and_then(|s| s.to_str()) != Some("json") {
+                continue;
+            }
+            let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or_default().to_string();
+            let fresh = match (index_modified, modified_at(&path)) {
+                (Some(i), Some(m)) => m <= i,
+                _ => false,
+            };
+            if fresh {
+                if let Some(meta) = cached.remove(&stem) {
+                    index.insert(stem, meta);
+                    continue;
+                }
+            }
+            if let Ok(note) = read_note_file(&path) {
+                index.insert(note.id.clone(), compute_note_metadata(&note));
+                reparsed += 1;
+            }
+        }
+    }
+
+    // Anything left in `cached` points at a note file that no longer exists
+    if reparsed > 0 || !cached.is_empty() || index_modified.is_none() {
+        save_index(&index)?;
+    }
+    Ok(index)
+}
+
+pub fn save_index(index: &HashMap<String, NoteMetadata>) -> Result<(), String> {
+    let path = get_notes_index_file()?;
+    let entries: Vec<&NoteMetadata> = index.values().collect();
+    let json = serde_json::to_string(&serde_json::json!({ "version": INDEX_VERSION, "notes": entries }))
+        .map_err(|e| format!("Failed to serialize notes index: {}", e))?;
+    fs::write(&path, json)
+        .map_err(|e| format!("Failed to write notes index: {}", e))
+}
+
+pub fn read_note(id: &str) -> Result<Note, String> {
+    let notes_dir = get_notes_dir()?;
+    let file_path = notes_dir.join(format!("{}.json", id));
+    if !file_path.exists() {
+        return Err("Note not found".to_string());
+    }
+    read_note_file(&file_path)
+}
+
+/// Writes the note body to disk and refreshes its cached metadata.
+pub fn write_note(index: &mut HashMap<String, NoteMetadata>, note: &Note) -> Result<(), String> {
+    insert_note(index, note)?;
+    save_index(index)
+}
+
+/// `write_note` without saving the index file, for batches that call
+/// `save_index` once at the end.
+pub fn insert_note(index: &mut HashMap<String, NoteMetadata>, note: &Note) -> Result<(), String> {
+    save_note_to_disk(note)?;
+    index.insert(note.id.clone(), compute_note_metadata(note));
+    Ok(())
+}
+
+pub fn remove_note(index: &mut HashMap<String, NoteMetadata>, id: &str) -> Result<(), String> {
+    delete_note_from_disk(id)?;
+    index.remove(id);
+    save_index(index)
+}
+
+/// Recomputes the metadata of notes that embed `image`, e.g. once its OCR text
+/// is known. Returns the ids of the refreshed notes.
+pub fn refresh_notes_with_image(index: &mut HashMap<String, NoteMetadata>, image: &Path) -> Result<Vec<String>, String> {
+    let name = match image.file_name().and_then(|n| n.to_str()) {
+        Some(n) => n.to_string(),
+        None => return Ok(Vec::new()),
+    };
+    // Notes reference images by path with spaces encoded
+    let encoded = name.replace(' ', "%20");
+    let candidates: Vec<String> = index
+        .values()
+        .filter(|m| m.image_count > 0)
+        .map(|m| m.id.clone())
+        .collect();
+
+    let mut refreshed = Vec::new();
+    for id in candidates {
+        let note = match read_note(&id) {
+            Ok(n) => n,
+            Err(_) => continue,
+        };
+        if note.content.contains(&name) || note.content.contains(&encoded) {
+            index.insert(id.clone(), compute_note_metadata(&note));
+            refreshed.push(id);
+        }
+    }
+    if !refreshed.is_empty() {
+        save_index(index)?;
+    }
+    Ok(refreshed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn index_from_another_version_is_discarded() {
+        let path = std::env::temp_dir().join(format!("notes_index_test_{}.json", std::process::id()));
+        let meta = serde_json::json!({
+            "id": "note_1",
+            "title": "Cached",
+            "created_at": "2024-01-01T00:00:00Z",
+            "updated_at": "2024-01-01T00:00:00Z",
+        });
+
+        // Before versioning the index was a bare list
+        fs::write(&path, serde_json::json!([meta]).to_string()).unwrap();
+        assert!(read_cached_index(&path).is_empty());
+
+        fs::write(&path, serde_json::json!({ "version": INDEX_VERSION - 1, "notes": [meta] }).to_string()).unwrap();
+        assert!(read_cached_index(&path).is_empty());
+
+        fs::write(&path, serde_json::json!({ "version": INDEX_VERSION, "notes": [meta] }).to_string()).unwrap();
+        let cached = read_cached_index(&path);
+        assert_eq!(cached["note_1"].title, "Cached");
+
+        let _ = fs::remove_file(&path);
+    }
+}
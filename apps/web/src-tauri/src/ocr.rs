@@ -0,0 +1,234 @@
+use app_lib::{AppState, ClipboardContent, OcrJob, OcrStatus};
+use chrono::Utc;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::mpsc::Receiver;
+use std::sync::OnceLock;
+use tauri::{Emitter, Manager};
+
+use crate::{get_app_data_dir, note_store, save_clipboard_history_to_disk};
+
+// Recognized text is kept next to the image as "<file name>.ocr.txt"
+const SIDECAR_SUFFIX: &str = ".ocr.txt";
+
+#[derive(Serialize, Clone)]
+struct OcrCompletedPayload {
+    image_path: String,
+    text: String,
+    note_ids: Vec<String>,
+}
+
+pub fn sidecar_path(image: &Path) -> PathBuf {
+    let name = image.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    image.with_file_name(format!("{}{}", name, SIDECAR_SUFFIX))
+}
+
+/// Previously recognized text for an image, if any.
+pub fn read_text(image: &Path) -> Option<String> {
+    fs::read_to_string(sidecar_path(image)).ok()
+}
+
+// Sidecar exists and is at least as new as the image
+fn is_current(image: &Path) -> bool {
+    let modified = |p: &Path| fs::metadata(p).and_then(|m| m.modified()).ok();
+    match (modified(image), modified(&sidecar_path(image))) {
+        (Some(i), Some(s)) => s >= i,
+        _ => false,
+    }
+}
+
+// The installer on Windows does not put tesseract on PATH
+fn tesseract_binary() -> &'static str {
+    static BINARY: OnceLock<String> = OnceLock::new();
+    BINARY.get_or_init(|| {
+        if let Ok(path) = std::env::var("TESSERACT_PATH") {
+            return path;
+        }
+        #[cfg(target_os = "windows")]
+        {
+            for dir in ["C:\\Program Files\\Tesseract-OCR", "C:\\Program Files (x86)\\Tesseract-OCR"] {
+                let candidate = Path::new(dir).join("tesseract.exe");
+                if candidate.is_file() {
+                    return candidate.to_string_lossy().to_string();
+                }
+            }
+        }
+        "tesseract".to_string()
+    })
+}
+
+#[cfg(target_os = "windows")]
+fn tesseract_command() -> Command {
+    use std::os::windows::process::CommandExt;
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+    let mut command = Command::new(tesseract_binary());
+    command.creation_flags(CREATE_NO_WINDOW);
+    command
+}
+
+#[cfg(not(target_os = "windows"))]
+fn tesseract_command() -> Command {
+    Command::new(tesseract_binary())
+}
+
+/// Whether Tesseract can be run. OCR depends on it being installed separately;
+/// settings show the result so a missing engine does not go unnoticed.
+pub fn status() -> OcrStatus {
+    let binary = tesseract_binary().to_string();
+    match tesseract_command().arg("--version").output() {
+        Ok(output) if output.status.success() => {
+            // Older versions print the version to stderr
+            let text = if output.stdout.is_empty() { output.stderr } else { output.stdout };
+            let version = String::from_utf8_lossy(&text).lines().next().map(|l| l.trim().to_string());
+            OcrStatus { available: true, binary, version, error: None }
+        }
+        Ok(output) => OcrStatus {
+            available: false,
+            binary,
+            version: None,
+            error: Some(format!("Tesseract failed: {}", String::from_utf8_lossy(&output.stderr).trim())),
+        },
+        Err(e) => OcrStatus { available: false, binary, version: None, error: Some(format!("Tesseract not found: {}", e)) },
+    }
+}
+
+/// Runs the local Tesseract engine on an image and returns the recognized text.
+pub fn recognize(image: &Path) -> Result<String, String> {
+    let output = tesseract_command()
+        .arg(image)
+        .arg("stdout")
+        .output()
+        .map_err(|e| format!("Failed to run tesseract (is it installed?): {}", e))?;
+    if !output.status.success() {
+        return Err(format!("Tesseract failed: {}", String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    let text = String::from_utf8_lossy(&output.stdout);
+    // Tesseract pads lines and ends pages with a form feed
+    let lines: Vec<&str> = text.lines().map(|l| l.trim_matches(|c: char| c.is_whitespace() || c == '\u{c}')).collect();
+    Ok(lines.join("\n").trim().to_string())
+}
+
+/// Recognizes an image and stores the text in its sidecar file. Cached text is
+/// returned unless the image changed or `force` is set.
+pub fn ocr_image(image: &Path, force: bool) -> Result<String, String> {
+    if !force && is_current(image) {
+        if let Some(text) = read_text(image) {
+            return Ok(text);
+        }
+    }
+    let text = recognize(image)?;
+    fs::write(sidecar_path(image), &text)
+        .map_err(|e| format!("Failed to write OCR text: {}", e))?;
+    Ok(text)
+}
+
+/// Re-indexes the notes that embed `image` so their metadata carries the new text.
+pub fn refresh_notes_for_image(app_state: &AppState, image: &Path) -> Result<Vec<String>, String> {
+    let mut index = app_state.note_metadata.lock()
+        .map_err(|_| "Failed to lock note metadata".to_string())?;
+    note_store::refresh_notes_with_image(&mut index, image)
+}
+
+fn complete_image(app_handle: &tauri::AppHandle, image: &Path, text: String) {
+    let note_ids = match app_handle.try_state::<AppState>() {
+        Some(state) => refresh_notes_for_image(&state, image).unwrap_or_else(|e| {
+            eprintln!("Failed to refresh notes after OCR: {}", e);
+            Vec::new()
+        }),
+        None => Vec::new(),
+    };
+    if !note_ids.is_empty() {
+        let _ = app_handle.emit("note-saved", ());
+    }
+    let _ = app_handle.emit("ocr-completed", OcrCompletedPayload {
+        image_path: image.to_string_lossy().to_string(),
+        text,
+        note_ids,
+    });
+}
+
+// Captured images already have an "image" history entry; the recognized text
+// makes it searchable
+fn complete_capture(app_handle: &tauri::AppHandle, hash: String, text: String) {
+    let state = match app_handle.try_state::<AppState>() {
+        Some(s) => s,
+        None => return,
+    };
+    if let Ok(mut history) = state.clipboard_history.lock() {
+        // Gone when a rule ignored the image or it was deleted in the meantime
+        let entry = match history.iter_mut().find(|e| e.content_hash.as_deref() == Some(hash.as_str())) {
+            Some(e) => e,
+            None => return,
+        };
+        if entry.text == text {
+            return;
+        }
+        entry.size_bytes = (entry.size_bytes + text.len() as u64).saturating_sub(entry.text.len() as u64);
+        entry.text = text;
+        if !entry.tags.iter().any(|t| t == "ocr") {
+            entry.tags.push("ocr".to_string());
+        }
+        if *state.persistence_enabled.lock().unwrap() {
+            if let Err(e) = save_clipboard_history_to_disk(&history) {
+                eprintln!("Failed to save clipboard history: {}", e);
+            }
+        }
+    }
+    let _ = app_handle.emit("clipboard-changed", ClipboardContent { text: String::new(), from_app: false });
+}
+
+fn recognize_png(png: &[u8]) -> Result<String, String> {
+    let path = get_app_data_dir()?.join(format!("ocr_capture_{}.png", Utc::now().timestamp_millis()));
+    fs::write(&path, png)
+        .map_err(|e| format!("Failed to write capture: {}", e))?;
+    let result = recognize(&path);
+    let _ = fs::remove_file(&path);
+    result
+}
+
+/// Background OCR loop; jobs are queued through `AppState::ocr_queue`.
+pub fn run_worker(app_handle: tauri::AppHandle, jobs: Receiver<OcrJob>) {
+    // The same image is often seen again by the clipboard poller
+    let mut by_hash: HashMap<String, String> = HashMap::new();
+    // A missing tesseract would otherwise log on every capture
+    let mut logged_failure = false;
+    for job in jobs {
+        match job {
+            OcrJob::Image(path) => match ocr_image(&path, false) {
+                Ok(text) => complete_image(&app_handle, &path, text),
+                Err(e) => {
+                    if !logged_failure {
+                        eprintln!("OCR failed for {}: {}", path.display(), e);
+                    }
+                    logged_failure = true;
+                }
+            },
+            OcrJob::Capture { png, hash } => {
+                let text = match by_hash.get(&hash) {
+                    Some(t) => Ok(t.clone()),
+                    None => recognize_png(&png),
+                };
+                match text {
+                    Ok(text) => {
+                        if by_hash.len() >= 256 {
+                            by_hash.clear();
+                        }
+                        by_hash.insert(hash.clone(), text.clone());
+                        if !text.is_empty() {
+                            complete_capture(&app_handle, hash, text);
+                        }
+                    }
+                    Err(e) => {
+                        if !logged_failure {
+                            eprintln!("OCR failed for captured image: {}", e);
+                        }
+                        logged_failure = true;
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,306 @@
+use app_lib::{CompiledRule, Rule, RuleAction, RuleCondition, RuleError, RuleField, TextTransform};
+use regex::Regex;
+use std::cmp::Reverse;
+use std::collections::HashMap;
+
+// Merge actions that do not set their own window or separator
+const DEFAULT_MERGE_WINDOW_SECS: u64 = 30;
+const DEFAULT_MERGE_SEPARATOR: &str = "\n";
+
+/// The capture as rules see it. Actions that change the text or the type update it
+/// in place.
+pub struct RuleInput<'a> {
+    pub text: String,
+    pub url: Option<&'a str>,
+    pub app: Option<&'a str>,
+    pub window: Option<&'a str>,
+    pub capture_type: String,
+}
+
+impl RuleInput<'_> {
+    fn field(&self, field: RuleField) -> &str {
+        match field {
+            RuleField::Text => &self.text,
+            RuleField::Url => self.url.unwrap_or(""),
+            RuleField::App => self.app.unwrap_or(""),
+            RuleField::Window => self.window.unwrap_or(""),
+            RuleField::Type => &self.capture_type,
+        }
+    }
+}
+
+/// A merge requested by a rule; the previous capture has to match that rule too.
+pub struct Merge<'a> {
+    pub rule: &'a CompiledRule,
+    pub window_seconds: u64,
+    pub separator: &'a str,
+}
+
+/// Everything the matching rules asked for besides text and type changes.
+#[derive(Default)]
+pub struct RuleOutcome<'a> {
+    pub tags: Vec<String>,
+    pub ignore: bool,
+    pub merge: Option<Merge<'a>>,
+    pub pin: bool,
+    pub note_ids: Vec<String>,
+}
+
+fn condition_matches(condition: &RuleCondition, patterns: &HashMap<String, Regex>, input: &RuleInput) -> bool {
+    match condition {
+        RuleCondition::Match { field, pattern, negate } => {
+            let found = patterns.get(pattern).is_some_and(|re| re.is_match(input.field(*field)));
+            found != *negate
+        }
+        RuleCondition::All(conditions) => conditions.iter().all(|c| condition_matches(c, patterns, input)),
+        RuleCondition::Any(conditions) => conditions.iter().any(|c| condition_matches(c, patterns, input)),
+    }
+}
+
+pub fn matches(rule: &CompiledRule, input: &RuleInput) -> bool {
+    condition_matches(&rule.rule.when, &rule.patterns, input)
+}
+
+fn transform(text: &str, transform: &TextTransform, patterns: &HashMap<String, Regex>) -> String {
+    match transform {
+        TextTransform::Trim => text.trim().to_string(),
+        TextTransform::Lowercase => text.to_lowercase(),
+        TextTransform::Uppercase => text.to_uppercase(),
+        TextTransform::CollapseWhitespace => text.split_whitespace().collect::<Vec<_>>().join(" "),
+        TextTransform::Replace { pattern, replacement } => match patterns.get(pattern) {
+            Some(re) => re.replace_all(text, replacement.as_str()).to_string(),
+            None => text.to_string(),
+        },
+    }
+}
+
+/// Runs the rules against a capture. An ignore action ends processing right away;
+/// otherwise a matching rule with `stop_processing` does.
+pub fn apply<'a>(rules: &'a [CompiledRule], input: &mut RuleInput) -> RuleOutcome<'a> {
+    let mut ordered: Vec<&CompiledRule> = rules.iter().collect();
+    // Stable, so equal priorities keep their list order
+    ordered.sort_by_key(|r| Reverse(r.rule.priority));
+
+    let mut outcome = RuleOutcome::default();
+    for compiled in ordered {
+        let rule = &compiled.rule;
+        if !matches(compiled, input) {
+            continue;
+        }
+        for action in &rule.actions {
+            match action {
+                RuleAction::Tag { tag } => {
+                    if !outcome.tags.contains(tag) { outcome.tags.push(tag.clone()); }
+                }
+                RuleAction::Ignore => {
+                    outcome.ignore = true;
+                    return outcome;
+                }
+                RuleAction::Merge { window_seconds, separator } => {
+                    if outcome.merge.is_none() {
+                        outcome.merge = Some(Merge {
+                            rule: compiled,
+                            window_seconds: window_seconds.unwrap_or(DEFAULT_MERGE_WINDOW_SECS),
+                            separator: separator.as_deref().unwrap_or(DEFAULT_MERGE_SEPARATOR),
+                        });
+                    }
+                }
+                RuleAction::SetType { capture_type } => input.capture_type = capture_type.clone(),
+                RuleAction::Transform { transform: t } => input.text = transform(&input.text, t, &compiled.patterns),
+                RuleAction::Pin => outcome.pin = true,
+                RuleAction::RouteToNote { note_id } => {
+                    if !outcome.note_ids.contains(note_id) { outcome.note_ids.push(note_id.clone()); }
+                }
+            }
+        }
+        if rule.stop_processing {
+            break;
+        }
+    }
+    outcome
+}
+
+fn check_condition(condition: &RuleCondition) -> Result<(), String> {
+    match condition {
+        RuleCondition::Match { .. } => Ok(()),
+        RuleCondition::All(conditions) | RuleCondition::Any(conditions) => {
+            if conditions.is_empty() {
+                return Err("Condition group is empty".to_string());
+            }
+            conditions.iter().try_for_each(check_condition)
+        }
+    }
+}
+
+fn check_rule(rule: &Rule, note_exists: &dyn Fn(&str) -> bool) -> Result<(), String> {
+    check_condition(&rule.when)?;
+    if rule.actions.is_empty() {
+        return Err("Rule has no actions".to_string());
+    }
+    for action in &rule.actions {
+        match action {
+            RuleAction::Tag { tag } if tag.trim().is_empty() => return Err("Tag is empty".to_string()),
+            RuleAction::SetType { capture_type } if capture_type.trim().is_empty() => {
+                return Err("Capture type is empty".to_string());
+            }
+            RuleAction::RouteToNote { note_id } if !note_exists(note_id) => {
+                return Err(format!("Note {} not found", note_id));
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+fn patterns<'a>(rule: &'a Rule, out: &mut Vec<&'a str>) {
+    fn walk<'a>(condition: &'a RuleCondition, out: &mut Vec<&'a str>) {
+        match condition {
+            RuleCondition::Match { pattern, .. } => out.push(pattern),
+            RuleCondition::All(conditions) | RuleCondition::Any(conditions) => {
+                conditions.iter().for_each(|c| walk(c, out));
+            }
+        }
+    }
+    walk(&rule.when, out);
+    for action in &rule.actions {
+        if let RuleAction::Transform { transform: TextTransform::Replace { pattern, .. } } = action {
+            out.push(pattern);
+        }
+    }
+}
+
+/// Compiles every pattern of `rule`; fails on the first invalid one.
+pub fn compile(rule: Rule) -> Result<CompiledRule, String> {
+    let mut sources = Vec::new();
+    patterns(&rule, &mut sources);
+    let mut compiled = HashMap::new();
+    for pattern in sources {
+        if !compiled.contains_key(pattern) {
+            let re = Regex::new(pattern)
+                .map_err(|e| format!("Invalid pattern {:?}: {}", pattern, e))?;
+            compiled.insert(pattern.to_string(), re);
+        }
+    }
+    Ok(CompiledRule { rule, patterns: compiled })
+}
+
+/// Parses, checks and compiles every rule, collecting one error per bad rule.
+pub fn parse(values: Vec<serde_json::Value>, note_exists: &dyn Fn(&str) -> bool) -> Result<Vec<CompiledRule>, Vec<RuleError>> {
+    let mut rules = Vec::new();
+    let mut errors = Vec::new();
+    for (index, value) in values.into_iter().enumerate() {
+        let checked = serde_json::from_value::<Rule>(value)
+            .map_err(|e| format!("Invalid rule: {}", e))
+            .and_then(|rule| check_rule(&rule, note_exists).map(|_| rule))
+            .and_then(compile);
+        match checked {
+            Ok(rule) => rules.push(rule),
+            Err(message) => errors.push(RuleError { index, message }),
+        }
+    }
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+    Ok(rules)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn compiled(values: Vec<serde_json::Value>) -> Vec<CompiledRule> {
+        parse(values, &|id| id == "note_1").unwrap()
+    }
+
+    fn input(text: &str) -> RuleInput<'static> {
+        RuleInput { text: text.to_string(), url: None, app: Some("Firefox"), window: None, capture_type: "text".to_string() }
+    }
+
+    fn text_match(pattern: &str) -> serde_json::Value {
+        json!({ "match": { "field": "text", "pattern": pattern } })
+    }
+
+    #[test]
+    fn rules_run_by_priority_then_list_order() {
+        let rules = compiled(vec![
+            json!({ "when": text_match("."), "actions": [{ "type": "tag", "tag": "low" }] }),
+            json!({ "priority": 5, "when": text_match("."), "actions": [{ "type": "tag", "tag": "high" }] }),
+            json!({ "priority": 5, "when": text_match("."), "actions": [{ "type": "tag", "tag": "high-second" }] }),
+        ]);
+        let outcome = apply(&rules, &mut input("hello"));
+        assert_eq!(outcome.tags, vec!["high", "high-second", "low"]);
+    }
+
+    #[test]
+    fn later_rules_see_earlier_transforms_and_stop_processing_ends_the_run() {
+        let rules = compiled(vec![
+            json!({ "priority": 2, "when": text_match("^ "), "actions": [
+                { "type": "transform", "transform": "trim" },
+                { "type": "transform", "transform": { "replace": { "pattern": "o+", "replacement": "0" } } },
+            ] }),
+            json!({ "priority": 1, "when": text_match("^hell0$"), "actions": [{ "type": "tag", "tag": "seen" }], "stop_processing": true }),
+            json!({ "when": text_match("."), "actions": [{ "type": "tag", "tag": "never" }] }),
+        ]);
+        let mut capture = input("  hellooo ");
+        let outcome = apply(&rules, &mut capture);
+        assert_eq!(capture.text, "hell0");
+        assert_eq!(outcome.tags, vec!["seen"]);
+    }
+
+    #[test]
+    fn all_and_any_groups_combine_conditions() {
+        let rules = compiled(vec![
+            json!({ "when": { "all": [text_match("todo"), { "match": { "field": "app", "pattern": "Firefox" } }] },
+                    "actions": [{ "type": "tag", "tag": "all" }] }),
+            json!({ "when": { "any": [text_match("^nope$"), { "match": { "field": "app", "pattern": "Firefox", "negate": true } }] },
+                    "actions": [{ "type": "tag", "tag": "any" }] }),
+            json!({ "when": { "any": [text_match("^nope$"), text_match("todo")] },
+                    "actions": [{ "type": "tag", "tag": "any-text" }] }),
+        ]);
+        assert_eq!(apply(&rules, &mut input("todo: buy milk")).tags, vec!["all", "any-text"]);
+        assert!(apply(&rules, &mut input("groceries")).tags.is_empty());
+    }
+
+    #[test]
+    fn ignore_skips_remaining_actions_and_rules() {
+        let rules = compiled(vec![
+            json!({ "priority": 1, "when": text_match("secret"), "actions": [
+                { "type": "tag", "tag": "before" },
+                { "type": "ignore" },
+                { "type": "pin" },
+            ] }),
+            json!({ "when": text_match("."), "actions": [{ "type": "route_to_note", "note_id": "note_1" }] }),
+        ]);
+        let outcome = apply(&rules, &mut input("my secret"));
+        assert!(outcome.ignore);
+        assert!(!outcome.pin);
+        assert!(outcome.note_ids.is_empty());
+
+        let outcome = apply(&rules, &mut input("public"));
+        assert!(!outcome.ignore);
+        assert_eq!(outcome.note_ids, vec!["note_1"]);
+    }
+
+    #[test]
+    fn parse_reports_every_bad_rule_by_index() {
+        let errors = parse(vec![
+            json!({ "when": text_match("ok"), "actions": [{ "type": "pin" }] }),
+            json!({ "when": text_match("("), "actions": [{ "type": "pin" }] }),
+            json!({ "when": text_match("ok"), "actions": [] }),
+            json!({ "when": { "any": [] }, "actions": [{ "type": "pin" }] }),
+            json!({ "when": text_match("ok"), "actions": [{ "type": "route_to_note", "note_id": "note_2" }] }),
+            json!({ "when": text_match("ok"), "actions": [{ "type": "transform", "transform": { "replace": { "pattern": "[" } } }] }),
+            json!({ "actions": [{ "type": "pin" }] }),
+        ], &|id| id == "note_1").unwrap_err();
+
+        let indices: Vec<usize> = errors.iter().map(|e| e.index).collect();
+        assert_eq!(indices, vec![1, 2, 3, 4, 5, 6]);
+        assert!(errors[0].message.starts_with("Invalid pattern"));
+        assert_eq!(errors[1].message, "Rule has no actions");
+        assert_eq!(errors[2].message, "Condition group is empty");
+        assert_eq!(errors[3].message, "Note note_2 not found");
+        assert!(errors[4].message.starts_with("Invalid pattern"));
+        assert!(errors[5].message.starts_with("Invalid rule"));
+    }
+}
@@ -0,0 +1,299 @@
+use app_lib::{Note, TaskFilter, TaskItem};
+use chrono::NaiveDate;
+use regex::Regex;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use crate::export::unescape_html;
+
+// Captures: 1 = prefix up to "[", 2 = check mark, 3 = "] ", 4 = task body
+fn task_line_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"^(\s*(?:[-*+]|\d+[.)])\s+\[)([ xX])(\]\s+)(.*)$").unwrap())
+}
+
+// Editor list items whose text starts with a check mark, e.g. `<li><p>[ ] x</p></li>`.
+// Captures: 1 = check mark, 2 = task body up to the end of its paragraph or item
+fn html_task_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(r"(?is)<li\b[^>]*>\s*(?:<p\b[^>]*>\s*)?\[([ xX])\]\s*(.*?)(?:</p>|</li>|<[uo]l\b)").unwrap()
+    })
+}
+
+fn html_tag_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"(?s)<[^>]+>").unwrap())
+}
+
+fn due_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"@due\((\d{4}-\d{2}-\d{2})\)").unwrap())
+}
+
+fn hashtag_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"(?:^|\s)#([A-Za-z0-9_-]+)").unwrap())
+}
+
+fn normalize_priority(tag: &str) -> Option<&'static str> {
+    match tag.to_lowercase().as_str() {
+        "high" | "urgent" | "p1" => Some("high"),
+        "medium" | "med" | "p2" => Some("medium"),
+        "low" | "p3" => Some("low"),
+        _ => None,
+    }
+}
+
+fn priority_rank(priority: Option<&str>) -> u8 {
+    match priority {
+        Some("high") => 0,
+        Some("medium") => 1,
+        Some("low") => 2,
+        _ => 3,
+    }
+}
+
+fn is_fence(line: &str) -> bool {
+    let t = line.trim_start();
+    t.starts_with("```") || t.starts_with("~~~")
+}
+
+// Stable across toggles: derived from the note, the raw task body (annotations
+// included) and how many identical bodies precede it in the note.
+fn task_id(note_id: &str, body: &str, occurrence: usize) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(note_id.as_bytes());
+    hasher.update([0u8]);
+    hasher.update(body.trim().as_bytes());
+    hasher.update([0u8]);
+    hasher.update(occurrence.to_le_bytes());
+    let hex = format!("{:x}", hasher.finalize());
+    format!("task_{}", &hex[..12])
+}
+
+fn parse_task_body(body: &str) -> (String, Option<NaiveDate>, Option<String>, Vec<String>) {
+    let due = due_regex()
+        .captures(body)
+        .and_then(|c| NaiveDate::parse_from_str(&c[1], "%Y-%m-%d").ok());
+
+    let mut priority: Option<String> = None;
+    let mut tags: Vec<String> = Vec::new();
+    for c in hashtag_regex().captures_iter(body) {
+        let tag = &c[1];
+        match normalize_priority(tag) {
+            Some(p) if priority.is_none() => priority = Some(p.to_string()),
+            Some(_) => {}
+            None => tags.push(tag.to_string()),
+        }
+    }
+
+    let without_due = due_regex().replace_all(body, "");
+    let without_tags = hashtag_regex().replace_all(&without_due, "");
+    let text = without_tags.split_whitespace().collect::<Vec<_>>().join(" ");
+    (text, due, priority, tags)
+}
+
+// A check mark found in note content; `offset` is the byte position of the mark
+struct TaskMark {
+    line: usize,
+    offset: usize,
+    done: bool,
+    body: String,
+}
+
+// The editor saves HTML; imported and new notes may still be Markdown
+fn is_html(content: &str) -> bool {
+    content.trim_start().starts_with('<')
+}
+
+fn markdown_marks(content: &str) -> Vec<TaskMark> {
+    let mut marks = Vec::new();
+    let mut in_fence = false;
+    let mut start = 0;
+    for (idx, raw_line) in content.split('\n').enumerate() {
+        let line_start = start;
+        start += raw_line.len() + 1;
+        let line = raw_line.trim_end_matches('\r');
+        if is_fence(line) {
+            in_fence = !in_fence;
+            continue;
+        }
+        if in_fence { continue; }
+        let caps = match task_line_regex().captures(line) { Some(c) => c, None => continue };
+        marks.push(TaskMark {
+            line: idx + 1,
+            offset: line_start + caps.get(2).unwrap().start(),
+            done: &caps[2] != " ",
+            body: caps[4].to_string(),
+        });
+    }
+    marks
+}
+
+fn html_marks(content: &str) -> Vec<TaskMark> {
+    html_task_regex()
+        .captures_iter(content)
+        .map(|caps| {
+            let mark = caps.get(1).unwrap();
+            let text = html_tag_regex().replace_all(&caps[2], " ");
+            TaskMark {
+                line: content[..mark.start()].matches('\n').count() + 1,
+                offset: mark.start(),
+                done: mark.as_str() != " ",
+                body: unescape_html(&text).split_whitespace().collect::<Vec<_>>().join(" "),
+            }
+        })
+        .collect()
+}
+
+fn task_marks(content: &str) -> Vec<TaskMark> {
+    if is_html(content) { html_marks(content) } else { markdown_marks(content) }
+}
+
+/// Extracts task items from a note: Markdown `- [ ]` / `- [x]` lines outside fenced
+/// code blocks, or editor list items starting with `[ ]` / `[x]`.
+pub fn extract_tasks(note: &Note) -> Vec<TaskItem> {
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    task_marks(&note.content)
+        .into_iter()
+        .map(|mark| {
+            let occurrence = seen.entry(mark.body.trim().to_string()).or_insert(0);
+            let id = task_id(&note.id, &mark.body, *occurrence);
+            *occurrence += 1;
+
+            let (text, due, priority, tags) = parse_task_body(&mark.body);
+            TaskItem {
+                id,
+                note_id: note.id.clone(),
+                note_title: note.title.clone(),
+                line: mark.line,
+                text,
+                done: mark.done,
+                due,
+                priority,
+                tags,
+            }
+        })
+        .collect()
+}
+
+pub fn matches_filter(task: &TaskItem, filter: &TaskFilter) -> bool {
+    if let Some(done) = filter.done {
+        if task.done != done { return false; }
+    }
+    if let Some(note_id) = &filter.note_id {
+        if &task.note_id != note_id { return false; }
+    }
+    if let Some(p) = &filter.priority {
+        let wanted = normalize_priority(p);
+        if wanted.is_none() || task.priority.as_deref() != wanted { return false; }
+    }
+    if let Some(tag) = &filter.tag {
+        let tag = tag.trim_start_matches('#');
+        if !task.tags.iter().any(|t| t.eq_ignore_ascii_case(tag)) { return false; }
+    }
+    if let Some(before) = filter.due_before {
+        match task.due {
+            Some(d) if d <= before => {}
+            _ => return false,
+        }
+    }
+    if let Some(q) = &filter.query {
+        let q = q.to_lowercase();
+        if !task.text.to_lowercase().contains(&q) && !task.note_title.to_lowercase().contains(&q) {
+            return false;
+        }
+    }
+    true
+}
+
+/// Collects tasks across notes: open first, then by due date, priority and note order.
+/// Notes are consumed one at a time so only the matching tasks are kept in memory.
+pub fn collect_tasks(notes: impl IntoIterator<Item = Note>, filter: &TaskFilter) -> Vec<TaskItem> {
+    let mut tasks: Vec<TaskItem> = notes
+        .into_iter()
+        .flat_map(|note| extract_tasks(&note))
+        .filter(|t| matches_filter(t, filter))
+        .collect();
+    // Stable sort keeps note order (most recently updated first) within equal keys
+    tasks.sort_by(|a, b| {
+        a.done.cmp(&b.done)
+            .then_with(|| match (a.due, b.due) {
+                (Some(x), Some(y)) => x.cmp(&y),
+                (Some(_), None) => core::cmp::Ordering::Less,
+                (None, Some(_)) => core::cmp::Ordering::Greater,
+                (None, None) => core::cmp::Ordering::Equal,
+            })
+            .then_with(|| priority_rank(a.priority.as_deref()).cmp(&priority_rank(b.priority.as_deref())))
+    });
+    tasks
+}
+
+/// Flips the check mark of `task_id` in the note content. Returns the new content,
+/// or `None` if the task no longer exists.
+pub fn toggle_task_in_content(note: &Note, task_id: &str) -> Option<String> {
+    let target = extract_tasks(note).into_iter().position(|t| t.id == task_id)?;
+    let mark = task_marks(&note.content).into_iter().nth(target)?;
+    let new_mark = if mark.done { " " } else { "x" };
+    let mut content = note.content.clone();
+    content.replace_range(mark.offset..mark.offset + 1, new_mark);
+    Some(content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn note(content: &str) -> Note {
+        serde_json::from_value(serde_json::json!({
+            "id": "note_1",
+            "title": "Tasks",
+            "content": content,
+            "links": [],
+            "created_at": "2024-01-01T00:00:00Z",
+            "updated_at": "2024-01-01T00:00:00Z",
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn markdown_tasks_are_extracted_and_toggled() {
+        let note = note("# Tasks\n- [ ] write report @due(2024-03-01) #high\n```\n- [ ] not a task\n```\n* [x] done #work");
+        let tasks = extract_tasks(&note);
+
+        assert_eq!(tasks.len(), 2);
+        assert_eq!(tasks[0].text, "write report");
+        assert_eq!(tasks[0].line, 2);
+        assert!(!tasks[0].done);
+        assert_eq!(tasks[0].due, NaiveDate::from_ymd_opt(2024, 3, 1));
+        assert_eq!(tasks[0].priority.as_deref(), Some("high"));
+        assert!(tasks[1].done);
+        assert_eq!(tasks[1].tags, vec!["work"]);
+
+        let toggled = toggle_task_in_content(&note, &tasks[0].id).unwrap();
+        assert!(toggled.contains("- [x] write report @due(2024-03-01) #high"));
+        assert!(toggled.contains("- [ ] not a task"));
+    }
+
+    #[test]
+    fn html_tasks_are_extracted_and_toggled() {
+        let note = note("<h1>Tasks</h1><ul><li><p>[ ] call <strong>Bob</strong> &amp; Ann #p1</p></li>\n<li><p>[x] buy milk</p><ul><li><p>plain item</p></li></ul></li></ul><p>[ ] not in a list</p>");
+        let tasks = extract_tasks(&note);
+
+        assert_eq!(tasks.len(), 2);
+        assert_eq!(tasks[0].text, "call Bob & Ann");
+        assert_eq!(tasks[0].priority.as_deref(), Some("high"));
+        assert!(!tasks[0].done);
+        assert_eq!(tasks[1].text, "buy milk");
+        assert_eq!(tasks[1].line, 2);
+        assert!(tasks[1].done);
+
+        let toggled = toggle_task_in_content(&note, &tasks[1].id).unwrap();
+        assert!(toggled.contains("<li><p>[ ] buy milk</p>"));
+        let after = extract_tasks(&Note { content: toggled, ..note });
+        assert_eq!(after[1].id, tasks[1].id);
+        assert!(!after[1].done);
+    }
+}
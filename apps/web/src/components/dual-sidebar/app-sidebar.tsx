@@ -54,6 +54,7 @@ interface NoteMetadata {
   title: string;
   created_at: string;
   updated_at: string;
+  image_text?: string; // OCR text of embedded images
 }
 
 interface SidebarState {
@@ -383,8 +384,10 @@ export function AppSidebar({ ...props }: React.ComponentProps<typeof Sidebar>) {
     setSearchQuery(value);
   }, []);
 
-  const filteredNotes = notes.filter((note) =>
-    note.title.toLowerCase().includes(searchQuery.toLowerCase())
+  const filteredNotes = notes.filter(
+    (note) =>
+      note.title.toLowerCase().includes(searchQuery.toLowerCase()) ||
+      (note.image_text ?? "").toLowerCase().includes(searchQuery.toLowerCase())
   );
 
   const formatDate = (dateString: string) => {
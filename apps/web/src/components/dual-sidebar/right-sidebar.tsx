@@ -40,11 +40,50 @@ type ClipboardEntry = {
   source_app?: string | null;
   window_title?: string | null;
   source_url?: string | null;
-  capture_type?: string; // "text" | "code" | "link" | "image"
+  capture_type?: string; // "text" | "code" | "link" | "image" | "files"
   tags?: string[];
   content_hash?: string | null;
+  formats?: {
+    html?: string;
+    rtf?: string;
+    files?: string[];
+    svg?: string;
+  };
+  image?: {
+    path: string;
+    thumbnail: string;
+    width: number;
+    height: number;
+  } | null;
+  sensitive_reason?: string | null;
+  expires_at?: string | null;
 };
 
+function ClipboardThumbnail({ entry }: { entry: ClipboardEntry }) {
+  const [src, setSrc] = useState<string | null>(null);
+
+  useEffect(() => {
+    let cancelled = false;
+    invoke<string>("get_clipboard_image", { id: entry.id, thumbnail: true })
+      .then((url) => {
+        if (!cancelled) setSrc(url);
+      })
+      .catch(() => {});
+    return () => {
+      cancelled = true;
+    };
+  }, [entry.id, entry.image?.thumbnail]);
+
+  if (!src) return <div className="h-12 w-16 rounded bg-muted" />;
+  return (
+    <img
+      src={src}
+      alt={`Image ${entry.image?.width}×${entry.image?.height}`}
+      className="max-h-16 max-w-[7rem] rounded object-contain"
+    />
+  );
+}
+
 export function RightSidebar({ ...props }: ComponentProps<typeof Sidebar>) {
   const [history, setHistory] = useState<ClipboardEntry[]>([]);
   const [limit, setLimit] = useState<number>(50);
@@ -138,13 +177,17 @@ export function RightSidebar({ ...props }: ComponentProps<typeof Sidebar>) {
     }
   };
 
-  const handleRestore = async (text: string) => {
+  const handleRestore = async (entry: ClipboardEntry) => {
+    const text = entry.text;
     try {
-      await invoke("restore_clipboard_entry", { text });
-      try {
-        // Also write via Web Clipboard API to improve reliability across targets
-        await navigator.clipboard.writeText(text);
-      } catch {}
+      await invoke("restore_clipboard_entry", { text, id: entry.id });
+      // Writing plain text again would replace the rich formats just restored
+      if (!entry.formats && !entry.image) {
+        try {
+          // Also write via Web Clipboard API to improve reliability across targets
+          await navigator.clipboard.writeText(text);
+        } catch {}
+      }
       toast.success("Copied to clipboard");
     } catch (e) {
       toast.error("Failed to restore to clipboard");
@@ -225,11 +268,14 @@ export function RightSidebar({ ...props }: ComponentProps<typeof Sidebar>) {
                           }}
                           title="Drag into editor to insert"
                         >
-                          <div className="text-xs whitespace-nowrap overflow-hidden min-h-5  text-ellipsis max-w-[7rem]">
-                            {e.text.replace(/\s+/g, " ").length > 100
-                              ? e.text.replace(/\s+/g, " ").slice(0, 100) + "…"
-                              : e.text.replace(/\s+/g, " ")}
-                          </div>
+                          {e.image ? <ClipboardThumbnail entry={e} /> : null}
+                          {e.text || !e.image ? (
+                            <div className="text-xs whitespace-nowrap overflow-hidden min-h-5  text-ellipsis max-w-[7rem]">
+                              {e.text.replace(/\s+/g, " ").length > 100
+                                ? e.text.replace(/\s+/g, " ").slice(0, 100) + "…"
+                                : e.text.replace(/\s+/g, " ")}
+                            </div>
+                          ) : null}
                           <div
                             className="flex items-center gap-1 opacity-70 text-[10px] flex-wrap"
                             title={e.window_title || undefined}
@@ -244,6 +290,18 @@ export function RightSidebar({ ...props }: ComponentProps<typeof Sidebar>) {
                                 {e.capture_type}
                               </span>
                             ) : null}
+                            {e.sensitive_reason ? (
+                              <span
+                                className="px-1 rounded bg-destructive/15 text-destructive"
+                                title={
+                                  e.expires_at
+                                    ? `${e.sensitive_reason} · not saved to disk, expires ${new Date(e.expires_at).toLocaleTimeString()}`
+                                    : e.sensitive_reason
+                                }
+                              >
+                                Sensitive
+                              </span>
+                            ) : null}
                             {e.tags && e.tags.length > 0
                               ? e.tags.slice(0, 3).map((t, i) => (
                                   <span
@@ -280,7 +338,7 @@ export function RightSidebar({ ...props }: ComponentProps<typeof Sidebar>) {
                           variant="ghost"
                           size="icon"
                           className="h-6 w-6"
-                          onClick={() => handleRestore(e.text)}
+                          onClick={() => handleRestore(e)}
                           title="Copy to clipboard"
                         >
                           <RotateCcw className="size-3" />
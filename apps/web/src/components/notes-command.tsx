@@ -20,6 +20,7 @@ interface NoteMetadata {
   title: string;
   created_at: string;
   updated_at: string;
+  image_text?: string; // OCR text of embedded images
 }
 
 export default function NotesCommand() {
@@ -61,7 +62,11 @@ export default function NotesCommand() {
   const filteredNotes = useMemo(() => {
     const q = query.trim().toLowerCase();
     if (!q) return notes;
-    return notes.filter((n) => n.title.toLowerCase().includes(q));
+    return notes.filter(
+      (n) =>
+        n.title.toLowerCase().includes(q) ||
+        (n.image_text ?? "").toLowerCase().includes(q)
+    );
   }, [notes, query]);
 
   const createNote = async () => {
@@ -101,6 +106,7 @@ export default function NotesCommand() {
             <CommandItem
               key={n.id}
               value={n.title}
+              keywords={n.image_text ? [n.image_text] : undefined}
               onSelect={() => {
                 setOpen(false);
                 navigate({ to: "/note/$noteId", params: { noteId: n.id } });
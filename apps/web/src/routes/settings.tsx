@@ -22,6 +22,22 @@ export const Route = createFileRoute("/settings")({
   component: SettingsPage,
 });
 
+type OcrStatus = {
+  available: boolean;
+  binary: string;
+  version: string | null;
+  error: string | null;
+};
+
+type RetentionPolicy = {
+  max_age_hours: number | null;
+  ttl_hours: Record<string, number>;
+  max_bytes: number | null;
+};
+
+const daysToHours = (value: string) =>
+  value === "" ? null : Math.max(1, Math.round(Number(value) * 24));
+
 function SettingsPage() {
   const navigate = useNavigate();
   const [appVersion, setAppVersion] = useState<string>("");
@@ -31,6 +47,14 @@ function SettingsPage() {
   const [minLen, setMinLen] = useState<number>(8);
   const [dedupMinutes, setDedupMinutes] = useState<number>(3);
   const [rulesJson, setRulesJson] = useState<string>("[]");
+  const [secretAction, setSecretAction] = useState<string>("memory_only");
+  const [secretTtl, setSecretTtl] = useState<number>(5);
+  const [retention, setRetention] = useState<RetentionPolicy>({
+    max_age_hours: null,
+    ttl_hours: {},
+    max_bytes: null,
+  });
+  const [ocrStatus, setOcrStatus] = useState<OcrStatus | null>(null);
   const [layoutPref, setLayoutPref] = useState<string>(() =>
     typeof window !== "undefined"
       ? localStorage.getItem("notes_layout_pref") || "vertical"
@@ -58,11 +82,30 @@ function SettingsPage() {
     invoke<number>("get_dedup_window_minutes")
       .then(setDedupMinutes)
       .catch(() => {});
+    invoke<string>("get_secret_action")
+      .then(setSecretAction)
+      .catch(() => {});
+    invoke<number>("get_secret_ttl_minutes")
+      .then(setSecretTtl)
+      .catch(() => {});
+    invoke<RetentionPolicy>("get_retention_policy")
+      .then(setRetention)
+      .catch(() => {});
     invoke<any[]>("get_rules")
       .then((r) => setRulesJson(JSON.stringify(r ?? [], null, 2)))
       .catch(() => {});
+    invoke<OcrStatus>("get_ocr_status")
+      .then(setOcrStatus)
+      .catch(() => {});
   }, []);
 
+  const saveRetention = async (policy: RetentionPolicy) => {
+    try {
+      await invoke("set_retention_policy", { policy });
+      toast.success("Updated");
+    } catch {}
+  };
+
   const handleCheckUpdates = async () => {
     setIsCheckingUpdate(true);
     try {
@@ -223,24 +266,186 @@ function SettingsPage() {
                 </p>
               </div>
             </div>
+            <div className="p-4 rounded-lg border border-border bg-card/50">
+              <h3 className="font-medium">Sensitive content</h3>
+              <p className="text-sm text-muted-foreground mb-3">
+                API keys, tokens, private keys, passwords and card numbers.
+              </p>
+              <div className="flex flex-wrap items-center gap-2">
+                {[
+                  { value: "skip", label: "Don't save" },
+                  { value: "mask", label: "Mask" },
+                  { value: "memory_only", label: "Memory only" },
+                ].map((opt) => (
+                  <Button
+                    key={opt.value}
+                    size="sm"
+                    variant={secretAction === opt.value ? "default" : "outline"}
+                    onClick={async () => {
+                      setSecretAction(opt.value);
+                      try {
+                        await invoke("set_secret_action", { action: opt.value });
+                      } catch {}
+                    }}
+                  >
+                    {opt.label}
+                  </Button>
+                ))}
+                {secretAction === "memory_only" ? (
+                  <label className="flex items-center gap-2 text-sm ml-2">
+                    Expire after
+                    <input
+                      type="number"
+                      min={1}
+                      value={secretTtl}
+                      onChange={(e) => setSecretTtl(Number(e.target.value))}
+                      onBlur={async () => {
+                        try {
+                          await invoke("set_secret_ttl_minutes", {
+                            minutes: secretTtl,
+                          });
+                          toast.success("Updated");
+                        } catch {}
+                      }}
+                      className="w-20 h-9 rounded-md bg-background border border-border px-2 text-sm"
+                    />
+                    min
+                  </label>
+                ) : null}
+              </div>
+            </div>
+            <div className="p-4 rounded-lg border border-border bg-card/50">
+              <h3 className="font-medium">Retention</h3>
+              <p className="text-sm text-muted-foreground mb-3">
+                Older entries are removed automatically. Pinned entries are
+                always kept; leave a field empty for no limit.
+              </p>
+              <div className="grid grid-cols-2 sm:grid-cols-3 gap-3">
+                <label className="flex flex-col gap-1 text-sm">
+                  Keep everything for (days)
+                  <input
+                    type="number"
+                    min={1}
+                    value={
+                      retention.max_age_hours == null
+                        ? ""
+                        : retention.max_age_hours / 24
+                    }
+                    onChange={(e) =>
+                      setRetention({
+                        ...retention,
+                        max_age_hours: daysToHours(e.target.value),
+                      })
+                    }
+                    onBlur={() => saveRetention(retention)}
+                    className="h-9 rounded-md bg-background border border-border px-2 text-sm"
+                  />
+                </label>
+                <label className="flex flex-col gap-1 text-sm">
+                  Total size (MB)
+                  <input
+                    type="number"
+                    min={1}
+                    value={
+                      retention.max_bytes == null
+                        ? ""
+                        : retention.max_bytes / (1024 * 1024)
+                    }
+                    onChange={(e) =>
+                      setRetention({
+                        ...retention,
+                        max_bytes:
+                          e.target.value === ""
+                            ? null
+                            : Math.max(1, Math.round(Number(e.target.value) * 1024 * 1024)),
+                      })
+                    }
+                    onBlur={() => saveRetention(retention)}
+                    className="h-9 rounded-md bg-background border border-border px-2 text-sm"
+                  />
+                </label>
+                {["text", "link", "code", "files", "image"].map((type) => (
+                  <label key={type} className="flex flex-col gap-1 text-sm">
+                    <span className="capitalize">{type} (days)</span>
+                    <input
+                      type="number"
+                      min={1}
+                      value={
+                        retention.ttl_hours[type] == null
+                          ? ""
+                          : retention.ttl_hours[type] / 24
+                      }
+                      onChange={(e) => {
+                        const ttl_hours = { ...retention.ttl_hours };
+                        const hours = daysToHours(e.target.value);
+                        if (hours == null) delete ttl_hours[type];
+                        else ttl_hours[type] = hours;
+                        setRetention({ ...retention, ttl_hours });
+                      }}
+                      onBlur={() => saveRetention(retention)}
+                      className="h-9 rounded-md bg-background border border-border px-2 text-sm"
+                    />
+                  </label>
+                ))}
+              </div>
+            </div>
+            <div className="flex items-center justify-between p-4 rounded-lg border border-border bg-card/50">
+              <div className="space-y-1">
+                <h3 className="font-medium">Text recognition (OCR)</h3>
+                <p className="text-sm text-muted-foreground">
+                  {ocrStatus === null
+                    ? "Checking for Tesseract..."
+                    : ocrStatus.available
+                      ? `Copied images and pasted screenshots become searchable (${ocrStatus.version ?? ocrStatus.binary}).`
+                      : `Tesseract was not found (${ocrStatus.binary}). Install it, or set TESSERACT_PATH, to search text in images.`}
+                </p>
+              </div>
+              <Button
+                size="sm"
+                variant="outline"
+                onClick={async () => {
+                  try {
+                    setOcrStatus(await invoke<OcrStatus>("get_ocr_status"));
+                  } catch {}
+                }}
+              >
+                Check again
+              </Button>
+            </div>
             <div className="p-4 rounded-lg border border-border bg-card/50">
               <div className="flex items-center justify-between mb-2">
                 <div>
                   <h3 className="font-medium">Rules</h3>
                   <p className="text-sm text-muted-foreground">
-                    Regex-based rules: field=text|url|app|type;
-                    action=tag|ignore|merge.
+                    Conditions match a regex on text|url|app|window|type
+                    and combine with all/any. Actions: tag, ignore, merge,
+                    set_type, transform, pin, route_to_note. Higher priority
+                    runs first; stop_processing skips the rest.
                   </p>
                 </div>
                 <Button
                   size="sm"
                   onClick={async () => {
+                    let parsed;
+                    try {
+                      parsed = JSON.parse(rulesJson);
+                    } catch {
+                      toast.error("Invalid JSON for rules");
+                      return;
+                    }
                     try {
-                      const parsed = JSON.parse(rulesJson);
                       await invoke("set_rules", { rules: parsed });
                       toast.success("Rules saved");
                     } catch (e: any) {
-                      toast.error("Invalid JSON for rules");
+                      const errors: { index: number; message: string }[] =
+                        Array.isArray(e) ? e : [];
+                      toast.error(
+                        errors.length
+                          ? errors
+                              .map((r) => `Rule ${r.index + 1}: ${r.message}`)
+                              .join("\n")
+                          : String(e),
+                      );
                     }
                   }}
                 >
@@ -253,8 +458,10 @@ function SettingsPage() {
                 spellCheck={false}
                 className="w-full min-h-40 rounded-md bg-background border border-border p-2 text-sm font-mono"
                 placeholder='[
-  {"pattern":"github\\.com","field":"url","action":"tag","tag":"github"},
-  {"pattern":"password","field":"text","action":"ignore"}
+  {"when":{"match":{"field":"url","pattern":"github\\.com"}},"actions":[{"type":"tag","tag":"github"}]},
+  {"priority":10,"when":{"match":{"field":"text","pattern":"password"}},"actions":[{"type":"ignore"}]},
+  {"when":{"all":[{"match":{"field":"app","pattern":"Terminal"}},{"match":{"field":"type","pattern":"^text$"}}]},
+   "actions":[{"type":"merge","window_seconds":30,"separator":"\\n"}],"stop_processing":true}
 ]'
               />
             </div>